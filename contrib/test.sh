@@ -1,6 +1,6 @@
 #!/bin/sh -ex
 
-FEATURES="serde serde-std std core2"
+FEATURES="serde serde-std std core2 rand_core subtle"
 
 if [ "$DO_ALLOC_TESTS" = true ]; then
 	FEATURES="$FEATURES alloc"
@@ -49,6 +49,12 @@ if [ "$DO_SCHEMARS_TESTS" = true ]; then
     (cd extended_tests/schemars && cargo test)
 fi
 
+# The `zeroize` dependency has a higher MSRV than this crate, see README.md.
+if [ "$DO_ZEROIZE_TESTS" = true ]; then
+    cargo test --all --no-default-features --features="zeroize"
+    cargo test --all --no-default-features --features="std,zeroize"
+fi
+
 # Build the docs if told to (this only works with the nightly toolchain)
 if [ "$DO_DOCS" = true ]; then
     RUSTDOCFLAGS="--cfg docsrs" cargo doc --all --features="$FEATURES"
@@ -3,6 +3,62 @@ fn main() {}
 mod tests {
     use bitcoin_hashes::*;
 
+    #[test]
+    fn blake2b() {
+        static HASH_BYTES: [u8; 64] = [
+            0x0b, 0x85, 0x19, 0xeb, 0x86, 0x57, 0x50, 0x5a, 0xaf, 0x0d, 0x11, 0x4f, 0x80, 0x5b,
+            0x45, 0x10, 0xfa, 0xe4, 0x1c, 0xeb, 0xc2, 0x73, 0xca, 0x89, 0xfe, 0x4d, 0xc3, 0xf7,
+            0xac, 0x67, 0x62, 0xa7, 0x9a, 0xbc, 0xc5, 0x2c, 0x94, 0x64, 0xb9, 0xe6, 0x9b, 0xe9,
+            0xde, 0x7a, 0xa3, 0xca, 0x71, 0xb7, 0x80, 0xa0, 0x92, 0xe0, 0x97, 0xa5, 0x4a, 0xfb,
+            0x31, 0x8c, 0x42, 0x6a, 0xfb, 0xf8, 0x01, 0xcc,
+        ];
+
+        let hash = blake2b::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(blake2b::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn blake2s() {
+        static HASH_BYTES: [u8; 32] = [
+            0x24, 0xb4, 0xcf, 0x6e, 0x7e, 0xfa, 0xbf, 0xcf, 0xed, 0x1d, 0xe9, 0xb2, 0x1d, 0x2d,
+            0x64, 0x72, 0x93, 0x18, 0xb6, 0x53, 0x85, 0x9a, 0x7e, 0x60, 0x76, 0xe1, 0xf5, 0xc3,
+            0xd5, 0xe7, 0x56, 0x3d,
+        ];
+
+        let hash = blake2s::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(blake2s::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn blake3() {
+        static HASH_BYTES: [u8; 32] = [
+            0x51, 0x9d, 0xec, 0x41, 0xa9, 0xed, 0x05, 0xce, 0x82, 0x51, 0x12, 0xa2, 0xdd, 0x69,
+            0x69, 0x72, 0x2a, 0xf8, 0x1c, 0x09, 0xb2, 0x39, 0x27, 0x2f, 0xf8, 0xb8, 0x76, 0xcf,
+            0xb3, 0x47, 0x4b, 0x81,
+        ];
+
+        let hash = blake3::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(blake3::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
     #[test]
     fn hash160() {
         static HASH_BYTES: [u8; 20] = [
@@ -40,6 +96,40 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn keccak256() {
+        static HASH_BYTES: [u8; 32] = [
+            0x48, 0x13, 0x0f, 0xfe, 0x2a, 0xc0, 0x2e, 0xdc, 0x08, 0x03, 0x56, 0xbe, 0xbf, 0xea,
+            0x33, 0x9b, 0xdd, 0x3c, 0xed, 0xb8, 0x6f, 0xe6, 0xe0, 0x06, 0xf4, 0xe1, 0x32, 0xe0,
+            0xce, 0x36, 0x0f, 0x64,
+        ];
+
+        let hash = keccak256::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(keccak256::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn murmur3() {
+        static HASH_BYTES: [u8; 4] = [
+            0x5c, 0x24, 0x4b, 0xa2,
+        ];
+
+        let hash = murmur3::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(murmur3::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
     #[test]
     fn ripemd160() {
         static HASH_BYTES: [u8; 20] = [
@@ -74,6 +164,40 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn sha1dc() {
+        static HASH_BYTES: [u8; 20] = [
+            0xfa, 0x37, 0xaf, 0x99, 0x35, 0xc1, 0x8e, 0xf5, 0x6b, 0x50, 0xd2, 0xf4, 0xac, 0x0d,
+            0xce, 0x56, 0xe6, 0x89, 0x24, 0x73,
+        ];
+
+        let hash = sha1dc::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(sha1dc::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn sha224() {
+        static HASH_BYTES: [u8; 28] = [
+            0xc5, 0xcd, 0x13, 0x63, 0xdb, 0x4c, 0x15, 0xdb, 0xd6, 0xd1, 0xa6, 0xfb, 0xe1, 0x48,
+            0x72, 0x0b, 0x7b, 0x84, 0xf4, 0xe5, 0x0b, 0x4a, 0x99, 0x46, 0x94, 0x22, 0x75, 0x46,
+        ];
+
+        let hash = sha224::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(sha224::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
     #[test]
     fn sha256d() {
         static HASH_BYTES: [u8; 32] = [
@@ -177,6 +301,115 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn sha512_256() {
+        static HASH_BYTES: [u8; 32] = [
+            0x57, 0xc7, 0x64, 0x7b, 0x36, 0x9e, 0x96, 0xd3, 0x65, 0x10, 0xf0, 0xc3, 0xe9, 0x36,
+            0x91, 0xd9, 0x79, 0x12, 0xad, 0x7b, 0xfd, 0x5b, 0xb6, 0xad, 0xe7, 0x36, 0xf8, 0x8f,
+            0xcb, 0xc8, 0x12, 0xe4,
+        ];
+
+        let hash = sha512_256::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(sha512_256::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn sha384() {
+        static HASH_BYTES: [u8; 48] = [
+            0x91, 0x43, 0xb3, 0x36, 0xec, 0x4f, 0x54, 0xa8, 0x9a, 0x95, 0x2f, 0x46, 0xcc, 0xd0,
+            0x8d, 0x7c, 0xe8, 0xb0, 0xc0, 0x85, 0xf2, 0x39, 0xca, 0x21, 0x25, 0x05, 0x0e, 0xe6,
+            0xe4, 0x37, 0x67, 0xcf, 0x5a, 0x95, 0x3c, 0xcd, 0x1e, 0x44, 0x53, 0xc1, 0x2b, 0x69,
+            0x4b, 0x80, 0xb5, 0x97, 0x21, 0xc2,
+        ];
+
+        let hash = sha384::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(sha384::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn sha3_256() {
+        static HASH_BYTES: [u8; 32] = [
+            0xcc, 0xab, 0x16, 0x75, 0xa5, 0x0e, 0xee, 0x3f, 0x50, 0x30, 0x46, 0x8c, 0xc3, 0xe9,
+            0x06, 0xf9, 0xb8, 0x19, 0x86, 0x1b, 0x62, 0x0e, 0x71, 0x28, 0x55, 0xfc, 0x88, 0x78,
+            0xc3, 0xa6, 0x35, 0xae,
+        ];
+
+        let hash = sha3_256::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(sha3_256::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn shake128() {
+        static HASH_BYTES: [u8; 32] = [
+            0xd8, 0x0b, 0x99, 0x24, 0x24, 0x94, 0x6b, 0x70, 0x32, 0x1d, 0x30, 0xa9, 0x96, 0x69,
+            0xa1, 0x7f, 0xf0, 0x01, 0xc2, 0xcc, 0xa0, 0xf6, 0x15, 0xf2, 0xc5, 0xfa, 0xe0, 0x7c,
+            0x53, 0xf6, 0x3c, 0x2a,
+        ];
+
+        let hash = shake128::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(shake128::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn shake256() {
+        static HASH_BYTES: [u8; 64] = [
+            0x61, 0x8f, 0xd0, 0xc8, 0xdf, 0x28, 0x39, 0x8c, 0x94, 0x59, 0x4c, 0xe7, 0x76, 0x22,
+            0x37, 0xfe, 0x65, 0x4f, 0x97, 0x13, 0x58, 0xe2, 0x28, 0x1c, 0x55, 0xcb, 0x48, 0xb8,
+            0x81, 0x64, 0xdd, 0xe8, 0xee, 0x98, 0x2f, 0x16, 0x1f, 0x8c, 0xc2, 0x56, 0xf9, 0x36,
+            0xa9, 0x27, 0xed, 0x4a, 0xc0, 0xfc, 0x60, 0x8e, 0x0c, 0x2f, 0x6b, 0xb5, 0xe8, 0x65,
+            0x52, 0x14, 0xbc, 0x95, 0x66, 0x66, 0xfc, 0xf4,
+        ];
+
+        let hash = shake256::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(shake256::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
+    #[test]
+    fn siphash13() {
+        static HASH_BYTES: [u8; 8] = [
+            0x36, 0x37, 0xe5, 0x80, 0x35, 0xbf, 0xf1, 0x40,
+        ];
+
+        let hash = siphash13::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(siphash13::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
+
     #[test]
     fn siphash24() {
         static HASH_BYTES: [u8; 8] = [0x8b, 0x41, 0xe1, 0xb7, 0x8a, 0xd1, 0x15, 0x21];
@@ -190,4 +423,21 @@ mod tests {
             .validate(&js)
             .is_ok());
     }
+
+    #[test]
+    fn siphash24_128() {
+        static HASH_BYTES: [u8; 16] = [
+            0xbe, 0xb7, 0xc5, 0x92, 0x4e, 0xb1, 0x36, 0x61, 0x08, 0xb6, 0xa9, 0xa6, 0xd4, 0x9a,
+            0x4b, 0xda,
+        ];
+
+        let hash = siphash24_128::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        let js = serde_json::from_str(&serde_json::to_string(&hash).unwrap()).unwrap();
+        let s = schemars::schema_for!(siphash24_128::Hash);
+        let schema = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert!(jsonschema_valid::Config::from_schema(&schema, None)
+            .unwrap()
+            .validate(&js)
+            .is_ok());
+    }
 }
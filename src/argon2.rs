@@ -0,0 +1,604 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Argon2 password hashing.
+//!
+//! Implementation of Argon2d, Argon2i and Argon2id (version 0x13) as specified in RFC 9106,
+//! built on [`blake2b`]. Argon2id is the recommended variant for password hashing and key
+//! derivation.
+//!
+//! Lanes are computed sequentially. The memory Argon2 works in is supplied by the caller as a
+//! slice of at least [`Params::block_count`] [`Block`]s, so no allocation is required.
+//!
+
+use core::fmt;
+
+use crate::blake2b;
+use crate::{Hash as _, HashEngine as _};
+
+/// Argon2 version implemented by this module.
+pub const VERSION: u32 = 0x13;
+
+/// Size of a memory block, in bytes.
+pub const BLOCK_SIZE: usize = 1024;
+
+/// Number of 64-bit words in a memory block.
+const BLOCK_WORDS: usize = BLOCK_SIZE / 8;
+
+/// Number of slices each lane is divided into.
+const SYNC_POINTS: u32 = 4;
+
+/// Minimum salt length, in bytes.
+pub const MIN_SALT_LENGTH: usize = 8;
+
+/// Minimum output length, in bytes.
+pub const MIN_OUTPUT_LENGTH: usize = 4;
+
+/// Maximum degree of parallelism.
+pub const MAX_PARALLELISM: u32 = 0xff_ffff;
+
+/// Argon2 error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The memory cost is less than 8 KiB per lane.
+    InvalidMemoryCost(u32),
+    /// The number of iterations is zero.
+    InvalidTimeCost(u32),
+    /// The degree of parallelism is zero or greater than [`MAX_PARALLELISM`].
+    InvalidParallelism(u32),
+    /// The salt is shorter than [`MIN_SALT_LENGTH`].
+    SaltTooShort(usize),
+    /// The output is shorter than [`MIN_OUTPUT_LENGTH`] or longer than `2^32 - 1` bytes.
+    InvalidOutputLength(usize),
+    /// An input (password, salt, secret or associated data) is longer than `2^32 - 1` bytes.
+    InputTooLong(usize),
+    /// The memory slice has too few blocks for the parameters (required, got).
+    MemoryTooSmall(usize, usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidMemoryCost(m) => write!(f, "invalid Argon2 memory cost {} KiB", m),
+            Error::InvalidTimeCost(t) => write!(f, "invalid Argon2 time cost {}", t),
+            Error::InvalidParallelism(p) => write!(f, "invalid Argon2 parallelism {}", p),
+            Error::SaltTooShort(len) => write!(f, "Argon2 salt too short: {} bytes (minimum {})", len, MIN_SALT_LENGTH),
+            Error::InvalidOutputLength(len) => write!(f, "invalid Argon2 output length {}", len),
+            Error::InputTooLong(len) => write!(f, "Argon2 input too long: {} bytes", len),
+            Error::MemoryTooSmall(req, got) => write!(f, "Argon2 memory too small: {} blocks (expected {})", got, req),
+        }
+    }
+}
+
+/// Argon2 variant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Variant {
+    /// Data-dependent memory access, faster but vulnerable to side-channel attacks.
+    Argon2d = 0,
+    /// Data-independent memory access.
+    Argon2i = 1,
+    /// Data-independent memory access for the first half of the first pass, data-dependent
+    /// afterwards.
+    Argon2id = 2,
+}
+
+/// Validated Argon2 cost parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Params {
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+impl Params {
+    /// Validates the memory cost (in KiB), the time cost (number of passes over the memory) and
+    /// the degree of parallelism (number of lanes).
+    pub fn new(memory_cost: u32, time_cost: u32, parallelism: u32) -> Result<Params, Error> {
+        if parallelism == 0 || parallelism > MAX_PARALLELISM {
+            return Err(Error::InvalidParallelism(parallelism));
+        }
+        if memory_cost < 2 * SYNC_POINTS * parallelism {
+            return Err(Error::InvalidMemoryCost(memory_cost));
+        }
+        if time_cost == 0 {
+            return Err(Error::InvalidTimeCost(time_cost));
+        }
+        Ok(Params { memory_cost, time_cost, parallelism })
+    }
+
+    /// Returns the memory cost, in KiB.
+    pub fn memory_cost(&self) -> u32 {
+        self.memory_cost
+    }
+
+    /// Returns the time cost.
+    pub fn time_cost(&self) -> u32 {
+        self.time_cost
+    }
+
+    /// Returns the degree of parallelism.
+    pub fn parallelism(&self) -> u32 {
+        self.parallelism
+    }
+
+    /// Returns the number of memory blocks used, which is the memory cost rounded down to a
+    /// multiple of `4 * parallelism`.
+    pub fn block_count(&self) -> usize {
+        let granularity = SYNC_POINTS * self.parallelism;
+        (self.memory_cost / granularity * granularity) as usize
+    }
+
+    fn lane_length(&self) -> usize {
+        self.block_count() / self.parallelism as usize
+    }
+
+    fn segment_length(&self) -> usize {
+        self.lane_length() / SYNC_POINTS as usize
+    }
+}
+
+/// A 1 KiB Argon2 memory block.
+#[derive(Clone, Copy)]
+pub struct Block([u64; BLOCK_WORDS]);
+
+impl Default for Block {
+    fn default() -> Block {
+        Block([0; BLOCK_WORDS])
+    }
+}
+
+impl fmt::Debug for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Block { .. }")
+    }
+}
+
+impl Block {
+    fn xor_assign(&mut self, other: &Block) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a ^= b;
+        }
+    }
+
+    fn load(&mut self, bytes: &[u8]) {
+        for (word, chunk) in self.0.iter_mut().zip(bytes.chunks(8)) {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(chunk);
+            *word = u64::from_le_bytes(buf);
+        }
+    }
+
+    fn store(&self, bytes: &mut [u8]) {
+        for (word, chunk) in self.0.iter().zip(bytes.chunks_mut(8)) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+/// Argon2 instance, combining a variant with parameters and optional keying inputs.
+#[derive(Clone)]
+pub struct Argon2<'a> {
+    variant: Variant,
+    params: Params,
+    secret: &'a [u8],
+    associated_data: &'a [u8],
+}
+
+impl<'a> fmt::Debug for Argon2<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Do not print the secret or the associated data.
+        f.debug_struct("Argon2").field("variant", &self.variant).field("params", &self.params).finish()
+    }
+}
+
+impl<'a> Argon2<'a> {
+    /// Creates an Argon2 instance without secret or associated data.
+    pub fn new(variant: Variant, params: Params) -> Argon2<'a> {
+        Argon2 { variant, params, secret: &[], associated_data: &[] }
+    }
+
+    /// Sets the secret value (the "pepper").
+    pub fn secret(&mut self, secret: &'a [u8]) -> &mut Argon2<'a> {
+        self.secret = secret;
+        self
+    }
+
+    /// Sets the associated data.
+    pub fn associated_data(&mut self, associated_data: &'a [u8]) -> &mut Argon2<'a> {
+        self.associated_data = associated_data;
+        self
+    }
+
+    /// Hashes `password` with `salt`, filling `out` with the tag.
+    ///
+    /// `memory` must hold at least [`Params::block_count`] blocks, its contents are overwritten.
+    pub fn hash(&self, password: &[u8], salt: &[u8], memory: &mut [Block], out: &mut [u8]) -> Result<(), Error> {
+        if salt.len() < MIN_SALT_LENGTH {
+            return Err(Error::SaltTooShort(salt.len()));
+        }
+        if out.len() < MIN_OUTPUT_LENGTH || out.len() as u64 > u64::from(u32::max_value()) {
+            return Err(Error::InvalidOutputLength(out.len()));
+        }
+        for input in [password, salt, self.secret, self.associated_data].iter() {
+            if input.len() as u64 > u64::from(u32::max_value()) {
+                return Err(Error::InputTooLong(input.len()));
+            }
+        }
+        let block_count = self.params.block_count();
+        if memory.len() < block_count {
+            return Err(Error::MemoryTooSmall(block_count, memory.len()));
+        }
+        let memory = &mut memory[..block_count];
+
+        let h0 = self.initial_hash(password, salt, out.len());
+        self.fill_first_blocks(&h0, memory);
+        for pass in 0..self.params.time_cost {
+            for slice in 0..SYNC_POINTS {
+                for lane in 0..self.params.parallelism {
+                    self.fill_segment(memory, pass, slice, lane);
+                }
+            }
+        }
+
+        let lane_length = self.params.lane_length();
+        let mut last = memory[lane_length - 1];
+        for lane in 1..self.params.parallelism as usize {
+            last.xor_assign(&memory[lane * lane_length + lane_length - 1]);
+        }
+        let mut bytes = [0u8; BLOCK_SIZE];
+        last.store(&mut bytes);
+        variable_hash(&[&bytes], out);
+        Ok(())
+    }
+
+    /// Computes the 64-byte pre-hashing digest H0.
+    fn initial_hash(&self, password: &[u8], salt: &[u8], out_len: usize) -> [u8; 64] {
+        let mut engine = blake2b::HashEngine::default();
+        for value in [
+            self.params.parallelism,
+            out_len as u32,
+            self.params.memory_cost,
+            self.params.time_cost,
+            VERSION,
+            self.variant as u32,
+        ].iter() {
+            engine.input(&value.to_le_bytes());
+        }
+        for input in [password, salt, self.secret, self.associated_data].iter() {
+            engine.input(&(input.len() as u32).to_le_bytes());
+            engine.input(input);
+        }
+        blake2b::Hash::from_engine(engine).into_inner()
+    }
+
+    /// Computes the first two blocks of each lane from H0.
+    fn fill_first_blocks(&self, h0: &[u8; 64], memory: &mut [Block]) {
+        let lane_length = self.params.lane_length();
+        let mut bytes = [0u8; BLOCK_SIZE];
+        for lane in 0..self.params.parallelism {
+            for column in 0..2u32 {
+                variable_hash(&[h0, &column.to_le_bytes(), &lane.to_le_bytes()], &mut bytes);
+                memory[lane as usize * lane_length + column as usize].load(&bytes);
+            }
+        }
+    }
+
+    fn fill_segment(&self, memory: &mut [Block], pass: u32, slice: u32, lane: u32) {
+        let lane_length = self.params.lane_length();
+        let segment_length = self.params.segment_length();
+        let data_independent = match self.variant {
+            Variant::Argon2d => false,
+            Variant::Argon2i => true,
+            Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+        };
+
+        let zero = Block::default();
+        let mut address = Block::default();
+        let mut input = Block::default();
+        if data_independent {
+            input.0[0] = u64::from(pass);
+            input.0[1] = u64::from(lane);
+            input.0[2] = u64::from(slice);
+            input.0[3] = self.params.block_count() as u64;
+            input.0[4] = u64::from(self.params.time_cost);
+            input.0[5] = self.variant as u64;
+        }
+
+        let mut start = 0;
+        if pass == 0 && slice == 0 {
+            // The first two blocks of each lane are already filled.
+            start = 2;
+            if data_independent {
+                next_addresses(&mut address, &mut input, &zero);
+            }
+        }
+
+        let lane_start = lane as usize * lane_length;
+        for index in start..segment_length {
+            let column = slice as usize * segment_length + index;
+            let prev = if column == 0 { lane_start + lane_length - 1 } else { lane_start + column - 1 };
+
+            let pseudo_rand = if data_independent {
+                if index % BLOCK_WORDS == 0 {
+                    next_addresses(&mut address, &mut input, &zero);
+                }
+                address.0[index % BLOCK_WORDS]
+            } else {
+                memory[prev].0[0]
+            };
+
+            let ref_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                ((pseudo_rand >> 32) % u64::from(self.params.parallelism)) as u32
+            };
+            let ref_column = self.reference_column(pass, slice, index, pseudo_rand as u32, ref_lane == lane);
+            let reference = ref_lane as usize * lane_length + ref_column;
+
+            let mut next = memory[lane_start + column];
+            compress(&memory[prev], &memory[reference], &mut next, pass != 0);
+            memory[lane_start + column] = next;
+        }
+    }
+
+    /// Maps the pseudo-random value `j1` to a column of the reference lane.
+    fn reference_column(&self, pass: u32, slice: u32, index: usize, j1: u32, same_lane: bool) -> usize {
+        let lane_length = self.params.lane_length();
+        let segment_length = self.params.segment_length();
+
+        // Number of blocks that may be referenced, excluding the previous block.
+        let area_size = if pass == 0 {
+            if slice == 0 || same_lane {
+                slice as usize * segment_length + index - 1
+            } else if index == 0 {
+                slice as usize * segment_length - 1
+            } else {
+                slice as usize * segment_length
+            }
+        } else if same_lane {
+            lane_length - segment_length + index - 1
+        } else if index == 0 {
+            lane_length - segment_length - 1
+        } else {
+            lane_length - segment_length
+        } as u64;
+
+        let x = (u64::from(j1) * u64::from(j1)) >> 32;
+        let relative = area_size - 1 - ((area_size * x) >> 32);
+
+        let start = if pass == 0 || slice == SYNC_POINTS - 1 {
+            0
+        } else {
+            (slice as usize + 1) * segment_length
+        };
+        (start + relative as usize) % lane_length
+    }
+}
+
+/// Generates the next block of pseudo-random addresses for data-independent addressing.
+fn next_addresses(address: &mut Block, input: &mut Block, zero: &Block) {
+    input.0[6] += 1;
+    compress(zero, input, address, false);
+    let tmp = *address;
+    compress(zero, &tmp, address, false);
+}
+
+/// The compression function G, writing `G(x, y)` to `out`, or XORing it into `out` if `with_xor`.
+fn compress(x: &Block, y: &Block, out: &mut Block, with_xor: bool) {
+    let mut r = *x;
+    r.xor_assign(y);
+    let mut tmp = r;
+    if with_xor {
+        tmp.xor_assign(out);
+    }
+
+    // Apply the permutation to the rows, then to the columns, of the 8x8 matrix of 16-byte
+    // registers.
+    for row in 0..8 {
+        let mut idx = [0; 16];
+        for (i, idx) in idx.iter_mut().enumerate() {
+            *idx = row * 16 + i;
+        }
+        permute(&mut r.0, &idx);
+    }
+    for column in 0..8 {
+        let mut idx = [0; 16];
+        for (i, idx) in idx.iter_mut().enumerate() {
+            *idx = (i / 2) * 16 + column * 2 + i % 2;
+        }
+        permute(&mut r.0, &idx);
+    }
+
+    tmp.xor_assign(&r);
+    *out = tmp;
+}
+
+/// The permutation P, a BLAKE2b round with multiplication-hardened mixing.
+fn permute(v: &mut [u64; BLOCK_WORDS], idx: &[usize; 16]) {
+    gb(v, idx[0], idx[4], idx[8], idx[12]);
+    gb(v, idx[1], idx[5], idx[9], idx[13]);
+    gb(v, idx[2], idx[6], idx[10], idx[14]);
+    gb(v, idx[3], idx[7], idx[11], idx[15]);
+    gb(v, idx[0], idx[5], idx[10], idx[15]);
+    gb(v, idx[1], idx[6], idx[11], idx[12]);
+    gb(v, idx[2], idx[7], idx[8], idx[13]);
+    gb(v, idx[3], idx[4], idx[9], idx[14]);
+}
+
+#[inline(always)]
+fn gb(v: &mut [u64; BLOCK_WORDS], a: usize, b: usize, c: usize, d: usize) {
+    #[inline(always)]
+    fn f(x: u64, y: u64) -> u64 {
+        x.wrapping_add(y).wrapping_add(2u64.wrapping_mul(x & 0xffff_ffff).wrapping_mul(y & 0xffff_ffff))
+    }
+
+    v[a] = f(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = f(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = f(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = f(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// The variable-length hash function H', hashing the concatenation of `inputs` into `out`.
+fn variable_hash(inputs: &[&[u8]], out: &mut [u8]) {
+    let mut engine = blake2b::Params::new()
+        .to_var_engine(core::cmp::min(out.len(), blake2b::MAX_HASH_LENGTH));
+    engine.input(&(out.len() as u32).to_le_bytes());
+    for input in inputs {
+        engine.input(input);
+    }
+
+    if out.len() <= blake2b::MAX_HASH_LENGTH {
+        engine.finalize_variable(out).expect("output length matches");
+        return;
+    }
+
+    // Emit the first half of each 64-byte digest, then the whole of the last one.
+    let mut v = [0u8; 64];
+    engine.finalize_variable(&mut v).expect("64 byte output");
+    let mut pos = 0;
+    while out.len() - pos > 64 {
+        out[pos..pos + 32].copy_from_slice(&v[..32]);
+        pos += 32;
+        let mut engine = blake2b::Params::new()
+            .to_var_engine(core::cmp::min(out.len() - pos, blake2b::MAX_HASH_LENGTH));
+        engine.input(&v);
+        let len = engine.hash_length();
+        engine.finalize_variable(&mut v[..len]).expect("output length matches");
+    }
+    let rest = out.len() - pos;
+    out[pos..].copy_from_slice(&v[..rest]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn rfc9106() {
+        use crate::hex::ToHex;
+
+        // Test vectors from section 5 of RFC 9106.
+        let tests = [
+            (Variant::Argon2d, "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb"),
+            (Variant::Argon2i, "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8"),
+            (Variant::Argon2id, "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"),
+        ];
+
+        let params = Params::new(32, 3, 4).unwrap();
+        let mut memory = vec![Block::default(); params.block_count()];
+        for &(variant, tag) in tests.iter() {
+            let mut out = [0u8; 32];
+            Argon2::new(variant, params)
+                .secret(&[3; 8])
+                .associated_data(&[4; 12])
+                .hash(&[1; 32], &[2; 16], &mut memory, &mut out)
+                .unwrap();
+            assert_eq!(out.to_hex(), tag);
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn long_output() {
+        use crate::hex::ToHex;
+
+        // More than 128 blocks per segment and an output longer than 64 bytes, checked against
+        // the reference implementation.
+        let tests = [
+            (Variant::Argon2d, "b00a9750fbb2adae5234d717c65aa1f88736e38f3acd19944283ed133174a020\
+                                a47e921f5678af722f29494a45149e2a5fe5a4d0633dd398fc350eed6c300ed9\
+                                eed8f883b4b9d968adc6118f108c2339a6a811da7d75226111dc66ea5792af9f\
+                                54761c2d"),
+            (Variant::Argon2i, "93bc9a609bba5f69a9b24fa874cfa04f43e7bbb6719612c59ee907413c4eb92d\
+                                221cfbe6439630afdb93e4445b38dba7a8d20b146e7a69641bb02ca0b5fa4b07\
+                                7799cb3cbf71e8c915c3e04e210517d639488957df63123631692aa2ac8a8dec\
+                                677ae1cb"),
+            (Variant::Argon2id, "cb5313b46ce4f829fdad34c5306caa14db87af19415fad09e22beccdc75b430d\
+                                 4bfb67e12e551f77fa2cca333e79ca4f4317e804e44c2356729191258e8d4beb\
+                                 a96b23743784089fb777f415c18abd2557d9b90dbe22e5591dcc959032ca93b7\
+                                 902e46dd"),
+        ];
+
+        let params = Params::new(1024, 2, 1).unwrap();
+        let mut memory = vec![Block::default(); params.block_count()];
+        for &(variant, tag) in tests.iter() {
+            let mut out = [0u8; 100];
+            Argon2::new(variant, params).hash(b"password", b"somesalt", &mut memory, &mut out).unwrap();
+            assert_eq!(out.to_hex(), tag);
+        }
+    }
+
+    #[test]
+    fn params() {
+        assert_eq!(Params::new(32, 3, 0), Err(Error::InvalidParallelism(0)));
+        assert_eq!(Params::new(32, 3, 1 << 24), Err(Error::InvalidParallelism(1 << 24)));
+        assert_eq!(Params::new(31, 3, 4), Err(Error::InvalidMemoryCost(31)));
+        assert_eq!(Params::new(32, 0, 4), Err(Error::InvalidTimeCost(0)));
+
+        // The memory is rounded down to a multiple of 4 blocks per lane.
+        let params = Params::new(256, 1, 3).unwrap();
+        assert_eq!(params.block_count(), 252);
+
+        let mut memory = [Block::default(); 252];
+        let mut out = [0u8; 4];
+        let argon2 = Argon2::new(Variant::Argon2id, params);
+        assert_eq!(argon2.hash(b"password", b"salt", &mut memory, &mut out), Err(Error::SaltTooShort(4)));
+        assert_eq!(
+            argon2.hash(b"password", b"somesalt", &mut memory, &mut out[..3]),
+            Err(Error::InvalidOutputLength(3)),
+        );
+        assert_eq!(
+            argon2.hash(b"password", b"somesalt", &mut memory[..251], &mut out),
+            Err(Error::MemoryTooSmall(252, 251)),
+        );
+        argon2.hash(b"password", b"somesalt", &mut memory, &mut out).unwrap();
+        assert_eq!(out, [0x02, 0xb1, 0xe7, 0xda]);
+    }
+
+    #[test]
+    fn debug_redacted() {
+        let params = Params::new(32, 1, 1).unwrap();
+        let mut argon2 = Argon2::new(Variant::Argon2id, params);
+        argon2.secret(b"pepper").associated_data(b"context");
+        let debug = format!("{:?}", argon2);
+        assert!(debug.contains("Argon2id"));
+        assert!(!debug.contains(&format!("{:?}", &b"pepper"[..])));
+        assert!(!debug.contains(&format!("{:?}", &b"context"[..])));
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use super::{Argon2, Block, Params, Variant};
+
+    #[bench]
+    pub fn argon2id_1m(bh: &mut Bencher) {
+        let params = Params::new(1024, 1, 1).unwrap();
+        let mut memory = vec![Block::default(); params.block_count()];
+        let mut out = [0u8; 32];
+        bh.iter( || {
+            Argon2::new(Variant::Argon2id, params)
+                .hash(b"password", b"somesalt", &mut memory, &mut out)
+                .unwrap();
+        });
+    }
+}
@@ -0,0 +1,666 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BLAKE2b implementation.
+//!
+//! BLAKE2b as specified in RFC 7693, with support for the keyed mode and the salt and
+//! personalization fields of the parameter block (see [`Params`]). The [`Hash`](struct@Hash) type holds the
+//! default 512-bit output; shorter outputs are produced by a [`VarHashEngine`].
+//!
+
+use core::{cmp, hash, str};
+use core::convert::TryInto;
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, HashEngine as _, hex};
+
+crate::internal_macros::hash_trait_impls!(512, false);
+
+pub(crate) const BLOCK_SIZE: usize = 128;
+
+/// Maximum output length, in bytes.
+pub const MAX_HASH_LENGTH: usize = 64;
+/// Maximum key length, in bytes.
+pub const MAX_KEY_LENGTH: usize = 64;
+/// Length of the salt field of the parameter block, in bytes.
+pub const SALT_LENGTH: usize = 16;
+/// Length of the personalization field of the parameter block, in bytes.
+pub const PERSONAL_LENGTH: usize = 16;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 12] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+];
+
+/// BLAKE2b parameter block, used to construct engines with a non-default configuration.
+///
+/// Only the fields relevant to sequential hashing are exposed; the tree hashing fields are
+/// always set to their sequential-mode values.
+#[derive(Clone)]
+pub struct Params {
+    key_length: usize,
+    key: [u8; MAX_KEY_LENGTH],
+    salt: [u8; SALT_LENGTH],
+    personal: [u8; PERSONAL_LENGTH],
+}
+
+impl Params {
+    /// Creates a parameter block for unkeyed hashing with a 64-byte output.
+    pub fn new() -> Params {
+        Params {
+            key_length: 0,
+            key: [0; MAX_KEY_LENGTH],
+            salt: [0; SALT_LENGTH],
+            personal: [0; PERSONAL_LENGTH],
+        }
+    }
+
+    /// Sets the key, switching the engine to keyed (MAC) mode. An empty key means unkeyed
+    /// hashing.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is longer than [`MAX_KEY_LENGTH`].
+    pub fn key(&mut self, key: &[u8]) -> &mut Params {
+        assert!(key.len() <= MAX_KEY_LENGTH, "BLAKE2b key too long");
+        self.key = [0; MAX_KEY_LENGTH];
+        self.key[..key.len()].copy_from_slice(key);
+        self.key_length = key.len();
+        self
+    }
+
+    /// Sets the salt. Salts shorter than [`SALT_LENGTH`] are padded with zeros.
+    ///
+    /// # Panics
+    ///
+    /// If `salt` is longer than [`SALT_LENGTH`].
+    pub fn salt(&mut self, salt: &[u8]) -> &mut Params {
+        assert!(salt.len() <= SALT_LENGTH, "BLAKE2b salt too long");
+        self.salt = [0; SALT_LENGTH];
+        self.salt[..salt.len()].copy_from_slice(salt);
+        self
+    }
+
+    /// Sets the personalization string. Strings shorter than [`PERSONAL_LENGTH`] are padded
+    /// with zeros.
+    ///
+    /// # Panics
+    ///
+    /// If `personal` is longer than [`PERSONAL_LENGTH`].
+    pub fn personal(&mut self, personal: &[u8]) -> &mut Params {
+        assert!(personal.len() <= PERSONAL_LENGTH, "BLAKE2b personalization too long");
+        self.personal = [0; PERSONAL_LENGTH];
+        self.personal[..personal.len()].copy_from_slice(personal);
+        self
+    }
+
+    /// Constructs an engine with the default 64-byte output using these parameters.
+    pub fn to_engine(&self) -> HashEngine {
+        self.engine(MAX_HASH_LENGTH)
+    }
+
+    /// Constructs an engine with a `hash_length`-byte output using these parameters.
+    ///
+    /// # Panics
+    ///
+    /// If `hash_length` is zero or greater than [`MAX_HASH_LENGTH`].
+    pub fn to_var_engine(&self, hash_length: usize) -> VarHashEngine {
+        assert!((1..=MAX_HASH_LENGTH).contains(&hash_length), "invalid BLAKE2b output length");
+        VarHashEngine(self.engine(hash_length))
+    }
+
+    fn engine(&self, hash_length: usize) -> HashEngine {
+        let mut h = IV;
+        h[0] ^= 0x01010000 ^ ((self.key_length as u64) << 8) ^ hash_length as u64;
+        h[4] ^= u64::from_le_bytes(self.salt[..8].try_into().expect("8 byte slice"));
+        h[5] ^= u64::from_le_bytes(self.salt[8..].try_into().expect("8 byte slice"));
+        h[6] ^= u64::from_le_bytes(self.personal[..8].try_into().expect("8 byte slice"));
+        h[7] ^= u64::from_le_bytes(self.personal[8..].try_into().expect("8 byte slice"));
+
+        let mut engine = HashEngine {
+            h,
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+            buf_len: 0,
+            hash_length,
+        };
+        // A keyed hash starts with the key padded to a full block.
+        if self.key_length > 0 {
+            let mut block = [0; BLOCK_SIZE];
+            block[..self.key_length].copy_from_slice(&self.key[..self.key_length]);
+            engine.input(&block);
+        }
+        engine
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params::new()
+    }
+}
+
+/// Engine to compute BLAKE2b hash function.
+#[derive(Clone)]
+pub struct HashEngine {
+    h: [u64; 8],
+    length: usize, // bytes compressed so far
+    buffer: [u8; BLOCK_SIZE],
+    buf_len: usize,
+    hash_length: usize, // only differs from the default in a `VarHashEngine`
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, h, length, buffer, buf_len, hash_length);
+
+impl HashEngine {
+    /// Creates a new keyed BLAKE2b engine with a 64-byte output.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is longer than [`MAX_KEY_LENGTH`].
+    pub fn with_key(key: &[u8]) -> HashEngine {
+        Params::new().key(key).to_engine()
+    }
+
+    /// Compresses the final block and returns the full state, of which the digest is a prefix.
+    fn finalize(mut self) -> [u8; 64] {
+        self.length += self.buf_len;
+        for b in self.buffer[self.buf_len..].iter_mut() {
+            *b = 0;
+        }
+        self.compress(true);
+        self.state_bytes()
+    }
+
+    fn state_bytes(&self) -> [u8; 64] {
+        let mut ret = [0; 64];
+        for (val, ret_bytes) in self.h.iter().zip(ret.chunks_exact_mut(8)) {
+            ret_bytes.copy_from_slice(&val.to_le_bytes());
+        }
+        ret
+    }
+
+    fn compress(&mut self, last: bool) {
+        let mut m = [0u64; 16];
+        for (m_val, buf_bytes) in m.iter_mut().zip(self.buffer.chunks_exact(8)) {
+            *m_val = u64::from_le_bytes(buf_bytes.try_into().expect("8 byte slice"));
+        }
+
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(&self.h);
+        v[8..].copy_from_slice(&IV);
+        // The counter is 128 bits but `usize` never exceeds 64 bits.
+        v[12] ^= self.length as u64;
+        if last {
+            v[14] = !v[14];
+        }
+
+        for s in SIGMA.iter() {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+
+        for i in 0..8 {
+            self.h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+#[inline(always)]
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        Params::new().to_engine()
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; 64];
+
+    fn midstate(&self) -> [u8; 64] {
+        self.state_bytes()
+    }
+
+    const BLOCK_SIZE: usize = 128;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length + self.buf_len
+    }
+
+    fn input(&mut self, mut inp: &[u8]) {
+        while !inp.is_empty() {
+            // The last block is compressed differently, so a full buffer is only compressed
+            // once we know more input follows it.
+            if self.buf_len == BLOCK_SIZE {
+                self.length += BLOCK_SIZE;
+                self.compress(false);
+                self.buf_len = 0;
+            }
+            let write_len = cmp::min(BLOCK_SIZE - self.buf_len, inp.len());
+            self.buffer[self.buf_len..self.buf_len + write_len]
+                .copy_from_slice(&inp[..write_len]);
+            self.buf_len += write_len;
+            inp = &inp[write_len..];
+        }
+    }
+}
+
+/// Engine to compute BLAKE2b hash function with a configurable output length.
+///
+/// Constructed by [`Params::to_var_engine`]; the output is only available through
+/// [`VarHashEngine::finalize_variable`].
+#[derive(Clone)]
+pub struct VarHashEngine(HashEngine);
+
+crate::internal_macros::engine_zeroize_impl!(wrapper VarHashEngine);
+
+impl VarHashEngine {
+    /// Returns the output length this engine was configured with, in bytes.
+    pub fn hash_length(&self) -> usize {
+        self.0.hash_length
+    }
+
+    /// Finalizes the engine into `out`, which must be exactly [`VarHashEngine::hash_length`]
+    /// bytes long.
+    pub fn finalize_variable(self, out: &mut [u8]) -> Result<(), Error> {
+        let hash_length = self.0.hash_length;
+        if out.len() != hash_length {
+            return Err(Error::InvalidLength(hash_length, out.len()));
+        }
+        out.copy_from_slice(&self.0.finalize()[..hash_length]);
+        Ok(())
+    }
+}
+
+impl Default for VarHashEngine {
+    fn default() -> Self {
+        Params::new().to_var_engine(MAX_HASH_LENGTH)
+    }
+}
+
+impl crate::HashEngine for VarHashEngine {
+    type MidState = [u8; 64];
+
+    fn midstate(&self) -> [u8; 64] {
+        self.0.midstate()
+    }
+
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.0.n_bytes_hashed()
+    }
+
+    fn input(&mut self, inp: &[u8]) {
+        self.0.input(inp)
+    }
+}
+
+/// Output of the BLAKE2b hash function.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[repr(transparent)]
+pub struct Hash(
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "crate::util::json_hex_string::len_64"))]
+    [u8; 64]
+);
+
+impl Hash {
+    fn internal_new(arr: [u8; 64]) -> Self {
+        Hash(arr)
+    }
+
+    fn internal_engine() -> HashEngine {
+        Default::default()
+    }
+
+    /// Hashes the given data with an engine keyed with `key`.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is longer than [`MAX_KEY_LENGTH`].
+    pub fn hash_with_key(key: &[u8], data: &[u8]) -> Hash {
+        let mut engine = HashEngine::with_key(key);
+        engine.input(data);
+        from_engine(engine)
+    }
+}
+
+impl PartialEq for Hash {
+    fn eq(&self, other: &Hash) -> bool {
+        self.0[..] == other.0[..]
+    }
+}
+
+impl Eq for Hash {}
+
+impl Default for Hash {
+    fn default() -> Hash {
+        Hash([0; 64])
+    }
+}
+
+impl PartialOrd for Hash {
+    fn partial_cmp(&self, other: &Hash) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hash {
+    fn cmp(&self, other: &Hash) -> cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl hash::Hash for Hash {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    Hash(e.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn test() {
+        use crate::{blake2b, Hash, HashEngine};
+        use crate::hex::{FromHex, ToHex};
+
+        #[derive(Clone)]
+        struct Test {
+            input: &'static str,
+            output: Vec<u8>,
+            output_str: &'static str,
+        }
+
+        let tests = vec![
+            // "abc" example from RFC 7693 appendix A, plus the empty and 896-bit
+            // messages used for the SHA-2 examples
+            Test {
+                input: "",
+                output: vec![
+                    0x78, 0x6a, 0x02, 0xf7, 0x42, 0x01, 0x59, 0x03,
+                    0xc6, 0xc6, 0xfd, 0x85, 0x25, 0x52, 0xd2, 0x72,
+                    0x91, 0x2f, 0x47, 0x40, 0xe1, 0x58, 0x47, 0x61,
+                    0x8a, 0x86, 0xe2, 0x17, 0xf7, 0x1f, 0x54, 0x19,
+                    0xd2, 0x5e, 0x10, 0x31, 0xaf, 0xee, 0x58, 0x53,
+                    0x13, 0x89, 0x64, 0x44, 0x93, 0x4e, 0xb0, 0x4b,
+                    0x90, 0x3a, 0x68, 0x5b, 0x14, 0x48, 0xb7, 0x55,
+                    0xd5, 0x6f, 0x70, 0x1a, 0xfe, 0x9b, 0xe2, 0xce,
+                ],
+                output_str: "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+                 d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce",
+            },
+            Test {
+                input: "abc",
+                output: vec![
+                    0xba, 0x80, 0xa5, 0x3f, 0x98, 0x1c, 0x4d, 0x0d,
+                    0x6a, 0x27, 0x97, 0xb6, 0x9f, 0x12, 0xf6, 0xe9,
+                    0x4c, 0x21, 0x2f, 0x14, 0x68, 0x5a, 0xc4, 0xb7,
+                    0x4b, 0x12, 0xbb, 0x6f, 0xdb, 0xff, 0xa2, 0xd1,
+                    0x7d, 0x87, 0xc5, 0x39, 0x2a, 0xab, 0x79, 0x2d,
+                    0xc2, 0x52, 0xd5, 0xde, 0x45, 0x33, 0xcc, 0x95,
+                    0x18, 0xd3, 0x8a, 0xa8, 0xdb, 0xf1, 0x92, 0x5a,
+                    0xb9, 0x23, 0x86, 0xed, 0xd4, 0x00, 0x99, 0x23,
+                ],
+                output_str: "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                 7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+            },
+            Test {
+                input: "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                output: vec![
+                    0xce, 0x74, 0x1a, 0xc5, 0x93, 0x0f, 0xe3, 0x46,
+                    0x81, 0x11, 0x75, 0xc5, 0x22, 0x7b, 0xb7, 0xbf,
+                    0xcd, 0x47, 0xf4, 0x26, 0x12, 0xfa, 0xe4, 0x6c,
+                    0x08, 0x09, 0x51, 0x4f, 0x9e, 0x0e, 0x3a, 0x11,
+                    0xee, 0x17, 0x73, 0x28, 0x71, 0x47, 0xcd, 0xea,
+                    0xee, 0xdf, 0xf5, 0x07, 0x09, 0xaa, 0x71, 0x63,
+                    0x41, 0xfe, 0x65, 0x24, 0x0f, 0x4a, 0xd6, 0x77,
+                    0x7d, 0x6b, 0xfa, 0xf9, 0x72, 0x6e, 0x5e, 0x52,
+                ],
+                output_str: "ce741ac5930fe346811175c5227bb7bfcd47f42612fae46c0809514f9e0e3a11\
+                 ee1773287147cdeaeedff50709aa716341fe65240f4ad6777d6bfaf9726e5e52",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = blake2b::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, blake2b::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash[..], &test.output[..]);
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = blake2b::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = blake2b::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+            assert_eq!(hash.into_inner()[..].as_ref(), test.output.as_slice());
+        }
+    }
+
+    #[test]
+    fn rfc7693_self_test() {
+        use crate::{blake2b, HashEngine};
+        use crate::hex::FromHex;
+
+        // Deterministic input sequence from RFC 7693 appendix E.
+        fn selftest_seq(out: &mut [u8], seed: u32) {
+            let mut a = 0xdead4bad_u32.wrapping_mul(seed);
+            let mut b = 1_u32;
+            for byte in out.iter_mut() {
+                let t = a.wrapping_add(b);
+                a = b;
+                b = t;
+                *byte = (t >> 24) as u8;
+            }
+        }
+
+        let mut grand = blake2b::Params::new().to_var_engine(32);
+        let mut input = [0u8; 1024];
+        let mut key = [0u8; blake2b::MAX_KEY_LENGTH];
+        let mut md = [0u8; blake2b::MAX_HASH_LENGTH];
+        for &outlen in &[20, 32, 48, 64] {
+            for &inlen in &[0, 3, 128, 129, 255, 1024] {
+                selftest_seq(&mut input[..inlen], inlen as u32);
+                selftest_seq(&mut key[..outlen], outlen as u32);
+
+                let mut engine = blake2b::Params::new().to_var_engine(outlen);
+                engine.input(&input[..inlen]);
+                engine.finalize_variable(&mut md[..outlen]).expect("correct length");
+                grand.input(&md[..outlen]);
+
+                let mut engine = blake2b::Params::new().key(&key[..outlen]).to_var_engine(outlen);
+                engine.input(&input[..inlen]);
+                engine.finalize_variable(&mut md[..outlen]).expect("correct length");
+                grand.input(&md[..outlen]);
+            }
+        }
+
+        let mut result = [0u8; 32];
+        grand.finalize_variable(&mut result).expect("correct length");
+        assert_eq!(
+            result,
+            <[u8; 32]>::from_hex("c23a7800d98123bd10f506c61e29da5603d763b8bbad2e737f5e765a7bccd475").unwrap(),
+        );
+    }
+
+    #[test]
+    fn keyed() {
+        use crate::{blake2b, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let mut key = [0u8; blake2b::MAX_KEY_LENGTH];
+        for (i, k) in key.iter_mut().enumerate() {
+            *k = i as u8;
+        }
+        let hash = blake2b::Hash::hash_with_key(&key, b"abc");
+        assert_eq!(
+            hash,
+            blake2b::Hash::from_hex("06bbc3dedf13a31139498655251b7588ccd3bb5aaa071b2d44d8e0a04095579e\
+             d590fbfdcf941f4370ce5ce623624e7a76d33e7a8109dcda9b57d72f8f8efa51").unwrap(),
+        );
+
+        // The key occupies a whole block of its own.
+        let engine = blake2b::HashEngine::with_key(&key);
+        assert_eq!(engine.n_bytes_hashed(), 128);
+        assert_ne!(hash, blake2b::Hash::hash(b"abc"));
+    }
+
+    #[test]
+    fn params() {
+        use crate::{blake2b, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let mut engine = blake2b::Params::new()
+            .key(b"key")
+            .salt(b"salty")
+            .personal(b"personal")
+            .to_var_engine(20);
+        engine.input(b"abc");
+        assert_eq!(engine.hash_length(), 20);
+
+        let mut out = [0u8; 20];
+        assert!(engine.clone().finalize_variable(&mut [0u8; 32]).is_err());
+        engine.finalize_variable(&mut out).expect("correct length");
+        assert_eq!(out, <[u8; 20]>::from_hex("e4513b62ed96926f6bbdc028156eca47015b1fcb").unwrap());
+
+        // At the default length the output matches `Hash`.
+        let mut engine = blake2b::Params::new().to_var_engine(blake2b::MAX_HASH_LENGTH);
+        engine.input(b"abc");
+        let mut out = [0u8; 64];
+        engine.finalize_variable(&mut out).expect("correct length");
+        assert_eq!(&out[..], &blake2b::Hash::hash(b"abc")[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn key_too_long() {
+        use crate::blake2b;
+
+        blake2b::Params::new().key(&[0; blake2b::MAX_KEY_LENGTH + 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn hash_length_too_long() {
+        use crate::blake2b;
+
+        blake2b::Params::new().to_var_engine(blake2b::MAX_HASH_LENGTH + 1);
+    }
+
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn blake2b_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{blake2b, Hash};
+
+        static HASH_BYTES: [u8; 64] = [
+            0xba, 0x80, 0xa5, 0x3f, 0x98, 0x1c, 0x4d, 0x0d,
+            0x6a, 0x27, 0x97, 0xb6, 0x9f, 0x12, 0xf6, 0xe9,
+            0x4c, 0x21, 0x2f, 0x14, 0x68, 0x5a, 0xc4, 0xb7,
+            0x4b, 0x12, 0xbb, 0x6f, 0xdb, 0xff, 0xa2, 0xd1,
+            0x7d, 0x87, 0xc5, 0x39, 0x2a, 0xab, 0x79, 0x2d,
+            0xc2, 0x52, 0xd5, 0xde, 0x45, 0x33, 0xcc, 0x95,
+            0x18, 0xd3, 0x8a, 0xa8, 0xdb, 0xf1, 0x92, 0x5a,
+            0xb9, 0x23, 0x86, 0xed, 0xd4, 0x00, 0x99, 0x23,
+        ];
+
+        let hash = blake2b::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(
+            &hash.readable(),
+            &[Token::Str(
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                 7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+            )],
+        );
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, blake2b};
+
+    #[bench]
+    pub fn blake2b_10(bh: &mut Bencher) {
+        let mut engine = blake2b::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn blake2b_1k(bh: &mut Bencher) {
+        let mut engine = blake2b::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn blake2b_64k(bh: &mut Bencher) {
+        let mut engine = blake2b::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
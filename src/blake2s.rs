@@ -0,0 +1,598 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BLAKE2s implementation.
+//!
+//! BLAKE2s as specified in RFC 7693, with support for the keyed mode and the salt and
+//! personalization fields of the parameter block (see [`Params`]). The [`Hash`](struct@Hash) type holds the
+//! default 256-bit output; shorter outputs are produced by a [`VarHashEngine`].
+//!
+
+use core::{cmp, str};
+use core::convert::TryInto;
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, HashEngine as _, hex};
+
+crate::internal_macros::hash_type! {
+    256,
+    false,
+    "Output of the BLAKE2s hash function.",
+    "crate::util::json_hex_string::len_32"
+}
+
+pub(crate) const BLOCK_SIZE: usize = 64;
+
+/// Maximum output length, in bytes.
+pub const MAX_HASH_LENGTH: usize = 32;
+/// Maximum key length, in bytes.
+pub const MAX_KEY_LENGTH: usize = 32;
+/// Length of the salt field of the parameter block, in bytes.
+pub const SALT_LENGTH: usize = 8;
+/// Length of the personalization field of the parameter block, in bytes.
+pub const PERSONAL_LENGTH: usize = 8;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// BLAKE2s parameter block, used to construct engines with a non-default configuration.
+///
+/// Only the fields relevant to sequential hashing are exposed; the tree hashing fields are
+/// always set to their sequential-mode values.
+#[derive(Clone)]
+pub struct Params {
+    key_length: usize,
+    key: [u8; MAX_KEY_LENGTH],
+    salt: [u8; SALT_LENGTH],
+    personal: [u8; PERSONAL_LENGTH],
+}
+
+impl Params {
+    /// Creates a parameter block for unkeyed hashing with a 32-byte output.
+    pub fn new() -> Params {
+        Params {
+            key_length: 0,
+            key: [0; MAX_KEY_LENGTH],
+            salt: [0; SALT_LENGTH],
+            personal: [0; PERSONAL_LENGTH],
+        }
+    }
+
+    /// Sets the key, switching the engine to keyed (MAC) mode. An empty key means unkeyed
+    /// hashing.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is longer than [`MAX_KEY_LENGTH`].
+    pub fn key(&mut self, key: &[u8]) -> &mut Params {
+        assert!(key.len() <= MAX_KEY_LENGTH, "BLAKE2s key too long");
+        self.key = [0; MAX_KEY_LENGTH];
+        self.key[..key.len()].copy_from_slice(key);
+        self.key_length = key.len();
+        self
+    }
+
+    /// Sets the salt. Salts shorter than [`SALT_LENGTH`] are padded with zeros.
+    ///
+    /// # Panics
+    ///
+    /// If `salt` is longer than [`SALT_LENGTH`].
+    pub fn salt(&mut self, salt: &[u8]) -> &mut Params {
+        assert!(salt.len() <= SALT_LENGTH, "BLAKE2s salt too long");
+        self.salt = [0; SALT_LENGTH];
+        self.salt[..salt.len()].copy_from_slice(salt);
+        self
+    }
+
+    /// Sets the personalization string. Strings shorter than [`PERSONAL_LENGTH`] are padded
+    /// with zeros.
+    ///
+    /// # Panics
+    ///
+    /// If `personal` is longer than [`PERSONAL_LENGTH`].
+    pub fn personal(&mut self, personal: &[u8]) -> &mut Params {
+        assert!(personal.len() <= PERSONAL_LENGTH, "BLAKE2s personalization too long");
+        self.personal = [0; PERSONAL_LENGTH];
+        self.personal[..personal.len()].copy_from_slice(personal);
+        self
+    }
+
+    /// Constructs an engine with the default 32-byte output using these parameters.
+    pub fn to_engine(&self) -> HashEngine {
+        self.engine(MAX_HASH_LENGTH)
+    }
+
+    /// Constructs an engine with a `hash_length`-byte output using these parameters.
+    ///
+    /// # Panics
+    ///
+    /// If `hash_length` is zero or greater than [`MAX_HASH_LENGTH`].
+    pub fn to_var_engine(&self, hash_length: usize) -> VarHashEngine {
+        assert!((1..=MAX_HASH_LENGTH).contains(&hash_length), "invalid BLAKE2s output length");
+        VarHashEngine(self.engine(hash_length))
+    }
+
+    fn engine(&self, hash_length: usize) -> HashEngine {
+        let mut h = IV;
+        h[0] ^= 0x01010000 ^ ((self.key_length as u32) << 8) ^ hash_length as u32;
+        h[4] ^= u32::from_le_bytes(self.salt[..4].try_into().expect("4 byte slice"));
+        h[5] ^= u32::from_le_bytes(self.salt[4..].try_into().expect("4 byte slice"));
+        h[6] ^= u32::from_le_bytes(self.personal[..4].try_into().expect("4 byte slice"));
+        h[7] ^= u32::from_le_bytes(self.personal[4..].try_into().expect("4 byte slice"));
+
+        let mut engine = HashEngine {
+            h,
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+            buf_len: 0,
+            hash_length,
+        };
+        // A keyed hash starts with the key padded to a full block.
+        if self.key_length > 0 {
+            let mut block = [0; BLOCK_SIZE];
+            block[..self.key_length].copy_from_slice(&self.key[..self.key_length]);
+            engine.input(&block);
+        }
+        engine
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params::new()
+    }
+}
+
+/// Engine to compute BLAKE2s hash function.
+#[derive(Clone)]
+pub struct HashEngine {
+    h: [u32; 8],
+    length: usize, // bytes compressed so far
+    buffer: [u8; BLOCK_SIZE],
+    buf_len: usize,
+    hash_length: usize, // only differs from the default in a `VarHashEngine`
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, h, length, buffer, buf_len, hash_length);
+
+impl HashEngine {
+    /// Creates a new keyed BLAKE2s engine with a 32-byte output.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is longer than [`MAX_KEY_LENGTH`].
+    pub fn with_key(key: &[u8]) -> HashEngine {
+        Params::new().key(key).to_engine()
+    }
+
+    /// Compresses the final block and returns the full state, of which the digest is a prefix.
+    fn finalize(mut self) -> [u8; 32] {
+        self.length += self.buf_len;
+        for b in self.buffer[self.buf_len..].iter_mut() {
+            *b = 0;
+        }
+        self.compress(true);
+        self.state_bytes()
+    }
+
+    fn state_bytes(&self) -> [u8; 32] {
+        let mut ret = [0; 32];
+        for (val, ret_bytes) in self.h.iter().zip(ret.chunks_exact_mut(4)) {
+            ret_bytes.copy_from_slice(&val.to_le_bytes());
+        }
+        ret
+    }
+
+    fn compress(&mut self, last: bool) {
+        let mut m = [0u32; 16];
+        for (m_val, buf_bytes) in m.iter_mut().zip(self.buffer.chunks_exact(4)) {
+            *m_val = u32::from_le_bytes(buf_bytes.try_into().expect("4 byte slice"));
+        }
+
+        let mut v = [0u32; 16];
+        v[..8].copy_from_slice(&self.h);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= self.length as u32;
+        v[13] ^= ((self.length as u64) >> 32) as u32;
+        if last {
+            v[14] = !v[14];
+        }
+
+        for s in SIGMA.iter() {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+
+        for i in 0..8 {
+            self.h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+#[inline(always)]
+fn g(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        Params::new().to_engine()
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; 32];
+
+    fn midstate(&self) -> [u8; 32] {
+        self.state_bytes()
+    }
+
+    const BLOCK_SIZE: usize = 64;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length + self.buf_len
+    }
+
+    fn input(&mut self, mut inp: &[u8]) {
+        while !inp.is_empty() {
+            // The last block is compressed differently, so a full buffer is only compressed
+            // once we know more input follows it.
+            if self.buf_len == BLOCK_SIZE {
+                self.length += BLOCK_SIZE;
+                self.compress(false);
+                self.buf_len = 0;
+            }
+            let write_len = cmp::min(BLOCK_SIZE - self.buf_len, inp.len());
+            self.buffer[self.buf_len..self.buf_len + write_len]
+                .copy_from_slice(&inp[..write_len]);
+            self.buf_len += write_len;
+            inp = &inp[write_len..];
+        }
+    }
+}
+
+/// Engine to compute BLAKE2s hash function with a configurable output length.
+///
+/// Constructed by [`Params::to_var_engine`]; the output is only available through
+/// [`VarHashEngine::finalize_variable`].
+#[derive(Clone)]
+pub struct VarHashEngine(HashEngine);
+
+crate::internal_macros::engine_zeroize_impl!(wrapper VarHashEngine);
+
+impl VarHashEngine {
+    /// Returns the output length this engine was configured with, in bytes.
+    pub fn hash_length(&self) -> usize {
+        self.0.hash_length
+    }
+
+    /// Finalizes the engine into `out`, which must be exactly [`VarHashEngine::hash_length`]
+    /// bytes long.
+    pub fn finalize_variable(self, out: &mut [u8]) -> Result<(), Error> {
+        let hash_length = self.0.hash_length;
+        if out.len() != hash_length {
+            return Err(Error::InvalidLength(hash_length, out.len()));
+        }
+        out.copy_from_slice(&self.0.finalize()[..hash_length]);
+        Ok(())
+    }
+}
+
+impl Default for VarHashEngine {
+    fn default() -> Self {
+        Params::new().to_var_engine(MAX_HASH_LENGTH)
+    }
+}
+
+impl crate::HashEngine for VarHashEngine {
+    type MidState = [u8; 32];
+
+    fn midstate(&self) -> [u8; 32] {
+        self.0.midstate()
+    }
+
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.0.n_bytes_hashed()
+    }
+
+    fn input(&mut self, inp: &[u8]) {
+        self.0.input(inp)
+    }
+}
+
+impl Hash {
+    /// Hashes the given data with an engine keyed with `key`.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is longer than [`MAX_KEY_LENGTH`].
+    pub fn hash_with_key(key: &[u8], data: &[u8]) -> Hash {
+        let mut engine = HashEngine::with_key(key);
+        engine.input(data);
+        from_engine(engine)
+    }
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    Hash(e.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn test() {
+        use crate::{blake2s, Hash, HashEngine};
+        use crate::hex::{FromHex, ToHex};
+
+        #[derive(Clone)]
+        struct Test {
+            input: &'static str,
+            output: Vec<u8>,
+            output_str: &'static str,
+        }
+
+        let tests = vec![
+            // "abc" example from RFC 7693 appendix B, plus the empty and 896-bit
+            // messages used for the SHA-2 examples
+            Test {
+                input: "",
+                output: vec![
+                    0x69, 0x21, 0x7a, 0x30, 0x79, 0x90, 0x80, 0x94,
+                    0xe1, 0x11, 0x21, 0xd0, 0x42, 0x35, 0x4a, 0x7c,
+                    0x1f, 0x55, 0xb6, 0x48, 0x2c, 0xa1, 0xa5, 0x1e,
+                    0x1b, 0x25, 0x0d, 0xfd, 0x1e, 0xd0, 0xee, 0xf9,
+                ],
+                output_str: "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9",
+            },
+            Test {
+                input: "abc",
+                output: vec![
+                    0x50, 0x8c, 0x5e, 0x8c, 0x32, 0x7c, 0x14, 0xe2,
+                    0xe1, 0xa7, 0x2b, 0xa3, 0x4e, 0xeb, 0x45, 0x2f,
+                    0x37, 0x45, 0x8b, 0x20, 0x9e, 0xd6, 0x3a, 0x29,
+                    0x4d, 0x99, 0x9b, 0x4c, 0x86, 0x67, 0x59, 0x82,
+                ],
+                output_str: "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982",
+            },
+            Test {
+                input: "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                output: vec![
+                    0x35, 0x8d, 0xd2, 0xed, 0x07, 0x80, 0xd4, 0x05,
+                    0x4e, 0x76, 0xcb, 0x6f, 0x3a, 0x5b, 0xce, 0x28,
+                    0x41, 0xe8, 0xe2, 0xf5, 0x47, 0x43, 0x1d, 0x4d,
+                    0x09, 0xdb, 0x21, 0xb6, 0x6d, 0x94, 0x1f, 0xc7,
+                ],
+                output_str: "358dd2ed0780d4054e76cb6f3a5bce2841e8e2f547431d4d09db21b66d941fc7",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = blake2s::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, blake2s::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash[..], &test.output[..]);
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = blake2s::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = blake2s::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+            assert_eq!(hash.into_inner()[..].as_ref(), test.output.as_slice());
+        }
+    }
+
+    #[test]
+    fn rfc7693_self_test() {
+        use crate::{blake2s, HashEngine};
+        use crate::hex::FromHex;
+
+        // Deterministic input sequence from RFC 7693 appendix E.
+        fn selftest_seq(out: &mut [u8], seed: u32) {
+            let mut a = 0xdead4bad_u32.wrapping_mul(seed);
+            let mut b = 1_u32;
+            for byte in out.iter_mut() {
+                let t = a.wrapping_add(b);
+                a = b;
+                b = t;
+                *byte = (t >> 24) as u8;
+            }
+        }
+
+        let mut grand = blake2s::Params::new().to_var_engine(32);
+        let mut input = [0u8; 1024];
+        let mut key = [0u8; blake2s::MAX_KEY_LENGTH];
+        let mut md = [0u8; blake2s::MAX_HASH_LENGTH];
+        for &outlen in &[16, 20, 28, 32] {
+            for &inlen in &[0, 3, 64, 65, 255, 1024] {
+                selftest_seq(&mut input[..inlen], inlen as u32);
+                selftest_seq(&mut key[..outlen], outlen as u32);
+
+                let mut engine = blake2s::Params::new().to_var_engine(outlen);
+                engine.input(&input[..inlen]);
+                engine.finalize_variable(&mut md[..outlen]).expect("correct length");
+                grand.input(&md[..outlen]);
+
+                let mut engine = blake2s::Params::new().key(&key[..outlen]).to_var_engine(outlen);
+                engine.input(&input[..inlen]);
+                engine.finalize_variable(&mut md[..outlen]).expect("correct length");
+                grand.input(&md[..outlen]);
+            }
+        }
+
+        let mut result = [0u8; 32];
+        grand.finalize_variable(&mut result).expect("correct length");
+        assert_eq!(
+            result,
+            <[u8; 32]>::from_hex("6a411f08ce25adcdfb02aba641451cec53c598b24f4fc787fbdc88797f4c1dfe").unwrap(),
+        );
+    }
+
+    #[test]
+    fn keyed() {
+        use crate::{blake2s, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let mut key = [0u8; blake2s::MAX_KEY_LENGTH];
+        for (i, k) in key.iter_mut().enumerate() {
+            *k = i as u8;
+        }
+        let hash = blake2s::Hash::hash_with_key(&key, b"abc");
+        assert_eq!(
+            hash,
+            blake2s::Hash::from_hex("a281f725754969a702f6fe36fc591b7def866e4b70173ece402fc01c064d6b65").unwrap(),
+        );
+
+        // The key occupies a whole block of its own.
+        let engine = blake2s::HashEngine::with_key(&key);
+        assert_eq!(engine.n_bytes_hashed(), 64);
+        assert_ne!(hash, blake2s::Hash::hash(b"abc"));
+    }
+
+    #[test]
+    fn params() {
+        use crate::{blake2s, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let mut engine = blake2s::Params::new()
+            .key(b"key")
+            .salt(b"salty")
+            .personal(b"personal")
+            .to_var_engine(20);
+        engine.input(b"abc");
+        assert_eq!(engine.hash_length(), 20);
+
+        let mut out = [0u8; 20];
+        assert!(engine.clone().finalize_variable(&mut [0u8; 32]).is_err());
+        engine.finalize_variable(&mut out).expect("correct length");
+        assert_eq!(out, <[u8; 20]>::from_hex("88a51f4cf0156e68fe103a67415794fa96323875").unwrap());
+
+        // At the default length the output matches `Hash`.
+        let mut engine = blake2s::Params::new().to_var_engine(blake2s::MAX_HASH_LENGTH);
+        engine.input(b"abc");
+        let mut out = [0u8; 32];
+        engine.finalize_variable(&mut out).expect("correct length");
+        assert_eq!(&out[..], &blake2s::Hash::hash(b"abc")[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn key_too_long() {
+        use crate::blake2s;
+
+        blake2s::Params::new().key(&[0; blake2s::MAX_KEY_LENGTH + 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn hash_length_too_long() {
+        use crate::blake2s;
+
+        blake2s::Params::new().to_var_engine(blake2s::MAX_HASH_LENGTH + 1);
+    }
+
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn blake2s_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{blake2s, Hash};
+
+        static HASH_BYTES: [u8; 32] = [
+            0x50, 0x8c, 0x5e, 0x8c, 0x32, 0x7c, 0x14, 0xe2,
+            0xe1, 0xa7, 0x2b, 0xa3, 0x4e, 0xeb, 0x45, 0x2f,
+            0x37, 0x45, 0x8b, 0x20, 0x9e, 0xd6, 0x3a, 0x29,
+            0x4d, 0x99, 0x9b, 0x4c, 0x86, 0x67, 0x59, 0x82,
+        ];
+
+        let hash = blake2s::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(
+            &hash.readable(),
+            &[Token::Str(
+                "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"
+            )],
+        );
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, blake2s};
+
+    #[bench]
+    pub fn blake2s_10(bh: &mut Bencher) {
+        let mut engine = blake2s::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn blake2s_1k(bh: &mut Bencher) {
+        let mut engine = blake2s::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn blake2s_64k(bh: &mut Bencher) {
+        let mut engine = blake2s::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
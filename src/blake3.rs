@@ -0,0 +1,892 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+// This module follows the structure of the BLAKE3 reference implementation,
+// which is dedicated to the public domain under CC0.
+
+//! BLAKE3 implementation.
+//!
+//! A portable implementation of BLAKE3 supporting the regular, keyed and key derivation modes,
+//! and extendable output through [`XofReader`].
+//!
+//! # Tree hashing
+//!
+//! BLAKE3 splits its input into chunks of [`CHUNK_LEN`] bytes, which form the leaves of a binary
+//! tree. The chaining values of subtrees can be computed independently, for instance in parallel
+//! or on different machines, and then combined:
+//!
+//! * compute the chaining value of a chunk or subtree with [`HashEngine::set_input_offset`] and
+//!   [`HashEngine::finalize_non_root`],
+//! * join two subtrees with [`merge_subtrees_non_root`],
+//! * join the two children of the root with [`merge_subtrees_root`] or
+//!   [`merge_subtrees_root_xof`].
+//!
+//! The left subtree of any node is the largest power-of-two number of chunks that is smaller
+//! than the node's input, see [`left_subtree_len`]. Splitting the input any other way produces
+//! garbage output.
+//!
+
+use core::{cmp, str};
+use core::convert::TryInto;
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, HashEngine as _, hex};
+
+crate::internal_macros::hash_type! {
+    256,
+    false,
+    "Output of the BLAKE3 hash function.",
+    "crate::util::json_hex_string::len_32"
+}
+
+/// Length of a chunk, the leaves of the BLAKE3 tree, in bytes.
+pub const CHUNK_LEN: usize = 1024;
+/// Length of a key, in bytes.
+pub const KEY_LEN: usize = 32;
+
+const BLOCK_LEN: usize = 64;
+// Enough for 2^54 chunks, which covers any input whose length fits in a u64.
+const MAX_DEPTH: usize = 54;
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+/// The chaining value of a chunk or subtree.
+pub type ChainingValue = [u8; 32];
+
+/// The mode of a BLAKE3 tree, which determines the key and flags of every node.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// Regular hashing.
+    Hash,
+    /// Keyed hashing with the given key.
+    KeyedHash([u8; KEY_LEN]),
+    /// Hashing of key material in the key derivation mode, using the context key returned by
+    /// [`hash_derive_key_context`].
+    DeriveKeyMaterial([u8; KEY_LEN]),
+}
+
+impl Mode {
+    fn key_words(&self) -> [u32; 8] {
+        match *self {
+            Mode::Hash => IV,
+            Mode::KeyedHash(ref key) | Mode::DeriveKeyMaterial(ref key) => words_from_le_bytes(key),
+        }
+    }
+
+    fn flags(&self) -> u32 {
+        match *self {
+            Mode::Hash => 0,
+            Mode::KeyedHash(_) => KEYED_HASH,
+            Mode::DeriveKeyMaterial(_) => DERIVE_KEY_MATERIAL,
+        }
+    }
+}
+
+#[inline(always)]
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(x);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(y);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn round(state: &mut [u32; 16], m: &[u32; 16]) {
+    // Mix the columns.
+    g(state, 0, 4, 8, 12, m[0], m[1]);
+    g(state, 1, 5, 9, 13, m[2], m[3]);
+    g(state, 2, 6, 10, 14, m[4], m[5]);
+    g(state, 3, 7, 11, 15, m[6], m[7]);
+    // Mix the diagonals.
+    g(state, 0, 5, 10, 15, m[8], m[9]);
+    g(state, 1, 6, 11, 12, m[10], m[11]);
+    g(state, 2, 7, 8, 13, m[12], m[13]);
+    g(state, 3, 4, 9, 14, m[14], m[15]);
+}
+
+fn compress(
+    chaining_value: &[u32; 8],
+    block_words: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..8].copy_from_slice(chaining_value);
+    state[8..12].copy_from_slice(&IV[..4]);
+    state[12] = counter as u32;
+    state[13] = (counter >> 32) as u32;
+    state[14] = block_len;
+    state[15] = flags;
+
+    let mut m = *block_words;
+    for i in 0..7 {
+        round(&mut state, &m);
+        if i < 6 {
+            let mut permuted = [0; 16];
+            for (p, &idx) in permuted.iter_mut().zip(MSG_PERMUTATION.iter()) {
+                *p = m[idx];
+            }
+            m = permuted;
+        }
+    }
+
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= chaining_value[i];
+    }
+    state
+}
+
+fn words_from_le_bytes(bytes: &[u8; 32]) -> [u32; 8] {
+    let mut words = [0; 8];
+    for (w, b) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *w = u32::from_le_bytes(b.try_into().expect("4 byte slice"));
+    }
+    words
+}
+
+fn block_words_from_le_bytes(bytes: &[u8; BLOCK_LEN]) -> [u32; 16] {
+    let mut words = [0; 16];
+    for (w, b) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *w = u32::from_le_bytes(b.try_into().expect("4 byte slice"));
+    }
+    words
+}
+
+fn le_bytes_from_words(words: &[u32; 8]) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    for (b, w) in bytes.chunks_exact_mut(4).zip(words.iter()) {
+        b.copy_from_slice(&w.to_le_bytes());
+    }
+    bytes
+}
+
+/// The inputs of a compression whose output has not been computed yet, because we don't know
+/// whether it is the root node.
+#[derive(Clone)]
+struct Output {
+    input_chaining_value: [u32; 8],
+    block_words: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        let out = compress(
+            &self.input_chaining_value,
+            &self.block_words,
+            self.counter,
+            self.block_len,
+            self.flags,
+        );
+        let mut cv = [0; 8];
+        cv.copy_from_slice(&out[..8]);
+        cv
+    }
+
+    fn root_output_block(&self, output_block_counter: u64) -> [u8; BLOCK_LEN] {
+        let words = compress(
+            &self.input_chaining_value,
+            &self.block_words,
+            output_block_counter,
+            self.block_len,
+            self.flags | ROOT,
+        );
+        let mut ret = [0; BLOCK_LEN];
+        for (b, w) in ret.chunks_exact_mut(4).zip(words.iter()) {
+            b.copy_from_slice(&w.to_le_bytes());
+        }
+        ret
+    }
+}
+
+fn parent_output(
+    left_child_cv: &[u32; 8],
+    right_child_cv: &[u32; 8],
+    key_words: &[u32; 8],
+    flags: u32,
+) -> Output {
+    let mut block_words = [0; 16];
+    block_words[..8].copy_from_slice(left_child_cv);
+    block_words[8..].copy_from_slice(right_child_cv);
+    Output {
+        input_chaining_value: *key_words,
+        block_words,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: PARENT | flags,
+    }
+}
+
+#[derive(Clone)]
+struct ChunkState {
+    chaining_value: [u32; 8],
+    chunk_counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+crate::internal_macros::zeroize_fields_impl!(ChunkState, chaining_value, chunk_counter, block, block_len, blocks_compressed, flags);
+
+impl ChunkState {
+    fn new(key_words: &[u32; 8], chunk_counter: u64, flags: u32) -> ChunkState {
+        ChunkState {
+            chaining_value: *key_words,
+            chunk_counter,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 { CHUNK_START } else { 0 }
+    }
+
+    fn input(&mut self, mut inp: &[u8]) {
+        while !inp.is_empty() {
+            // The last block of a chunk is compressed differently, so a full buffer is only
+            // compressed once we know more input follows it.
+            if self.block_len == BLOCK_LEN {
+                let block_words = block_words_from_le_bytes(&self.block);
+                let out = compress(
+                    &self.chaining_value,
+                    &block_words,
+                    self.chunk_counter,
+                    BLOCK_LEN as u32,
+                    self.flags | self.start_flag(),
+                );
+                self.chaining_value.copy_from_slice(&out[..8]);
+                self.blocks_compressed += 1;
+                self.block = [0; BLOCK_LEN];
+                self.block_len = 0;
+            }
+
+            let write_len = cmp::min(BLOCK_LEN - self.block_len, inp.len());
+            self.block[self.block_len..self.block_len + write_len].copy_from_slice(&inp[..write_len]);
+            self.block_len += write_len;
+            inp = &inp[write_len..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            input_chaining_value: self.chaining_value,
+            block_words: block_words_from_le_bytes(&self.block),
+            counter: self.chunk_counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+/// Engine to compute BLAKE3 hash function.
+#[derive(Clone)]
+pub struct HashEngine {
+    chunk_state: ChunkState,
+    key_words: [u32; 8],
+    cv_stack: [[u32; 8]; MAX_DEPTH],
+    cv_stack_len: usize,
+    flags: u32,
+    initial_chunk_counter: u64,
+    length: usize,
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, chunk_state, key_words, cv_stack, cv_stack_len, flags, initial_chunk_counter, length);
+
+impl HashEngine {
+    fn new_internal(key_words: [u32; 8], flags: u32) -> HashEngine {
+        HashEngine {
+            chunk_state: ChunkState::new(&key_words, 0, flags),
+            key_words,
+            cv_stack: [[0; 8]; MAX_DEPTH],
+            cv_stack_len: 0,
+            flags,
+            initial_chunk_counter: 0,
+            length: 0,
+        }
+    }
+
+    /// Creates a new engine for the given tree mode.
+    pub fn with_mode(mode: Mode) -> HashEngine {
+        HashEngine::new_internal(mode.key_words(), mode.flags())
+    }
+
+    /// Creates a new engine for keyed hashing.
+    pub fn with_key(key: &[u8; KEY_LEN]) -> HashEngine {
+        HashEngine::with_mode(Mode::KeyedHash(*key))
+    }
+
+    /// Creates a new engine for the key derivation mode. The `context` string should be
+    /// hardcoded, globally unique and application specific; the key material is then input
+    /// into the engine.
+    pub fn with_derive_key_context(context: &str) -> HashEngine {
+        HashEngine::with_mode(Mode::DeriveKeyMaterial(hash_derive_key_context(context)))
+    }
+
+    /// Sets the offset, in bytes, of the subtree hashed by this engine within the whole input.
+    ///
+    /// Only engines finalized with [`HashEngine::finalize_non_root`] may have a non-zero
+    /// offset, and the subtree may not be longer than [`max_subtree_len`] of the offset.
+    ///
+    /// # Panics
+    ///
+    /// If `offset` is not a multiple of [`CHUNK_LEN`] or the engine has already been given input.
+    pub fn set_input_offset(&mut self, offset: u64) {
+        assert_eq!(self.length, 0, "engine has already been given input");
+        assert_eq!(offset % CHUNK_LEN as u64, 0, "offset is no multiple of the chunk length");
+
+        let counter = offset / CHUNK_LEN as u64;
+        self.chunk_state.chunk_counter = counter;
+        self.initial_chunk_counter = counter;
+    }
+
+    /// Finalizes the engine into the chaining value of the chunk or subtree it has hashed.
+    ///
+    /// # Panics
+    ///
+    /// If the engine has not been given any input; empty subtrees do not exist.
+    pub fn finalize_non_root(self) -> ChainingValue {
+        assert_ne!(self.length, 0, "empty subtrees are never valid");
+        le_bytes_from_words(&self.final_output().chaining_value())
+    }
+
+    /// Finalizes the engine into a reader from which output of any length can be squeezed.
+    ///
+    /// # Panics
+    ///
+    /// If a non-zero input offset was set.
+    pub fn finalize_xof(self) -> XofReader {
+        assert_eq!(self.initial_chunk_counter, 0, "engines with an input offset are never the root");
+        XofReader::new(self.final_output())
+    }
+
+    fn push_stack(&mut self, cv: [u32; 8]) {
+        self.cv_stack[self.cv_stack_len] = cv;
+        self.cv_stack_len += 1;
+    }
+
+    fn pop_stack(&mut self) -> [u32; 8] {
+        self.cv_stack_len -= 1;
+        self.cv_stack[self.cv_stack_len]
+    }
+
+    // Each completed subtree is merged with its left sibling as soon as both exist; the number
+    // of trailing zero bits of the chunk count says how many merges are due.
+    fn add_chunk_chaining_value(&mut self, mut new_cv: [u32; 8], mut total_chunks: u64) {
+        while total_chunks & 1 == 0 {
+            let left = self.pop_stack();
+            new_cv = parent_output(&left, &new_cv, &self.key_words, self.flags).chaining_value();
+            total_chunks >>= 1;
+        }
+        self.push_stack(new_cv);
+    }
+
+    fn final_output(&self) -> Output {
+        let mut output = self.chunk_state.output();
+        let mut parent_nodes_remaining = self.cv_stack_len;
+        while parent_nodes_remaining > 0 {
+            parent_nodes_remaining -= 1;
+            output = parent_output(
+                &self.cv_stack[parent_nodes_remaining],
+                &output.chaining_value(),
+                &self.key_words,
+                self.flags,
+            );
+        }
+        output
+    }
+}
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine::with_mode(Mode::Hash)
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; 32];
+
+    /// Outputs the chaining value of the current chunk, covering the blocks of it compressed
+    /// so far. This is not a complete description of the engine's state.
+    fn midstate(&self) -> [u8; 32] {
+        le_bytes_from_words(&self.chunk_state.chaining_value)
+    }
+
+    const BLOCK_SIZE: usize = 64;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length
+    }
+
+    fn input(&mut self, mut inp: &[u8]) {
+        self.length += inp.len();
+        while !inp.is_empty() {
+            // A full chunk is only finished once we know more input follows it, since the last
+            // chunk may be the root.
+            if self.chunk_state.len() == CHUNK_LEN {
+                let chunk_cv = self.chunk_state.output().chaining_value();
+                let total_chunks = self.chunk_state.chunk_counter - self.initial_chunk_counter + 1;
+                self.add_chunk_chaining_value(chunk_cv, total_chunks);
+                self.chunk_state = ChunkState::new(
+                    &self.key_words,
+                    self.chunk_state.chunk_counter + 1,
+                    self.flags,
+                );
+            }
+
+            let write_len = cmp::min(CHUNK_LEN - self.chunk_state.len(), inp.len());
+            self.chunk_state.input(&inp[..write_len]);
+            inp = &inp[write_len..];
+        }
+    }
+}
+
+/// Reader for the extendable output of BLAKE3.
+#[derive(Clone)]
+pub struct XofReader {
+    output: Output,
+    block_counter: u64,
+    block: [u8; BLOCK_LEN],
+    pos: usize,
+}
+
+impl XofReader {
+    fn new(output: Output) -> XofReader {
+        let block = output.root_output_block(0);
+        XofReader { output, block_counter: 0, block, pos: 0 }
+    }
+
+    /// Fills `out` with the next `out.len()` bytes of output.
+    ///
+    /// Successive calls continue where the previous one left off, so the output does not depend
+    /// on how it is split into calls.
+    pub fn squeeze(&mut self, mut out: &mut [u8]) {
+        while !out.is_empty() {
+            if self.pos == BLOCK_LEN {
+                self.block_counter += 1;
+                self.block = self.output.root_output_block(self.block_counter);
+                self.pos = 0;
+            }
+            let write_len = cmp::min(BLOCK_LEN - self.pos, out.len());
+            out[..write_len].copy_from_slice(&self.block[self.pos..self.pos + write_len]);
+            self.pos += write_len;
+            out = &mut out[write_len..];
+        }
+    }
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    let mut ret = [0; 32];
+    e.finalize_xof().squeeze(&mut ret);
+    Hash(ret)
+}
+
+impl Hash {
+    /// Hashes the given data with an engine keyed with `key`.
+    pub fn hash_with_key(key: &[u8; KEY_LEN], data: &[u8]) -> Hash {
+        let mut engine = HashEngine::with_key(key);
+        engine.input(data);
+        from_engine(engine)
+    }
+
+    /// Derives a key from `key_material` in the key derivation mode, see
+    /// [`HashEngine::with_derive_key_context`].
+    pub fn derive_key(context: &str, key_material: &[u8]) -> [u8; KEY_LEN] {
+        let mut engine = HashEngine::with_derive_key_context(context);
+        engine.input(key_material);
+        from_engine(engine).0
+    }
+}
+
+/// Hashes a key derivation context string into the context key used by
+/// [`Mode::DeriveKeyMaterial`].
+pub fn hash_derive_key_context(context: &str) -> [u8; KEY_LEN] {
+    let mut engine = HashEngine::new_internal(IV, DERIVE_KEY_CONTEXT);
+    engine.input(context.as_bytes());
+    from_engine(engine).0
+}
+
+/// Returns the length, in bytes, of the left subtree of a node covering `input_len` bytes.
+///
+/// # Panics
+///
+/// If `input_len` is not larger than [`CHUNK_LEN`], in which case the input is a single chunk.
+pub fn left_subtree_len(input_len: u64) -> u64 {
+    assert!(input_len > CHUNK_LEN as u64, "a single chunk has no subtrees");
+    // The largest power of two number of chunks that leaves at least one byte on the right.
+    ((input_len + 1) / 2).next_power_of_two()
+}
+
+/// Returns the maximum length of a subtree starting at `input_offset`, which must be a multiple
+/// of [`CHUNK_LEN`], or `None` for an offset of zero where there is no limit.
+pub fn max_subtree_len(input_offset: u64) -> Option<u64> {
+    if input_offset == 0 {
+        return None;
+    }
+    let chunks = input_offset / CHUNK_LEN as u64;
+    Some((1u64 << chunks.trailing_zeros()) * CHUNK_LEN as u64)
+}
+
+fn merge_subtrees(left_child: &ChainingValue, right_child: &ChainingValue, mode: Mode) -> Output {
+    parent_output(
+        &words_from_le_bytes(left_child),
+        &words_from_le_bytes(right_child),
+        &mode.key_words(),
+        mode.flags(),
+    )
+}
+
+/// Combines the chaining values of two sibling subtrees into the chaining value of their parent.
+pub fn merge_subtrees_non_root(
+    left_child: &ChainingValue,
+    right_child: &ChainingValue,
+    mode: Mode,
+) -> ChainingValue {
+    le_bytes_from_words(&merge_subtrees(left_child, right_child, mode).chaining_value())
+}
+
+/// Combines the chaining values of the two children of the root into the final hash.
+pub fn merge_subtrees_root(
+    left_child: &ChainingValue,
+    right_child: &ChainingValue,
+    mode: Mode,
+) -> Hash {
+    let mut ret = [0; 32];
+    merge_subtrees_root_xof(left_child, right_child, mode).squeeze(&mut ret);
+    Hash(ret)
+}
+
+/// Combines the chaining values of the two children of the root into an extendable output.
+pub fn merge_subtrees_root_xof(
+    left_child: &ChainingValue,
+    right_child: &ChainingValue,
+    mode: Mode,
+) -> XofReader {
+    XofReader::new(merge_subtrees(left_child, right_child, mode))
+}
+
+#[cfg(test)]
+mod tests {
+    // Input of the official test vectors: the byte sequence 0, 1, ..., 250, 0, 1, ...
+    fn test_input(out: &mut [u8]) {
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = (i % 251) as u8;
+        }
+    }
+
+    const TEST_KEY: &[u8; 32] = b"whats the Elvish word for friend";
+    const TEST_CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    #[test]
+    fn test() {
+        use crate::{blake3, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        // From test_vectors.json of the BLAKE3 reference implementation: input length, then
+        // the first 32 bytes of the hash, keyed_hash and derive_key outputs.
+        let tests = [
+            (0, "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+                "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26",
+                "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d"),
+            (1, "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213",
+                "6d7878dfff2f485635d39013278ae14f1454b8c0a3a2d34bc1ab38228a80c95b",
+                "b3e2e340a117a499c6cf2398a19ee0d29cca2bb7404c73063382693bf66cb06c"),
+            (63, "e9bc37a594daad83be9470df7f7b3798297c3d834ce80ba85d6e207627b7db7b",
+                "bb1eb5d4afa793c1ebdd9fb08def6c36d10096986ae0cfe148cd101170ce37ae",
+                "b6451e30b953c206e34644c6803724e9d2725e0893039cfc49584f991f451af3"),
+            (64, "4eed7141ea4a5cd4b788606bd23f46e212af9cacebacdc7d1f4c6dc7f2511b98",
+                "ba8ced36f327700d213f120b1a207a3b8c04330528586f414d09f2f7d9ccb7e6",
+                "a5c4a7053fa86b64746d4bb688d06ad1f02a18fce9afd3e818fefaa7126bf73e"),
+            (65, "de1e5fa0be70df6d2be8fffd0e99ceaa8eb6e8c93a63f2d8d1c30ecb6b263dee",
+                "c0a4edefa2d2accb9277c371ac12fcdbb52988a86edc54f0716e1591b4326e72",
+                "51fd05c3c1cfbc8ed67d139ad76f5cf8236cd2acd26627a30c104dfd9d3ff8a8"),
+            (1023, "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11",
+                "c951ecdf03288d0fcc96ee3413563d8a6d3589547f2c2fb36d9786470f1b9d6e",
+                "74a16c1c3d44368a86e1ca6df64be6a2f64cce8f09220787450722d85725dea5"),
+            (1024, "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7",
+                "75c46f6f3d9eb4f55ecaaee480db732e6c2105546f1e675003687c31719c7ba4",
+                "7356cd7720d5b66b6d0697eb3177d9f8d73a4a5c5e968896eb6a689684302706"),
+            (1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444",
+                "357dc55de0c7e382c900fd6e320acc04146be01db6a8ce7210b7189bd664ea69",
+                "effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb"),
+            (2048, "e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a",
+                "879cf1fa2ea0e79126cb1063617a05b6ad9d0b696d0d757cf053439f60a99dd1",
+                "7b2945cb4fef70885cc5d78a87bf6f6207dd901ff239201351ffac04e1088a23"),
+            (2049, "5f4d72f40d7a5f82b15ca2b2e44b1de3c2ef86c426c95c1af0b6879522563030",
+                "9f29700902f7c86e514ddc4df1e3049f258b2472b6dd5267f61bf13983b78dd5",
+                "2ea477c5515cc3dd606512ee72bb3e0e758cfae7232826f35fb98ca1bcbdf273"),
+            (3072, "b98cb0ff3623be03326b373de6b9095218513e64f1ee2edd2525c7ad1e5cffd2",
+                "044a0e7b172a312dc02a4c9a818c036ffa2776368d7f528268d2e6b5df191770",
+                "050df97f8c2ead654d9bb3ab8c9178edcd902a32f8495949feadcc1e0480c46b"),
+            (3073, "7124b49501012f81cc7f11ca069ec9226cecb8a2c850cfe644e327d22d3e1cd3",
+                "68dede9bef00ba89e43f31a6825f4cf433389fedae75c04ee9f0cf16a427c95a",
+                "72613c9ec9ff7e40f8f5c173784c532ad852e827dba2bf85b2ab4b76f7079081"),
+            (4096, "015094013f57a5277b59d8475c0501042c0b642e531b0a1c8f58d2163229e969",
+                "befc660aea2f1718884cd8deb9902811d332f4fc4a38cf7c7300d597a081bfc0",
+                "1e0d7f3db8c414c97c6307cbda6cd27ac3b030949da8e23be1a1a924ad2f25b9"),
+            (4097, "9b4052b38f1c5fc8b1f9ff7ac7b27cd242487b3d890d15c96a1c25b8aa0fb995",
+                "00df940cd36bb9fa7cbbc3556744e0dbc8191401afe70520ba292ee3ca80abbc",
+                "aca51029626b55fda7117b42a7c211f8c6e9ba4fe5b7a8ca922f34299500ead8"),
+            (8192, "aae792484c8efe4f19e2ca7d371d8c467ffb10748d8a5a1ae579948f718a2a63",
+                "dc9637c8845a770b4cbf76b8daec0eebf7dc2eac11498517f08d44c8fc00d58a",
+                "ad01d7ae4ad059b0d33baa3c01319dcf8088094d0359e5fd45d6aeaa8b2d0c3d"),
+            (8193, "bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3b",
+                "954a2a75420c8d6547e3ba5b98d963e6fa6491addc8c023189cc519821b4a1f5",
+                "af1e0346e389b17c23200270a64aa4e1ead98c61695d917de7d5b00491c9b0f1"),
+            (31744, "62b6960e1a44bcc1eb1a611a8d6235b6b4b78f32e7abc4fb4c6cdcce94895c47",
+                "efa53b389ab67c593dba624d898d0f7353ab99e4ac9d42302ee64cbf9939a419",
+                "39772aef80e0ebe60596361e45b061e8f417429d529171b6764468c22928e28e"),
+            (102400, "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085",
+                "1c35d1a5811083fd7119f5d5d1ba027b4d01c0c6c49fb6ff2cf75393ea5db4a7",
+                "4652cff7a3f385a6103b5c260fc1593e13c778dbe608efb092fe7ee69df6e9c6"),
+        ];
+
+        let mut input = [0u8; 102400];
+        test_input(&mut input);
+        for &(len, hash, keyed_hash, derive_key) in tests.iter() {
+            let input = &input[..len];
+
+            let expected = blake3::Hash::from_hex(hash).expect("parse hex");
+            assert_eq!(blake3::Hash::hash(input), expected);
+
+            // Hash through engine, with input that isn't aligned to blocks or chunks
+            let mut engine = blake3::Hash::engine();
+            for chunk in input.chunks(97) {
+                engine.input(chunk);
+            }
+            assert_eq!(engine.n_bytes_hashed(), len);
+            assert_eq!(blake3::Hash::from_engine(engine), expected);
+
+            let expected = blake3::Hash::from_hex(keyed_hash).expect("parse hex");
+            assert_eq!(blake3::Hash::hash_with_key(TEST_KEY, input), expected);
+
+            let expected = <[u8; 32]>::from_hex(derive_key).expect("parse hex");
+            assert_eq!(blake3::Hash::derive_key(TEST_CONTEXT, input), expected);
+        }
+    }
+
+    #[test]
+    fn xof() {
+        use crate::{blake3, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let mut input = [0u8; 1025];
+        test_input(&mut input);
+        let mut engine = blake3::Hash::engine();
+        engine.input(&input);
+
+        // The extended output of the official test vectors is 131 bytes long.
+        let mut out = [0u8; 131];
+        engine.clone().finalize_xof().squeeze(&mut out);
+        let expected = [
+            0xd0, 0x02, 0x78, 0xae, 0x47, 0xeb, 0x27, 0xb3,
+            0x4f, 0xae, 0xcf, 0x67, 0xb4, 0xfe, 0x26, 0x3f,
+            0x82, 0xd5, 0x41, 0x29, 0x16, 0xc1, 0xff, 0xd9,
+            0x7c, 0x8c, 0xb7, 0xfb, 0x81, 0x4b, 0x84, 0x44,
+            0xf4, 0xc4, 0xa2, 0x2b, 0x4b, 0x39, 0x91, 0x55,
+            0x35, 0x8a, 0x99, 0x4e, 0x52, 0xbf, 0x25, 0x5d,
+            0xe6, 0x00, 0x35, 0x74, 0x2e, 0xc7, 0x1b, 0xd0,
+            0x8a, 0xc2, 0x75, 0xa1, 0xb5, 0x1c, 0xc6, 0xbf,
+            0xe3, 0x32, 0xb0, 0xef, 0x84, 0xb4, 0x09, 0x10,
+            0x8c, 0xda, 0x08, 0x0e, 0x62, 0x69, 0xed, 0x4b,
+            0x3e, 0x2c, 0x3f, 0x7d, 0x72, 0x2a, 0xa4, 0xcd,
+            0xc9, 0x8d, 0x16, 0xde, 0xb5, 0x54, 0xe5, 0x62,
+            0x7b, 0xe8, 0xf9, 0x55, 0xc9, 0x8e, 0x1d, 0x5f,
+            0x95, 0x65, 0xa9, 0x19, 0x4c, 0xad, 0x0c, 0x42,
+            0x85, 0xf9, 0x37, 0x00, 0x06, 0x2d, 0x95, 0x95,
+            0xad, 0xb9, 0x92, 0xae, 0x68, 0xff, 0x12, 0x80,
+            0x0a, 0xb6, 0x7a,
+        ];
+        assert_eq!(&out[..], &expected[..]);
+
+        let mut reader = engine.finalize_xof();
+        let mut pieces = [0u8; 131];
+        for chunk in pieces.chunks_mut(10) {
+            reader.squeeze(chunk);
+        }
+        assert_eq!(&out[..], &pieces[..]);
+        assert_eq!(&out[..32], &blake3::Hash::hash(&input)[..]);
+        assert_eq!(
+            out[..32],
+            <[u8; 32]>::from_hex("d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444").unwrap(),
+        );
+    }
+
+    #[test]
+    fn subtrees() {
+        use crate::{blake3, Hash, HashEngine};
+        use crate::blake3::{Mode, CHUNK_LEN};
+        use crate::hex::FromHex;
+
+        //            root
+        //          /      \
+        //      parent      \
+        //    /       \      \
+        // chunk0  chunk1  chunk2
+        let mut input = [0u8; 2 * CHUNK_LEN + 42];
+        input[..CHUNK_LEN].copy_from_slice(&[b'a'; CHUNK_LEN]);
+        input[CHUNK_LEN..2 * CHUNK_LEN].copy_from_slice(&[b'b'; CHUNK_LEN]);
+        input[2 * CHUNK_LEN..].copy_from_slice(&[b'c'; 42]);
+        let (chunk0, rest) = input.split_at(CHUNK_LEN);
+        let (chunk1, chunk2) = rest.split_at(CHUNK_LEN);
+
+        assert_eq!(blake3::left_subtree_len(input.len() as u64), 2 * CHUNK_LEN as u64);
+        assert_eq!(blake3::max_subtree_len(0), None);
+        assert_eq!(blake3::max_subtree_len(CHUNK_LEN as u64), Some(CHUNK_LEN as u64));
+        assert_eq!(blake3::max_subtree_len(2 * CHUNK_LEN as u64), Some(2 * CHUNK_LEN as u64));
+
+        for &mode in &[Mode::Hash, Mode::KeyedHash(*TEST_KEY)] {
+            let mut engine = blake3::HashEngine::with_mode(mode);
+            engine.input(chunk0);
+            let chunk0_cv = engine.finalize_non_root();
+
+            let mut engine = blake3::HashEngine::with_mode(mode);
+            engine.set_input_offset(CHUNK_LEN as u64);
+            engine.input(chunk1);
+            let chunk1_cv = engine.finalize_non_root();
+
+            let mut engine = blake3::HashEngine::with_mode(mode);
+            engine.set_input_offset(2 * CHUNK_LEN as u64);
+            engine.input(chunk2);
+            let chunk2_cv = engine.finalize_non_root();
+
+            let parent_cv = blake3::merge_subtrees_non_root(&chunk0_cv, &chunk1_cv, mode);
+            let root = blake3::merge_subtrees_root(&parent_cv, &chunk2_cv, mode);
+
+            // The left subtree can also be hashed in one go.
+            let mut engine = blake3::HashEngine::with_mode(mode);
+            engine.input(&input[..2 * CHUNK_LEN]);
+            assert_eq!(engine.finalize_non_root(), parent_cv);
+
+            let mut engine = blake3::HashEngine::with_mode(mode);
+            engine.input(&input);
+            assert_eq!(blake3::Hash::from_engine(engine), root);
+
+            let expected = match mode {
+                Mode::Hash => {
+                    assert_eq!(
+                        chunk0_cv,
+                        <[u8; 32]>::from_hex("537d1afdfed07c773c287fcfe42f8495b9e7356003fb639450f4de0b373ee4a8").unwrap(),
+                    );
+                    "e9fb71f7f88e26080891909724468f780db8eecc807c8abf1685e41fcaf38a3d"
+                }
+                _ => "0fad3f1c25a79ba4cff8e99042432f66a7ddb25975ddad70f08512166a8bfcb9",
+            };
+            assert_eq!(root, blake3::Hash::from_hex(expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn derive_key_mode() {
+        use crate::{blake3, Hash, HashEngine};
+
+        let context_key = blake3::hash_derive_key_context(TEST_CONTEXT);
+        let mut engine = blake3::HashEngine::with_mode(blake3::Mode::DeriveKeyMaterial(context_key));
+        engine.input(b"key material");
+        assert_eq!(
+            blake3::Hash::from_engine(engine).into_inner(),
+            blake3::Hash::derive_key(TEST_CONTEXT, b"key material"),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn unaligned_input_offset() {
+        use crate::blake3;
+
+        blake3::HashEngine::default().set_input_offset(100);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn blake3_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{blake3, Hash};
+
+        static HASH_BYTES: [u8; 32] = [
+            0xaf, 0x13, 0x49, 0xb9, 0xf5, 0xf9, 0xa1, 0xa6,
+            0xa0, 0x40, 0x4d, 0xea, 0x36, 0xdc, 0xc9, 0x49,
+            0x9b, 0xcb, 0x25, 0xc9, 0xad, 0xc1, 0x12, 0xb7,
+            0xcc, 0x9a, 0x93, 0xca, 0xe4, 0x1f, 0x32, 0x62,
+        ];
+
+        let hash = blake3::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(&hash.readable(), &[Token::Str("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262")]);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, blake3};
+
+    #[bench]
+    pub fn blake3_10(bh: &mut Bencher) {
+        let mut engine = blake3::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn blake3_1k(bh: &mut Bencher) {
+        let mut engine = blake3::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn blake3_64k(bh: &mut Bencher) {
+        let mut engine = blake3::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
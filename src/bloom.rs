@@ -0,0 +1,322 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BIP37 bloom filters.
+//!
+//! Implementation of the connection bloom filters of BIP37, as sent in `filterload` messages,
+//! using [`murmur3`] as the hash function.
+//!
+
+#[cfg(feature = "std")]
+use core::{cmp, f64::consts::LN_2};
+
+use crate::alloc::vec::Vec;
+
+use crate::{murmur3, sha256d};
+
+/// Maximum size of a filter, in bytes, that peers accept.
+pub const MAX_BLOOM_FILTER_SIZE: usize = 36_000;
+/// Maximum number of hash functions of a filter that peers accept.
+pub const MAX_HASH_FUNCS: u32 = 50;
+
+// Multiplier of the hash function number in the seed of each hash function.
+const SEED_MULTIPLIER: u32 = 0xfba4c795;
+
+#[cfg(feature = "std")]
+const LN2_SQUARED: f64 = LN_2 * LN_2;
+
+/// How a peer updates a filter when an output of a transaction matches it.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum BloomFlags {
+    /// Never update the filter (`BLOOM_UPDATE_NONE`).
+    None,
+    /// Insert the outpoint of every matching output (`BLOOM_UPDATE_ALL`).
+    All,
+    /// Insert the outpoint of matching pay-to-pubkey and bare multisig outputs only
+    /// (`BLOOM_UPDATE_P2PUBKEY_ONLY`).
+    PubkeyOnly,
+}
+
+impl BloomFlags {
+    /// Returns the flags encoded in the `nFlags` byte of a `filterload` message, or `None` for
+    /// an undefined value.
+    pub fn from_u8(flags: u8) -> Option<BloomFlags> {
+        match flags {
+            0 => Some(BloomFlags::None),
+            1 => Some(BloomFlags::All),
+            2 => Some(BloomFlags::PubkeyOnly),
+            _ => None,
+        }
+    }
+
+    /// Returns the `nFlags` byte of a `filterload` message.
+    pub fn to_u8(self) -> u8 {
+        match self {
+            BloomFlags::None => 0,
+            BloomFlags::All => 1,
+            BloomFlags::PubkeyOnly => 2,
+        }
+    }
+
+    /// Returns whether the outpoint of an output whose script matched the filter must be
+    /// inserted into the filter. `is_pubkey_or_multisig` tells whether the output script is
+    /// pay-to-pubkey or bare multisig.
+    pub fn updates_outpoint(self, is_pubkey_or_multisig: bool) -> bool {
+        match self {
+            BloomFlags::None => false,
+            BloomFlags::All => true,
+            BloomFlags::PubkeyOnly => is_pubkey_or_multisig,
+        }
+    }
+}
+
+/// A BIP37 bloom filter.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BloomFilter {
+    data: Vec<u8>,
+    n_hash_funcs: u32,
+    n_tweak: u32,
+    flags: BloomFlags,
+}
+
+impl BloomFilter {
+    /// Creates a filter sized for `n_elements` elements with a false positive rate of about
+    /// `fp_rate`, using the same parameters as Bitcoin Core. The result is clamped to
+    /// [`MAX_BLOOM_FILTER_SIZE`] and [`MAX_HASH_FUNCS`].
+    ///
+    /// # Panics
+    ///
+    /// If `n_elements` is zero.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn new(n_elements: u32, fp_rate: f64, n_tweak: u32, flags: BloomFlags) -> BloomFilter {
+        assert!(n_elements > 0, "a bloom filter needs at least one element");
+
+        let n_bits = (-1.0 / LN2_SQUARED * n_elements as f64 * fp_rate.ln()) as u32;
+        let n_bytes = cmp::min(n_bits as usize, MAX_BLOOM_FILTER_SIZE * 8) / 8;
+        // Note that the division is integer division, as in Bitcoin Core.
+        let n_hash_funcs = ((n_bytes * 8) as u32 / n_elements) as f64 * LN_2;
+        BloomFilter {
+            data: vec![0; n_bytes],
+            n_hash_funcs: cmp::min(n_hash_funcs as u32, MAX_HASH_FUNCS),
+            n_tweak,
+            flags,
+        }
+    }
+
+    /// Creates a filter from the fields of a `filterload` message.
+    ///
+    /// The parameters are not checked, see [`BloomFilter::is_within_size_constraints`].
+    pub fn from_parts(data: Vec<u8>, n_hash_funcs: u32, n_tweak: u32, flags: BloomFlags) -> BloomFilter {
+        BloomFilter { data, n_hash_funcs, n_tweak, flags }
+    }
+
+    /// Returns the bit field of the filter.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the number of hash functions of the filter.
+    pub fn n_hash_funcs(&self) -> u32 {
+        self.n_hash_funcs
+    }
+
+    /// Returns the random value added to the seed of each hash function.
+    pub fn n_tweak(&self) -> u32 {
+        self.n_tweak
+    }
+
+    /// Returns the update flags of the filter.
+    pub fn flags(&self) -> BloomFlags {
+        self.flags
+    }
+
+    /// Returns whether the filter satisfies the size limits of BIP37.
+    pub fn is_within_size_constraints(&self) -> bool {
+        self.data.len() <= MAX_BLOOM_FILTER_SIZE && self.n_hash_funcs <= MAX_HASH_FUNCS
+    }
+
+    fn bit_index(&self, n_hash_num: u32, key: &[u8]) -> usize {
+        let seed = n_hash_num.wrapping_mul(SEED_MULTIPLIER).wrapping_add(self.n_tweak);
+        let hash = murmur3::Hash::hash_to_u32_with_seed(seed, key);
+        (hash as u64 % (self.data.len() as u64 * 8)) as usize
+    }
+
+    /// Inserts `key` into the filter.
+    pub fn insert(&mut self, key: &[u8]) {
+        // Avoid dividing by zero (CVE-2013-5700).
+        if self.data.is_empty() {
+            return;
+        }
+        for i in 0..self.n_hash_funcs {
+            let index = self.bit_index(i, key);
+            self.data[index >> 3] |= 1 << (index & 7);
+        }
+    }
+
+    /// Returns whether `key` may have been inserted into the filter. An empty filter matches
+    /// everything.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        if self.data.is_empty() {
+            return true;
+        }
+        (0..self.n_hash_funcs).all(|i| {
+            let index = self.bit_index(i, key);
+            self.data[index >> 3] & (1 << (index & 7)) != 0
+        })
+    }
+
+    /// Inserts the outpoint given by `txid` and `vout` into the filter.
+    pub fn insert_outpoint(&mut self, txid: &sha256d::Hash, vout: u32) {
+        self.insert(&outpoint_bytes(txid, vout));
+    }
+
+    /// Returns whether the outpoint given by `txid` and `vout` may have been inserted into the
+    /// filter.
+    pub fn contains_outpoint(&self, txid: &sha256d::Hash, vout: u32) -> bool {
+        self.contains(&outpoint_bytes(txid, vout))
+    }
+
+    /// Serializes the filter as the payload of a `filterload` message.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(self.data.len() + 14);
+        write_compact_size(&mut ret, self.data.len() as u64);
+        ret.extend_from_slice(&self.data);
+        ret.extend_from_slice(&self.n_hash_funcs.to_le_bytes());
+        ret.extend_from_slice(&self.n_tweak.to_le_bytes());
+        ret.push(self.flags.to_u8());
+        ret
+    }
+}
+
+fn outpoint_bytes(txid: &sha256d::Hash, vout: u32) -> [u8; 36] {
+    let mut ret = [0; 36];
+    ret[..32].copy_from_slice(&txid[..]);
+    ret[32..].copy_from_slice(&vout.to_le_bytes());
+    ret
+}
+
+pub(crate) fn write_compact_size(out: &mut Vec<u8>, n: u64) {
+    if n < 0xfd {
+        out.push(n as u8);
+    } else if n <= 0xffff {
+        out.push(0xfd);
+        out.extend_from_slice(&(n as u16).to_le_bytes());
+    } else if n <= 0xffff_ffff {
+        out.push(0xfe);
+        out.extend_from_slice(&(n as u32).to_le_bytes());
+    } else {
+        out.push(0xff);
+        out.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::{FromHex, ToHex};
+    use crate::Hash;
+
+    // Test vectors from Bitcoin Core's bloom_tests.cpp
+    #[test]
+    #[cfg(feature = "std")]
+    fn bloom_create_insert_serialize() {
+        let mut filter = BloomFilter::new(3, 0.01, 0, BloomFlags::All);
+
+        let key = Vec::from_hex("99108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap();
+        filter.insert(&key);
+        assert!(filter.contains(&key));
+        // One bit different in first byte
+        assert!(!filter.contains(&Vec::from_hex("19108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap()));
+
+        let key = Vec::from_hex("b5a2c786d9ef4658287ced5914b37a1b4aa32eee").unwrap();
+        filter.insert(&key);
+        assert!(filter.contains(&key));
+
+        let key = Vec::from_hex("b9300670b4c5366e95b2699e8b18bc75e5f729c5").unwrap();
+        filter.insert(&key);
+        assert!(filter.contains(&key));
+
+        assert_eq!(filter.serialize().to_hex(), "03614e9b050000000000000001");
+        assert!(filter.is_within_size_constraints());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn bloom_create_insert_serialize_with_tweak() {
+        // Same test as bloom_create_insert_serialize, but with a tweak
+        let mut filter = BloomFilter::new(3, 0.01, 2147483649, BloomFlags::All);
+
+        let key = Vec::from_hex("99108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap();
+        filter.insert(&key);
+        assert!(filter.contains(&key));
+        assert!(!filter.contains(&Vec::from_hex("19108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap()));
+
+        filter.insert(&Vec::from_hex("b5a2c786d9ef4658287ced5914b37a1b4aa32eee").unwrap());
+        filter.insert(&Vec::from_hex("b9300670b4c5366e95b2699e8b18bc75e5f729c5").unwrap());
+
+        assert_eq!(filter.serialize().to_hex(), "03ce4299050000000100008001");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn bloom_create_insert_key() {
+        // Public key of the WIF private key 5Kg1gnAjaLfKiwhhPpGS3QfRg2m6awQvaj98JCZBZQ5SuS2F15C
+        let pubkey = Vec::from_hex(
+            "045b81f0017e2091e2edcd5eecf10d5bdd120a5514cb3ee65b8447ec18bfc4575c\
+             6d5bf415e54e03b1067934a0f0ba76b01c6b9ab227142ee1d543764b69d901e0"
+        ).unwrap();
+
+        let mut filter = BloomFilter::new(2, 0.001, 0, BloomFlags::All);
+        filter.insert(&pubkey);
+        filter.insert(&crate::hash160::Hash::hash(&pubkey)[..]);
+
+        assert_eq!(filter.serialize().to_hex(), "038fc16b080000000000000001");
+    }
+
+    #[test]
+    fn from_parts() {
+        let data = Vec::from_hex("614e9b").unwrap();
+        let mut filter = BloomFilter::from_parts(data, 5, 0, BloomFlags::All);
+        assert!(filter.contains(&Vec::from_hex("99108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap()));
+        assert!(!filter.contains(&Vec::from_hex("19108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap()));
+        assert_eq!(filter.serialize().to_hex(), "03614e9b050000000000000001");
+
+        let txid = sha256d::Hash::hash(b"transaction");
+        filter.insert_outpoint(&txid, 1);
+        assert!(filter.contains_outpoint(&txid, 1));
+
+        let oversized = BloomFilter::from_parts(vec![0; MAX_BLOOM_FILTER_SIZE + 1], 5, 0, BloomFlags::None);
+        assert!(!oversized.is_within_size_constraints());
+
+        // An empty filter matches everything and ignores insertions.
+        let mut empty = BloomFilter::from_parts(Vec::new(), 5, 0, BloomFlags::None);
+        empty.insert(b"key");
+        assert!(empty.contains(b"anything"));
+        assert!(empty.data().is_empty());
+    }
+
+    #[test]
+    fn flags() {
+        for flags in &[BloomFlags::None, BloomFlags::All, BloomFlags::PubkeyOnly] {
+            assert_eq!(BloomFlags::from_u8(flags.to_u8()), Some(*flags));
+        }
+        assert_eq!(BloomFlags::from_u8(3), None);
+
+        assert!(!BloomFlags::None.updates_outpoint(true));
+        assert!(BloomFlags::All.updates_outpoint(false));
+        assert!(BloomFlags::PubkeyOnly.updates_outpoint(true));
+        assert!(!BloomFlags::PubkeyOnly.updates_outpoint(false));
+    }
+}
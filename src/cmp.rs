@@ -1,5 +1,20 @@
 //! Useful comparison functions.
 
+/// Equality comparison which takes the same time regardless of the values being compared.
+///
+/// The [`PartialEq`] implementations of the hash types return as soon as a byte differs, so
+/// comparing a secret value such as an HMAC tag or a hashlock preimage with `==` leaks timing
+/// information. This trait is implemented for all hash types, [`Hmac`](crate::Hmac),
+/// [`sha256::Midstate`](crate::sha256::Midstate) and types created by
+/// [`hash_newtype!`](crate::hash_newtype).
+///
+/// With the `subtle` feature enabled, the same types also implement `subtle::ConstantTimeEq`,
+/// whose `ct_eq` method returns a `subtle::Choice` instead of a `bool`.
+pub trait ConstantTimeEq {
+    /// Returns `true` if `self` and `other` are equal, in fixed time.
+    fn fixed_time_eq(&self, other: &Self) -> bool;
+}
+
 /// Compare two slices for equality in fixed time. Panics if the slices are of non-equal length.
 ///
 /// This works by XOR'ing each byte of the two inputs together and keeping an OR counter of the
@@ -79,6 +94,55 @@ fn eq_test() {
     assert!(!fixed_time_eq(&[0b00000000, 0b00000000], &[0b00000001, 0b00000001]));
 }
 
+#[test]
+fn ct_eq_test() {
+    use crate::{Hash, Hmac, HmacEngine, HashEngine, sha256, sha256d, sha512};
+
+    let a = sha256::Hash::hash(&[0; 1]);
+    let b = sha256::Hash::hash(&[1; 1]);
+    assert!(a.fixed_time_eq(&a));
+    assert!(!a.fixed_time_eq(&b));
+
+    let a = sha512::Hash::hash(&[0; 1]);
+    let b = sha512::Hash::hash(&[1; 1]);
+    assert!(a.fixed_time_eq(&a));
+    assert!(!a.fixed_time_eq(&b));
+
+    let a = Hmac::<sha256::Hash>::from_engine(HmacEngine::new(&[0; 1]));
+    let b = Hmac::<sha256::Hash>::from_engine(HmacEngine::new(&[1; 1]));
+    assert!(a.fixed_time_eq(&a));
+    assert!(!a.fixed_time_eq(&b));
+
+    let mut engine = sha256::Hash::engine();
+    let a = engine.midstate();
+    engine.input(&[0; 64]);
+    let b = engine.midstate();
+    assert!(a.fixed_time_eq(&a));
+    assert!(!a.fixed_time_eq(&b));
+
+    hash_newtype!(TestNewtype, sha256d::Hash, 32, doc="A test newtype");
+    let a = TestNewtype::hash(&[0; 1]);
+    let b = TestNewtype::hash(&[1; 1]);
+    assert!(a.fixed_time_eq(&a));
+    assert!(!a.fixed_time_eq(&b));
+}
+
+#[cfg(feature = "subtle")]
+#[test]
+fn subtle_ct_eq_test() {
+    use crate::{Hash, sha256};
+
+    let a = sha256::Hash::hash(&[0; 1]);
+    let b = sha256::Hash::hash(&[1; 1]);
+    assert!(bool::from(subtle::ConstantTimeEq::ct_eq(&a, &a)));
+    assert!(!bool::from(subtle::ConstantTimeEq::ct_eq(&a, &b)));
+
+    // Both traits can be in scope at once.
+    use subtle::ConstantTimeEq as _;
+    assert!(bool::from(a.ct_eq(&a)));
+    assert!(a.fixed_time_eq(&a));
+}
+
 #[cfg(bench)]
 mod benches {
     use test::Bencher;
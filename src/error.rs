@@ -23,12 +23,15 @@ use core::fmt;
 pub enum Error {
     /// Tried to create a fixed-length hash from a slice with the wrong size (expected, got).
     InvalidLength(usize, usize),
+    /// An HMAC tag did not match the expected value.
+    InvalidMac,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::InvalidLength(ell, ell2) => write!(f, "bad slice length {} (expected {})", ell2, ell),
+            Error::InvalidMac => f.write_str("HMAC verification failed"),
         }
     }
 }
@@ -0,0 +1,516 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BIP158 Golomb-coded set compact block filters.
+//!
+//! A Golomb-coded set (GCS) is a probabilistic set: every element is hashed with [`siphash24`]
+//! and mapped uniformly into the range `[0, N * M)`, where `N` is the number of elements, and
+//! the sorted values are stored as Golomb-Rice coded differences. Querying an element that is
+//! not in the set matches with a probability of about `1 / M`.
+//!
+//! The basic block filters of BIP158 use [`P`] and [`M`] as parameters and key the hash function
+//! with the first 16 bytes of the block hash. Filters are committed to by a chain of filter
+//! headers, see [`BlockFilter::filter_header`].
+//!
+
+use core::fmt;
+
+use crate::alloc::vec::Vec;
+
+use crate::{sha256d, siphash24, Hash, HashEngine};
+
+/// Golomb-Rice coding parameter of basic block filters.
+pub const P: u8 = 19;
+/// Inverse false positive rate of basic block filters.
+pub const M: u64 = 784931;
+
+/// The `OP_RETURN` opcode, output scripts starting with it are not added to basic filters.
+const OP_RETURN: u8 = 0x6a;
+
+hash_newtype!(FilterHash, sha256d::Hash, 32, doc="Hash of a BIP158 filter, as committed to by filter headers.");
+hash_newtype!(FilterHeader, sha256d::Hash, 32, doc="BIP157 filter header, committing to a filter and all previous filter headers.");
+
+/// Golomb-coded set error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The filter ended before all of its elements were decoded.
+    UnexpectedEnd,
+    /// The number of elements of the filter times `M` does not fit in 64 bits.
+    TooManyElements(u64),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::UnexpectedEnd => f.write_str("unexpected end of filter"),
+            Error::TooManyElements(n) => write!(f, "too many elements in filter: {}", n),
+        }
+    }
+}
+
+/// Parameters of a Golomb-coded set: the SipHash-2-4 keys, the Golomb-Rice coding parameter `P`
+/// and the inverse false positive rate `M`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GcsFilter {
+    k0: u64,
+    k1: u64,
+    p: u8,
+    m: u64,
+}
+
+impl GcsFilter {
+    /// Creates the filter parameters from the SipHash-2-4 keys, `p` and `m`.
+    ///
+    /// # Panics
+    ///
+    /// If `p` is not less than 64.
+    pub fn new(k0: u64, k1: u64, p: u8, m: u64) -> GcsFilter {
+        assert!(p < 64, "Golomb-Rice parameter must be less than 64");
+        GcsFilter { k0, k1, p, m }
+    }
+
+    /// Creates the parameters of the basic filter of the block with hash `block_hash`.
+    pub fn basic(block_hash: &sha256d::Hash) -> GcsFilter {
+        let mut k0 = [0; 8];
+        let mut k1 = [0; 8];
+        k0.copy_from_slice(&block_hash[0..8]);
+        k1.copy_from_slice(&block_hash[8..16]);
+        GcsFilter::new(u64::from_le_bytes(k0), u64::from_le_bytes(k1), P, M)
+    }
+
+    /// Encodes the set of `elements`, duplicates are only added once.
+    ///
+    /// # Panics
+    ///
+    /// If the number of distinct elements times `M` does not fit in 64 bits.
+    pub fn build<'a, I>(&self, elements: I) -> Vec<u8>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let mut elements = elements.into_iter().collect::<Vec<_>>();
+        elements.sort_unstable();
+        elements.dedup();
+
+        let n = elements.len() as u64;
+        let range = n.checked_mul(self.m).expect("too many elements for the filter parameters");
+        let mut values = elements.iter().map(|e| self.hash_to_range(e, range)).collect::<Vec<_>>();
+        values.sort_unstable();
+
+        let mut out = Vec::new();
+        crate::bloom::write_compact_size(&mut out, n);
+        let mut writer = BitWriter::new(out);
+        let mut last = 0;
+        for value in values {
+            writer.write_golomb_rice(value - last, self.p);
+            last = value;
+        }
+        writer.finish()
+    }
+
+    /// Returns true if any element of `query` matches the encoded `filter`.
+    ///
+    /// An empty query never matches.
+    pub fn match_any<'a, I>(&self, filter: &[u8], query: I) -> Result<bool, Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let (mut reader, query) = self.prepare(filter, query)?;
+
+        let mut query = query.iter().peekable();
+        while let Some(value) = reader.next_value()? {
+            while query.peek().map_or(false, |q| **q < value) {
+                query.next();
+            }
+            match query.peek() {
+                None => return Ok(false),
+                Some(q) if **q == value => return Ok(true),
+                Some(_) => {}
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns true if all elements of `query` match the encoded `filter`.
+    ///
+    /// An empty query always matches.
+    pub fn match_all<'a, I>(&self, filter: &[u8], query: I) -> Result<bool, Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let (mut reader, query) = self.prepare(filter, query)?;
+
+        let mut current = None;
+        for q in query {
+            loop {
+                match current {
+                    Some(value) if value == q => break,
+                    Some(value) if value > q => return Ok(false),
+                    _ => match reader.next_value()? {
+                        Some(value) => current = Some(value),
+                        None => return Ok(false),
+                    },
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads the number of elements of `filter` and maps `query` to the sorted and deduplicated
+    /// values to look for.
+    fn prepare<'a, 'f, I>(&self, filter: &'f [u8], query: I) -> Result<(GcsReader<'f>, Vec<u64>), Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let (n, filter) = read_compact_size(filter).ok_or(Error::UnexpectedEnd)?;
+        let range = n.checked_mul(self.m).ok_or(Error::TooManyElements(n))?;
+
+        let mut values = query.into_iter().map(|e| self.hash_to_range(e, range)).collect::<Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+
+        let reader = GcsReader { bits: BitReader::new(filter), remaining: n, last: 0, p: self.p };
+        Ok((reader, values))
+    }
+
+    /// Hashes `element` and maps the hash uniformly into `[0, range)`.
+    fn hash_to_range(&self, element: &[u8], range: u64) -> u64 {
+        let hash = siphash24::Hash::hash_to_u64_with_keys(self.k0, self.k1, element);
+        ((u128::from(hash) * u128::from(range)) >> 64) as u64
+    }
+}
+
+/// A BIP158 basic block filter.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BlockFilter {
+    content: Vec<u8>,
+}
+
+impl BlockFilter {
+    /// Creates a filter from its serialized content, as sent in `cfilter` messages.
+    pub fn new(content: Vec<u8>) -> BlockFilter {
+        BlockFilter { content }
+    }
+
+    /// Builds the basic filter of the block with hash `block_hash`.
+    ///
+    /// `scripts` must contain the output scripts of all transactions of the block and the
+    /// scripts of all outputs spent by them, except the coinbase input. Empty scripts and
+    /// scripts starting with `OP_RETURN` are skipped.
+    pub fn from_scripts<'a, I>(block_hash: &sha256d::Hash, scripts: I) -> BlockFilter
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let scripts = scripts.into_iter().filter(|s| s.first().map_or(false, |op| *op != OP_RETURN));
+        BlockFilter::new(GcsFilter::basic(block_hash).build(scripts))
+    }
+
+    /// Returns the serialized content of the filter.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Computes the hash of the filter.
+    pub fn filter_hash(&self) -> FilterHash {
+        FilterHash::hash(&self.content)
+    }
+
+    /// Computes the header of the filter given the header of the filter of the previous block,
+    /// that is the hash of the filter hash followed by the previous header.
+    pub fn filter_header(&self, previous_filter_header: &FilterHeader) -> FilterHeader {
+        let mut engine = FilterHeader::engine();
+        engine.input(&self.filter_hash()[..]);
+        engine.input(&previous_filter_header[..]);
+        FilterHeader::from_engine(engine)
+    }
+
+    /// Returns true if any script of `query` matches the filter of the block with hash
+    /// `block_hash`.
+    pub fn match_any<'a, I>(&self, block_hash: &sha256d::Hash, query: I) -> Result<bool, Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        GcsFilter::basic(block_hash).match_any(&self.content, query)
+    }
+
+    /// Returns true if all scripts of `query` match the filter of the block with hash
+    /// `block_hash`.
+    pub fn match_all<'a, I>(&self, block_hash: &sha256d::Hash, query: I) -> Result<bool, Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        GcsFilter::basic(block_hash).match_all(&self.content, query)
+    }
+}
+
+/// Reads a compact size integer from the start of `data`, returning it and the remaining data.
+fn read_compact_size(data: &[u8]) -> Option<(u64, &[u8])> {
+    let (first, rest) = data.split_first()?;
+    let len = match *first {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        n => return Some((u64::from(n), rest)),
+    };
+    if rest.len() < len {
+        return None;
+    }
+    let mut bytes = [0; 8];
+    bytes[..len].copy_from_slice(&rest[..len]);
+    Some((u64::from_le_bytes(bytes), &rest[len..]))
+}
+
+/// Decodes the Golomb-Rice coded values of a filter.
+struct GcsReader<'a> {
+    bits: BitReader<'a>,
+    remaining: u64,
+    last: u64,
+    p: u8,
+}
+
+impl<'a> GcsReader<'a> {
+    /// Returns the next value, or `None` once all values have been read.
+    fn next_value(&mut self) -> Result<Option<u64>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        let mut quotient = 0u64;
+        while self.bits.read_bit()? {
+            quotient += 1;
+        }
+        let delta = (quotient << self.p) | self.bits.read_bits(self.p)?;
+        self.last = self.last.wrapping_add(delta);
+        Ok(Some(self.last))
+    }
+}
+
+/// Reads bits most significant first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data, pos: 0 }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error> {
+        let byte = self.data.get(self.pos / 8).ok_or(Error::UnexpectedEnd)?;
+        let bit = byte & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, n: u8) -> Result<u64, Error> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | u64::from(self.read_bit()?);
+        }
+        Ok(value)
+    }
+}
+
+/// Writes bits most significant first, appending to a buffer.
+struct BitWriter {
+    out: Vec<u8>,
+    n_bits: u8,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> BitWriter {
+        BitWriter { out, n_bits: 0 }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        if self.n_bits == 0 {
+            self.out.push(0);
+        }
+        if bit {
+            *self.out.last_mut().expect("pushed above") |= 0x80 >> self.n_bits;
+        }
+        self.n_bits = (self.n_bits + 1) % 8;
+    }
+
+    fn write_bits(&mut self, value: u64, n: u8) {
+        for i in (0..n).rev() {
+            self.write_bit(value & (1 << i) != 0);
+        }
+    }
+
+    /// Writes the quotient of `value` by `2^p` in unary, followed by the `p` low bits of `value`.
+    fn write_golomb_rice(&mut self, value: u64, p: u8) {
+        for _ in 0..(value >> p) {
+            self.write_bit(true);
+        }
+        self.write_bit(false);
+        self.write_bits(value, p);
+    }
+
+    /// Returns the buffer, the last byte is padded with zero bits.
+    fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::FromHex;
+
+    /// Returns the output scripts of all transactions of a serialized block.
+    fn output_scripts(block: &[u8]) -> Vec<&[u8]> {
+        fn compact_size(data: &mut &[u8]) -> usize {
+            let (n, rest) = read_compact_size(data).unwrap();
+            *data = rest;
+            n as usize
+        }
+
+        fn take<'a>(data: &mut &'a [u8], len: usize) -> &'a [u8] {
+            let (head, rest) = data.split_at(len);
+            *data = rest;
+            head
+        }
+
+        let mut data = &block[80..];
+        let mut scripts = Vec::new();
+        for _ in 0..compact_size(&mut data) {
+            take(&mut data, 4);
+            let segwit = data[0] == 0;
+            if segwit {
+                take(&mut data, 2);
+            }
+            let n_inputs = compact_size(&mut data);
+            for _ in 0..n_inputs {
+                take(&mut data, 36);
+                let len = compact_size(&mut data);
+                take(&mut data, len + 4);
+            }
+            for _ in 0..compact_size(&mut data) {
+                take(&mut data, 8);
+                let len = compact_size(&mut data);
+                scripts.push(take(&mut data, len));
+            }
+            if segwit {
+                for _ in 0..n_inputs {
+                    for _ in 0..compact_size(&mut data) {
+                        let len = compact_size(&mut data);
+                        take(&mut data, len);
+                    }
+                }
+            }
+            take(&mut data, 4);
+        }
+        assert!(data.is_empty());
+        scripts
+    }
+
+    #[test]
+    fn bip158_vectors() {
+        let data = include_str!("../test_data/blockfilters.json");
+        let testdata = serde_json::from_str::<serde_json::Value>(data).unwrap();
+        for t in testdata.as_array().unwrap().iter().skip(1) {
+            let block_hash = sha256d::Hash::from_hex(t[1].as_str().unwrap()).unwrap();
+            let block = Vec::from_hex(t[2].as_str().unwrap()).unwrap();
+            let previous_scripts = t[3].as_array().unwrap().iter()
+                .map(|s| Vec::from_hex(s.as_str().unwrap()).unwrap())
+                .collect::<Vec<_>>();
+            let previous_header = FilterHeader::from_hex(t[4].as_str().unwrap()).unwrap();
+            let expected_filter = Vec::from_hex(t[5].as_str().unwrap()).unwrap();
+            let expected_header = FilterHeader::from_hex(t[6].as_str().unwrap()).unwrap();
+
+            let outputs = output_scripts(&block);
+            let scripts = outputs.iter().copied().chain(previous_scripts.iter().map(|s| &s[..]));
+            let filter = BlockFilter::from_scripts(&block_hash, scripts.clone());
+            assert_eq!(filter.content(), &expected_filter[..], "block {}", t[0]);
+            assert_eq!(filter.filter_header(&previous_header), expected_header, "block {}", t[0]);
+
+            let included = scripts.filter(|s| !s.is_empty() && s[0] != OP_RETURN).collect::<Vec<_>>();
+            assert!(filter.match_all(&block_hash, included.iter().copied()).unwrap());
+            for script in included.iter() {
+                assert!(filter.match_any(&block_hash, Some(*script)).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn match_queries() {
+        let filter = GcsFilter::new(1, 2, P, M);
+        let elements: [&[u8]; 4] = [b"a", b"b", b"c", b"b"];
+        let content = filter.build(elements.iter().copied());
+        // Duplicates are only counted once.
+        assert_eq!(content[0], 3);
+
+        let absent: [&[u8]; 2] = [b"d", b"e"];
+        assert!(filter.match_any(&content, [&b"c"[..], b"d"].iter().copied()).unwrap());
+        assert!(!filter.match_any(&content, absent.iter().copied()).unwrap());
+        assert!(!filter.match_any(&content, None).unwrap());
+
+        assert!(filter.match_all(&content, [&b"c"[..], b"a", b"c"].iter().copied()).unwrap());
+        assert!(!filter.match_all(&content, [&b"c"[..], b"d"].iter().copied()).unwrap());
+        assert!(filter.match_all(&content, None).unwrap());
+
+        // Querying with different keys does not match.
+        let other = GcsFilter::new(2, 1, P, M);
+        assert!(!other.match_all(&content, elements.iter().copied()).unwrap());
+
+        let empty = filter.build(None);
+        assert_eq!(empty, [0]);
+        assert!(!filter.match_any(&empty, elements.iter().copied()).unwrap());
+        assert!(!filter.match_all(&empty, elements.iter().copied()).unwrap());
+    }
+
+    #[test]
+    fn malformed_filters() {
+        let filter = GcsFilter::new(1, 2, P, M);
+        let content = filter.build([&b"a"[..], b"b", b"c"].iter().copied());
+        let query = [&b"z"[..]];
+
+        assert_eq!(filter.match_any(&[], query.iter().copied()), Err(Error::UnexpectedEnd));
+        assert_eq!(filter.match_any(&[0xfd, 0x01], query.iter().copied()), Err(Error::UnexpectedEnd));
+        assert_eq!(filter.match_any(&content[..content.len() - 1], query.iter().copied()), Err(Error::UnexpectedEnd));
+        assert_eq!(filter.match_all(&content[..content.len() - 1], query.iter().copied()), Err(Error::UnexpectedEnd));
+
+        let mut too_many = [0xff; 9];
+        too_many[8] = 0x00;
+        assert_eq!(filter.match_any(&too_many, query.iter().copied()), Err(Error::TooManyElements(0x00ff_ffff_ffff_ffff)));
+    }
+
+    #[test]
+    fn golomb_rice() {
+        let mut writer = BitWriter::new(Vec::new());
+        let values = [0, 1, 7, 8, 9, 100, 3];
+        for value in values.iter() {
+            writer.write_golomb_rice(*value, 3);
+        }
+        let out = writer.finish();
+
+        let mut reader = BitReader::new(&out);
+        for value in values.iter() {
+            let mut quotient = 0;
+            while reader.read_bit().unwrap() {
+                quotient += 1;
+            }
+            assert_eq!((quotient << 3) | reader.read_bits(3).unwrap(), *value);
+        }
+
+        // 0 is `0 000` and 15 is `1 0 111`, the last byte is padded with zeros.
+        let mut writer = BitWriter::new(Vec::new());
+        writer.write_golomb_rice(0, 3);
+        writer.write_golomb_rice(15, 3);
+        assert_eq!(writer.finish(), [0b0000_1011, 0b1000_0000]);
+    }
+}
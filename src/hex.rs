@@ -292,6 +292,7 @@ impl_fromhex_array!(24);
 impl_fromhex_array!(28);
 impl_fromhex_array!(32);
 impl_fromhex_array!(33);
+impl_fromhex_array!(48);
 impl_fromhex_array!(64);
 impl_fromhex_array!(65);
 impl_fromhex_array!(128);
@@ -0,0 +1,251 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! HMAC-based Extract-and-Expand Key Derivation Function (HKDF).
+//!
+//! Implementation of RFC 5869, generic over the underlying hash function. The main API writes
+//! into caller-provided buffers and does not allocate.
+//!
+
+use core::fmt;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::alloc::vec::Vec;
+
+use crate::{Hash, HashEngine, Hmac, HmacEngine};
+
+/// Maximum number of output blocks, since the block counter is a single byte.
+const MAX_OUTPUT_BLOCKS: usize = 255;
+
+/// Requested output is longer than the `255 * T::LEN` bytes HKDF can produce.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MaxLengthError {
+    max: usize,
+}
+
+impl MaxLengthError {
+    /// Returns the maximum output length, in bytes.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl fmt::Display for MaxLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "requested output exceeds the maximum HKDF output length of {} bytes", self.max)
+    }
+}
+
+/// HKDF instance holding the pseudorandom key (PRK) produced by the extract step.
+#[derive(Copy, Clone)]
+pub struct Hkdf<T: Hash> {
+    prk: Hmac<T>,
+}
+
+impl<T: Hash> Hkdf<T> {
+    /// Runs the extract step: computes the PRK from the input keying material `ikm` and `salt`.
+    ///
+    /// An empty `salt` is equivalent to a salt of `T::LEN` zero bytes.
+    pub fn extract(salt: &[u8], ikm: &[u8]) -> Hkdf<T> {
+        let mut engine = HmacEngine::<T>::new(salt);
+        engine.input(ikm);
+        Hkdf { prk: Hmac::from_engine(engine) }
+    }
+
+    /// Creates an instance from a PRK, skipping the extract step.
+    pub fn from_prk(prk: Hmac<T>) -> Hkdf<T> {
+        Hkdf { prk }
+    }
+
+    /// Returns the PRK.
+    pub fn prk(&self) -> Hmac<T> {
+        self.prk
+    }
+
+    /// Returns the maximum output length of the expand step, in bytes.
+    pub fn max_output_len() -> usize {
+        MAX_OUTPUT_BLOCKS * T::LEN
+    }
+
+    /// Runs the expand step: fills `okm` with output keying material bound to `info`.
+    ///
+    /// Fails if `okm` is longer than [`Hkdf::max_output_len`].
+    pub fn expand(&self, info: &[u8], okm: &mut [u8]) -> Result<(), MaxLengthError> {
+        if okm.len() > Self::max_output_len() {
+            return Err(MaxLengthError { max: Self::max_output_len() });
+        }
+
+        let keyed = HmacEngine::<T>::new(&self.prk[..]);
+        let mut prev: Option<Hmac<T>> = None;
+        for (counter, chunk) in (1..=MAX_OUTPUT_BLOCKS as u8).zip(okm.chunks_mut(T::LEN)) {
+            let mut engine = keyed.clone();
+            if let Some(prev) = prev {
+                engine.input(&prev[..]);
+            }
+            engine.input(info);
+            engine.input(&[counter]);
+            let block = Hmac::from_engine(engine);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            prev = Some(block);
+        }
+        Ok(())
+    }
+
+    /// Runs the expand step, returning `len` bytes of output keying material bound to `info`.
+    ///
+    /// Fails if `len` is larger than [`Hkdf::max_output_len`].
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+    pub fn expand_to_len(&self, info: &[u8], len: usize) -> Result<Vec<u8>, MaxLengthError> {
+        let mut okm = crate::alloc::vec![0; len];
+        self.expand(info, &mut okm)?;
+        Ok(okm)
+    }
+}
+
+impl<T: Hash> fmt::Debug for Hkdf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Do not print the key material.
+        f.write_str("Hkdf { .. }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn rfc5869() {
+        use crate::{sha1, sha256};
+        use crate::hex::{FromHex, ToHex};
+
+        struct Test {
+            ikm: &'static str,
+            salt: &'static str,
+            info: &'static str,
+            prk: &'static str,
+            okm: &'static str,
+        }
+
+        // Test cases 1 to 3 of RFC 5869 (SHA256)
+        let tests = vec![
+            Test {
+                ikm: "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+                salt: "000102030405060708090a0b0c",
+                info: "f0f1f2f3f4f5f6f7f8f9",
+                prk: "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+                okm: "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+                      34007208d5b887185865",
+            },
+            Test {
+                ikm: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+                      202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f\
+                      404142434445464748494a4b4c4d4e4f",
+                salt: "606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f\
+                       808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f\
+                       a0a1a2a3a4a5a6a7a8a9aaabacadaeaf",
+                info: "b0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecf\
+                       d0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeef\
+                       f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+                prk: "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244",
+                okm: "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+                      59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+                      cc30c58179ec3e87c14c01d5c1f3434f1d87",
+            },
+            Test {
+                ikm: "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+                salt: "",
+                info: "",
+                prk: "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04",
+                okm: "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+                      9d201395faa4b61a96c8",
+            },
+        ];
+
+        for test in tests {
+            let ikm = Vec::from_hex(test.ikm).unwrap();
+            let salt = Vec::from_hex(test.salt).unwrap();
+            let info = Vec::from_hex(test.info).unwrap();
+            let okm = Vec::from_hex(test.okm).unwrap();
+
+            let hkdf = Hkdf::<sha256::Hash>::extract(&salt, &ikm);
+            assert_eq!(hkdf.prk().to_hex(), test.prk);
+
+            let mut out = vec![0; okm.len()];
+            hkdf.expand(&info, &mut out).unwrap();
+            assert_eq!(out, okm);
+            assert_eq!(hkdf.expand_to_len(&info, okm.len()).unwrap(), okm);
+
+            // A shorter output is a prefix of a longer one.
+            let mut short = [0; 7];
+            hkdf.expand(&info, &mut short).unwrap();
+            assert_eq!(&short[..], &okm[..7]);
+        }
+
+        // Test case 4 of RFC 5869 (SHA1)
+        let hkdf = Hkdf::<sha1::Hash>::extract(
+            &Vec::from_hex("000102030405060708090a0b0c").unwrap(),
+            &Vec::from_hex("0b0b0b0b0b0b0b0b0b0b0b").unwrap(),
+        );
+        assert_eq!(hkdf.prk().to_hex(), "9b6c18c432a7bf8f0e71c8eb88f4b30baa2ba243");
+        assert_eq!(
+            hkdf.expand_to_len(&Vec::from_hex("f0f1f2f3f4f5f6f7f8f9").unwrap(), 42).unwrap().to_hex(),
+            "085a01ea1b10f36933068b56efa5ad81a4f14b822f5b091568a9cdd4f155fda2c22e422478d305f3f896",
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn bip324() {
+        use crate::sha256;
+        use crate::hex::{FromHex, ToHex};
+
+        // Key derivation of the first test vector of BIP324 (packet_encoding_test_vectors.csv),
+        // from the ECDH shared secret and the mainnet network magic.
+        let ecdh = Vec::from_hex("c6992a117f5edbea70c3f511d32d26b9798be4b81a62eaee1a5acaa8459a3592").unwrap();
+        let salt = [&b"bitcoin_v2_shared_secret"[..], &[0xf9, 0xbe, 0xb4, 0xd9]].concat();
+        let hkdf = Hkdf::<sha256::Hash>::extract(&salt, &ecdh);
+
+        let mut key = [0u8; 32];
+        hkdf.expand(b"initiator_L", &mut key).unwrap();
+        assert_eq!(key.to_hex(), "9a6478b5fbab1f4dd2f78994b774c03211c78312786e602da75a0d1767fb55cf");
+        hkdf.expand(b"initiator_P", &mut key).unwrap();
+        assert_eq!(key.to_hex(), "7d0c7820ba6a4d29ce40baf2caa6035e04f1e1cefd59f3e7e59e9e5af84f1f51");
+        hkdf.expand(b"responder_L", &mut key).unwrap();
+        assert_eq!(key.to_hex(), "17bc726421e4054ac6a1d54915085aaa766f4d3cf67bbd168e6080eac289d15e");
+        hkdf.expand(b"responder_P", &mut key).unwrap();
+        assert_eq!(key.to_hex(), "9f0fc1c0e85fd9a8eee07e6fc41dba2ff54c7729068a239ac97c37c524cca1c0");
+        hkdf.expand(b"session_id", &mut key).unwrap();
+        assert_eq!(key.to_hex(), "ce72dffb015da62b0d0f5474cab8bc72605225b0cee3f62312ec680ec5f41ba5");
+
+        let mut garbage_terminators = [0u8; 32];
+        hkdf.expand(b"garbage_terminators", &mut garbage_terminators).unwrap();
+        assert_eq!(garbage_terminators[..16].to_hex(), "faef555dfcdb936425d84aba524758f3");
+        assert_eq!(garbage_terminators[16..].to_hex(), "02cb8ff24307a6e27de3b4e7ea3fa65b");
+    }
+
+    #[test]
+    fn max_length() {
+        use crate::sha256;
+
+        let hkdf = Hkdf::<sha256::Hash>::extract(b"salt", b"ikm");
+        assert_eq!(Hkdf::<sha256::Hash>::max_output_len(), 255 * 32);
+
+        let mut okm = [0u8; 255 * 32 + 1];
+        assert_eq!(hkdf.expand(b"info", &mut okm), Err(MaxLengthError { max: 255 * 32 }));
+        assert!(hkdf.expand(b"info", &mut okm[..255 * 32]).is_ok());
+        assert_ne!(&okm[255 * 32 - 32..255 * 32], &[0u8; 32][..]);
+    }
+}
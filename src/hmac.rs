@@ -20,11 +20,11 @@
 //! Hash-based Message Authentication Code (HMAC).
 //!
 
-use core::{borrow, fmt, ops, str};
+use core::{borrow, cmp, fmt, ops, str};
 #[cfg(feature = "serde")]
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
-use crate::{Error, Hash, HashEngine};
+use crate::{Error, FromMidstate, Hash, HashEngine, hex};
 
 /// A hash computed from a RFC 2104 HMAC. Parameterized by the underlying hash function.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -48,6 +48,65 @@ pub struct HmacMidState<T: Hash> {
     pub outer: <T::Engine as HashEngine>::MidState,
 }
 
+impl<T: Hash> Clone for HmacMidState<T> where <T::Engine as HashEngine>::MidState: Clone {
+    fn clone(&self) -> Self {
+        HmacMidState {
+            inner: self.inner.clone(),
+            outer: self.outer.clone(),
+        }
+    }
+}
+
+impl<T: Hash> Copy for HmacMidState<T> where <T::Engine as HashEngine>::MidState: Copy {}
+
+impl<T: Hash> PartialEq for HmacMidState<T> where <T::Engine as HashEngine>::MidState: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.outer == other.outer
+    }
+}
+
+impl<T: Hash> Eq for HmacMidState<T> where <T::Engine as HashEngine>::MidState: Eq {}
+
+impl<T: Hash> fmt::Debug for HmacMidState<T> where <T::Engine as HashEngine>::MidState: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HmacMidState")
+            .field("inner", &self.inner)
+            .field("outer", &self.outer)
+            .finish()
+    }
+}
+
+/// Formats the inner midstate followed by the outer midstate, each in its own hex format.
+impl<T: Hash> fmt::LowerHex for HmacMidState<T> where <T::Engine as HashEngine>::MidState: fmt::LowerHex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:x}{:x}", self.inner, self.outer)
+    }
+}
+
+impl<T: Hash> fmt::Display for HmacMidState<T> where <T::Engine as HashEngine>::MidState: fmt::LowerHex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl<T: Hash> str::FromStr for HmacMidState<T> where <T::Engine as HashEngine>::MidState: hex::FromHex {
+    type Err = hex::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() % 2 != 0 {
+            return Err(hex::Error::OddLengthString(s.len()));
+        }
+        let mid = s.len() / 2;
+        if !s.is_char_boundary(mid) {
+            return Err(hex::Error::InvalidChar(s.as_bytes()[mid]));
+        }
+        let (inner, outer) = s.split_at(mid);
+        Ok(HmacMidState {
+            inner: hex::FromHex::from_hex(inner)?,
+            outer: hex::FromHex::from_hex(outer)?,
+        })
+    }
+}
+
 /// Pair of underyling hash engines, used for the inner and outer hash of HMAC.
 #[derive(Clone)]
 pub struct HmacEngine<T: Hash> {
@@ -64,16 +123,8 @@ impl<T: Hash> Default for HmacEngine<T> {
 impl<T: Hash> HmacEngine<T> {
     /// Constructs a new keyed HMAC from `key`.
     ///
-    /// We only support underlying hashes whose block sizes are ≤ 128 bytes.
-    ///
-    /// # Panics
-    ///
-    /// Larger hashes will result in a panic.
+    /// Underlying hashes of any block size are supported.
     pub fn new(key: &[u8]) -> HmacEngine<T> {
-        debug_assert!(T::Engine::BLOCK_SIZE <= 128);
-
-        let mut ipad = [0x36u8; 128];
-        let mut opad = [0x5cu8; 128];
         let mut ret = HmacEngine {
             iengine: <T as Hash>::engine(),
             oengine: <T as Hash>::engine(),
@@ -81,26 +132,38 @@ impl<T: Hash> HmacEngine<T> {
 
         if key.len() > T::Engine::BLOCK_SIZE {
             let hash = <T as Hash>::hash(key);
-            for (b_i, b_h) in ipad.iter_mut().zip(&hash[..]) {
-                *b_i ^= *b_h;
-            }
-            for (b_o, b_h) in opad.iter_mut().zip(&hash[..]) {
-                *b_o ^= *b_h;
-            }
+            ret.input_pads(&hash[..]);
         } else {
-            for (b_i, b_h) in ipad.iter_mut().zip(key) {
-                *b_i ^= *b_h;
-            }
-            for (b_o, b_h) in opad.iter_mut().zip(key) {
-                *b_o ^= *b_h;
-            }
-        };
-
-        HashEngine::input(&mut ret.iengine, &ipad[..T::Engine::BLOCK_SIZE]);
-        HashEngine::input(&mut ret.oengine, &opad[..T::Engine::BLOCK_SIZE]);
+            ret.input_pads(key);
+        }
         ret
     }
 
+    /// Inputs the key, zero padded to the block size and XORed with the inner and outer pads,
+    /// into the inner and outer engines.
+    ///
+    /// The pads are produced in fixed-size chunks, so block sizes of any length are supported
+    /// without allocation.
+    fn input_pads(&mut self, key: &[u8]) {
+        const CHUNK_SIZE: usize = 64;
+
+        let mut ipad = [0u8; CHUNK_SIZE];
+        let mut opad = [0u8; CHUNK_SIZE];
+        let mut pos = 0;
+        while pos < T::Engine::BLOCK_SIZE {
+            let len = cmp::min(CHUNK_SIZE, T::Engine::BLOCK_SIZE - pos);
+            let key_chunk = key.get(pos..).unwrap_or(&[]);
+            for (i, (b_i, b_o)) in ipad.iter_mut().zip(opad.iter_mut()).enumerate() {
+                let b_k = key_chunk.get(i).copied().unwrap_or(0);
+                *b_i = 0x36 ^ b_k;
+                *b_o = 0x5c ^ b_k;
+            }
+            self.iengine.input(&ipad[..len]);
+            self.oengine.input(&opad[..len]);
+            pos += len;
+        }
+    }
+
     /// A special constructor giving direct access to the underlying "inner" and "outer" engines.
     pub fn from_inner_engines(iengine: T::Engine, oengine: T::Engine) -> HmacEngine<T> {
         HmacEngine {
@@ -110,6 +173,86 @@ impl<T: Hash> HmacEngine<T> {
     }
 }
 
+impl<T: Hash> HmacEngine<T> where T::Engine: FromMidstate {
+    /// Restores an engine from its midstate and the number of bytes hashed by it, as returned
+    /// by [`HashEngine::n_bytes_hashed`] (which includes the padded key).
+    ///
+    /// # Panics
+    ///
+    /// If `length` is not a multiple of the block size.
+    pub fn from_midstate(midstate: HmacMidState<T>, length: usize) -> HmacEngine<T> {
+        HmacEngine {
+            iengine: T::Engine::from_midstate(midstate.inner, length),
+            oengine: T::Engine::from_midstate(midstate.outer, T::Engine::BLOCK_SIZE),
+        }
+    }
+}
+
+impl<T: Hash> FromMidstate for HmacEngine<T> where T::Engine: FromMidstate {
+    fn from_midstate(midstate: HmacMidState<T>, length: usize) -> HmacEngine<T> {
+        HmacEngine::from_midstate(midstate, length)
+    }
+}
+
+/// HMAC key with the padded key already absorbed by the inner and outer engines.
+///
+/// Engines created from an `HmacKey` start from the precomputed state, so the key is processed
+/// only once when authenticating many messages under it.
+#[derive(Clone)]
+pub struct HmacKey<T: Hash> {
+    iengine: T::Engine,
+    oengine: T::Engine,
+}
+
+impl<T: Hash> HmacKey<T> {
+    /// Precomputes the HMAC state for `key`.
+    pub fn new(key: &[u8]) -> HmacKey<T> {
+        let engine = HmacEngine::<T>::new(key);
+        HmacKey {
+            iengine: engine.iengine,
+            oengine: engine.oengine,
+        }
+    }
+
+    /// Returns a fresh engine keyed with this key.
+    pub fn engine(&self) -> HmacEngine<T> {
+        HmacEngine::from_inner_engines(self.iengine.clone(), self.oengine.clone())
+    }
+
+    /// Computes the HMAC of `data` under this key.
+    pub fn hmac(&self, data: &[u8]) -> Hmac<T> {
+        let mut engine = self.engine();
+        engine.input(data);
+        Hmac::from_engine(engine)
+    }
+
+    /// Returns the midstates of the inner and outer engines.
+    pub fn midstate(&self) -> HmacMidState<T> {
+        HmacMidState {
+            inner: self.iengine.midstate(),
+            outer: self.oengine.midstate(),
+        }
+    }
+}
+
+impl<T: Hash> HmacKey<T> where T::Engine: FromMidstate {
+    /// Restores a key from the midstate returned by [`HmacKey::midstate`].
+    pub fn from_midstate(midstate: HmacMidState<T>) -> HmacKey<T> {
+        let engine = HmacEngine::from_midstate(midstate, T::Engine::BLOCK_SIZE);
+        HmacKey {
+            iengine: engine.iengine,
+            oengine: engine.oengine,
+        }
+    }
+}
+
+impl<T: Hash> fmt::Debug for HmacKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Do not print the key material.
+        f.write_str("HmacKey { .. }")
+    }
+}
+
 impl<T: Hash> HashEngine for HmacEngine<T> {
     type MidState = HmacMidState<T>;
 
@@ -131,6 +274,24 @@ impl<T: Hash> HashEngine for HmacEngine<T> {
     }
 }
 
+impl<T: Hash> Hmac<T> {
+    /// Checks in fixed time that `tag` is equal to this HMAC.
+    ///
+    /// Unlike [`fixed_time_eq`](crate::cmp::fixed_time_eq) this does not panic if `tag` has the
+    /// wrong length, [`Error::InvalidLength`] is returned instead. The length of `tag` is not
+    /// treated as secret.
+    pub fn verify(&self, tag: &[u8]) -> Result<(), Error> {
+        if tag.len() != T::LEN {
+            return Err(Error::InvalidLength(T::LEN, tag.len()));
+        }
+        if crate::cmp::fixed_time_eq(&self[..], tag) {
+            Ok(())
+        } else {
+            Err(Error::InvalidMac)
+        }
+    }
+}
+
 impl<T: Hash> fmt::Debug for Hmac<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Debug::fmt(&self.0, f)
@@ -184,6 +345,8 @@ impl<T: Hash> ops::Index<ops::RangeFull> for Hmac<T> {
     }
 }
 
+ct_eq_impl!(Hmac, T: Hash);
+
 impl<T: Hash> borrow::Borrow<[u8]> for Hmac<T> {
     fn borrow(&self) -> &[u8] {
         &self[..]
@@ -242,6 +405,58 @@ impl<'de, T: Hash + Deserialize<'de>> Deserialize<'de> for Hmac<T> {
     }
 }
 
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<T: Hash> Serialize for HmacMidState<T> where <T::Engine as HashEngine>::MidState: Serialize {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        Serialize::serialize(&(&self.inner, &self.outer), s)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de, T: Hash> Deserialize<'de> for HmacMidState<T> where <T::Engine as HashEngine>::MidState: Deserialize<'de> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<HmacMidState<T>, D::Error> {
+        let (inner, outer) = Deserialize::deserialize(d)?;
+        Ok(HmacMidState { inner, outer })
+    }
+}
+
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl<T: Hash + zeroize::Zeroize> zeroize::Zeroize for Hmac<T> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl<T: Hash> zeroize::Zeroize for HmacEngine<T> where T::Engine: zeroize::Zeroize {
+    fn zeroize(&mut self) {
+        self.iengine.zeroize();
+        self.oengine.zeroize();
+    }
+}
+
+// The inner and outer engines wipe themselves when dropped.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl<T: Hash> zeroize::ZeroizeOnDrop for HmacEngine<T> where T::Engine: zeroize::ZeroizeOnDrop {}
+
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl<T: Hash> zeroize::Zeroize for HmacKey<T> where T::Engine: zeroize::Zeroize {
+    fn zeroize(&mut self) {
+        self.iengine.zeroize();
+        self.oengine.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl<T: Hash> zeroize::ZeroizeOnDrop for HmacKey<T> where T::Engine: zeroize::ZeroizeOnDrop {}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -369,6 +584,196 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hmac_sha3_256() {
+        use crate::{sha3_256, HashEngine, HmacEngine, Hash, Hmac};
+        use crate::hex::FromHex;
+
+        // SHA3-256 has a 136 byte block, larger than any SHA2 hash.
+        let mut engine = HmacEngine::<sha3_256::Hash>::new(b"key");
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        let hash = Hmac::<sha3_256::Hash>::from_engine(engine);
+        assert_eq!(
+            hash,
+            Hmac::from_hex("8c6e0683409427f8931711b10ca92a506eb1fafa48fadd66d76126f47ac2c333").unwrap(),
+        );
+    }
+
+    /// SHA256 with the block size used for HMAC padding set to 256 bytes, to exercise block
+    /// sizes larger than those of the hashes in this crate.
+    mod large_block {
+        use core::str;
+        use core::ops::Index;
+        use core::slice::SliceIndex;
+
+        use crate::{sha256, Error, Hash as _, hex};
+
+        crate::internal_macros::hash_type! {
+            256,
+            false,
+            "SHA256 with a 256 byte block size.",
+            "crate::util::json_hex_string::len_32"
+        }
+
+        #[derive(Clone, Default)]
+        pub struct HashEngine(sha256::HashEngine);
+
+        impl crate::HashEngine for HashEngine {
+            type MidState = sha256::Midstate;
+
+            fn midstate(&self) -> sha256::Midstate {
+                self.0.midstate()
+            }
+
+            const BLOCK_SIZE: usize = 256;
+
+            fn input(&mut self, data: &[u8]) {
+                self.0.input(data)
+            }
+
+            fn n_bytes_hashed(&self) -> usize {
+                self.0.n_bytes_hashed()
+            }
+        }
+
+        fn from_engine(e: HashEngine) -> Hash {
+            Hash(sha256::Hash::from_engine(e.0).into_inner())
+        }
+    }
+
+    #[test]
+    fn hmac_large_block() {
+        use crate::{HashEngine, HmacEngine, Hash, Hmac};
+        use crate::hex::FromHex;
+
+        let mut key = [0u8; 300];
+        for (i, b) in key.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+
+        // A short key, a key longer than any block size in this crate and a key longer than
+        // the block size, which is hashed.
+        let tests = [
+            (&b"key"[..], "1eebb93c6d7b5c7a67a68b1638beafb511e84474464f9d425edf1957fcbf27b5"),
+            (&key[..200], "1dc82419add2d989de9ceb356c3e1d7483782a766695e60fbf2f874dbd91d752"),
+            (&key[..], "12436788da5a69b40fddeb2e219f9dbe1f9cf49057968635bf706988c12872a1"),
+        ];
+
+        for &(key, expected) in tests.iter() {
+            let mut engine = HmacEngine::<large_block::Hash>::new(key);
+            engine.input(b"The quick brown fox jumps over the lazy dog");
+            let hash = Hmac::<large_block::Hash>::from_engine(engine);
+            assert_eq!(hash, Hmac::from_hex(expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn hmac_key() {
+        use crate::{sha256, sha512, FromMidstate, HashEngine, HmacEngine, HmacKey, Hash, Hmac};
+
+        fn check<T: Hash>() where T::Engine: FromMidstate {
+            let key = HmacKey::<T>::new(b"key");
+            let mut engine = HmacEngine::<T>::new(b"key");
+            engine.input(b"The quick brown fox jumps over the lazy dog");
+            let expected = Hmac::from_engine(engine);
+
+            // Engines can be spawned repeatedly from the same key.
+            for _ in 0..2 {
+                assert_eq!(key.hmac(b"The quick brown fox jumps over the lazy dog"), expected);
+            }
+
+            let restored = HmacKey::<T>::from_midstate(key.midstate());
+            assert_eq!(restored.hmac(b"The quick brown fox jumps over the lazy dog"), expected);
+
+            // Restore an engine part way through a message.
+            let data = [0xab; 300];
+            let mut engine = key.engine();
+            engine.input(&data[..2 * T::Engine::BLOCK_SIZE]);
+            let mut restored = HmacEngine::<T>::from_midstate(engine.midstate(), engine.n_bytes_hashed());
+            assert_eq!(restored.n_bytes_hashed(), 3 * T::Engine::BLOCK_SIZE);
+            engine.input(&data[2 * T::Engine::BLOCK_SIZE..]);
+            restored.input(&data[2 * T::Engine::BLOCK_SIZE..]);
+            assert_eq!(Hmac::<T>::from_engine(restored), Hmac::from_engine(engine));
+        }
+
+        check::<sha256::Hash>();
+        check::<sha512::Hash>();
+    }
+
+    #[test]
+    fn hmac_verify() {
+        use crate::{sha256, Error, Hash, HashEngine, Hmac, HmacEngine};
+
+        let mut engine = HmacEngine::<sha256::Hash>::new(b"key");
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        let tag = Hmac::from_engine(engine);
+
+        assert_eq!(tag.verify(&tag[..]), Ok(()));
+
+        let mut bad = tag.into_inner();
+        bad[31] ^= 1;
+        assert_eq!(tag.verify(&bad), Err(Error::InvalidMac));
+        assert_eq!(tag.verify(&bad[..16]), Err(Error::InvalidLength(32, 16)));
+        assert_eq!(tag.verify(&[]), Err(Error::InvalidLength(32, 0)));
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn hmac_zeroize() {
+        use zeroize::{Zeroize, ZeroizeOnDrop};
+        use crate::{sha256, sha512, siphash24, Hash, HashEngine, Hmac, HmacEngine, HmacKey};
+
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<HmacEngine<sha256::Hash>>();
+        assert_zeroize_on_drop::<HmacKey<sha512::Hash>>();
+        assert_zeroize_on_drop::<siphash24::HashEngine>();
+
+        let mut engine = HmacEngine::<sha256::Hash>::new(b"key");
+        engine.input(b"The quick brown fox");
+        engine.zeroize();
+        assert_eq!(engine.n_bytes_hashed(), 0);
+        assert_eq!(engine.midstate().inner, sha256::Midstate::from_inner([0; 32]));
+        assert_eq!(engine.midstate().outer, sha256::Midstate::from_inner([0; 32]));
+
+        let mut hmac = Hmac::<sha256::Hash>::hash(b"The quick brown fox");
+        hmac.zeroize();
+        assert_eq!(hmac, Hmac::all_zeros());
+    }
+
+    #[test]
+    fn hmac_midstate_hex() {
+        use crate::{sha256, HmacKey};
+        use crate::hmac::HmacMidState;
+
+        let midstate = HmacKey::<sha256::Hash>::new(b"key").midstate();
+        let hex = format!("{}", midstate);
+        assert_eq!(hex.len(), 128);
+        assert_eq!(&hex[..64], &format!("{:x}", midstate.inner));
+        assert_eq!(hex.parse::<HmacMidState<sha256::Hash>>(), Ok(midstate));
+        assert_eq!(
+            hex[1..].parse::<HmacMidState<sha256::Hash>>(),
+            Err(crate::hex::Error::OddLengthString(127)),
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn hmac_midstate_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{sha256, HmacKey};
+
+        let midstate = HmacKey::<sha256::Hash>::new(b"key").midstate();
+        assert_tokens(
+            &midstate.readable(),
+            &[
+                Token::Tuple { len: 2 },
+                Token::Str("35ebdc828b36036c6493a864893e10a6dd067e372af11f7479e11e483ac16cce"),
+                Token::Str("6245c4a9dc780d595bd06ccebe5ccd16c9b927174ddde67fecbbab7e3e8bec3d"),
+                Token::TupleEnd,
+            ],
+        );
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn hmac_sha512_serde() {
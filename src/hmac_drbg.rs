@@ -0,0 +1,369 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! HMAC-based deterministic random bit generator (HMAC_DRBG).
+//!
+//! Implementation of HMAC_DRBG from NIST SP 800-90A, generic over the underlying hash function.
+//! Prediction resistance is not supported; the caller is responsible for reseeding with fresh
+//! entropy when required.
+//!
+
+use core::fmt;
+
+use crate::{Hash, HashEngine, Hmac, HmacEngine};
+use crate::hex::FromHex;
+
+/// Maximum number of generate requests between reseeds.
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Maximum number of bytes returned by a single generate request.
+pub const MAX_REQUEST_BYTES: usize = 1 << 16;
+
+/// HMAC_DRBG error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The reseed counter has exceeded [`RESEED_INTERVAL`], the generator must be reseeded.
+    ReseedRequired,
+    /// More than [`MAX_REQUEST_BYTES`] bytes were requested at once.
+    RequestTooLarge(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::ReseedRequired => f.write_str("HMAC_DRBG must be reseeded"),
+            Error::RequestTooLarge(len) => write!(f, "HMAC_DRBG request too large: {} bytes (maximum {})", len, MAX_REQUEST_BYTES),
+        }
+    }
+}
+
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+impl From<Error> for rand_core::Error {
+    /// Converts the error into a custom error code: [`Error::ReseedRequired`] becomes
+    /// `rand_core::Error::CUSTOM_START` and [`Error::RequestTooLarge`] `CUSTOM_START + 1`.
+    fn from(err: Error) -> rand_core::Error {
+        let offset = match err {
+            Error::ReseedRequired => 0,
+            Error::RequestTooLarge(_) => 1,
+        };
+        let code = core::num::NonZeroU32::new(rand_core::Error::CUSTOM_START + offset).expect("non-zero");
+        rand_core::Error::from(code)
+    }
+}
+
+/// HMAC_DRBG state.
+#[derive(Clone)]
+pub struct HmacDrbg<T: Hash> {
+    /// HMAC engine keyed with the current value of `Key`.
+    k: HmacEngine<T>,
+    v: Hmac<T>,
+    reseed_counter: u64,
+}
+
+impl<T: Hash> HmacDrbg<T> {
+    /// Instantiates the generator from `entropy`, a `nonce` and an optional personalization
+    /// string.
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> HmacDrbg<T> {
+        // An all-zero key is equivalent to an empty one since HMAC keys are zero padded.
+        let ones = T::Inner::from_byte_iter((0..T::LEN).map(|_| Ok(0x01))).expect("correct length");
+        let mut drbg = HmacDrbg {
+            k: HmacEngine::new(&[]),
+            v: Hmac::from_inner(ones),
+            reseed_counter: 1,
+        };
+        drbg.update(&[entropy, nonce, personalization]);
+        drbg
+    }
+
+    /// Reseeds the generator with fresh `entropy` and optional additional input.
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) {
+        self.update(&[entropy, additional_input]);
+        self.reseed_counter = 1;
+    }
+
+    /// Fills `out` with pseudorandom bytes.
+    pub fn generate(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        self.generate_with_input(&[], out)
+    }
+
+    /// Fills `out` with pseudorandom bytes, mixing in `additional_input`.
+    pub fn generate_with_input(&mut self, additional_input: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        if out.len() > MAX_REQUEST_BYTES {
+            return Err(Error::RequestTooLarge(out.len()));
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(Error::ReseedRequired);
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+        for chunk in out.chunks_mut(T::LEN) {
+            self.v = self.hmac(&[&self.v[..]]);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// Returns the number of generate requests since the last (re)seeding, plus one.
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    /// The HMAC_DRBG_Update function, with the provided data given as a list of slices to be
+    /// concatenated.
+    fn update(&mut self, provided_data: &[&[u8]]) {
+        let empty = provided_data.iter().all(|data| data.is_empty());
+        for &separator in [0x00u8, 0x01].iter() {
+            let mut engine = self.k.clone();
+            engine.input(&self.v[..]);
+            engine.input(&[separator]);
+            for data in provided_data {
+                engine.input(data);
+            }
+            self.k = HmacEngine::new(&Hmac::from_engine(engine)[..]);
+            self.v = self.hmac(&[&self.v[..]]);
+
+            if empty {
+                break;
+            }
+        }
+    }
+
+    fn hmac(&self, data: &[&[u8]]) -> Hmac<T> {
+        let mut engine = self.k.clone();
+        for data in data {
+            engine.input(data);
+        }
+        Hmac::from_engine(engine)
+    }
+}
+
+impl<T: Hash> fmt::Debug for HmacDrbg<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Do not print the internal state.
+        f.debug_struct("HmacDrbg").field("reseed_counter", &self.reseed_counter).finish()
+    }
+}
+
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+impl<T: Hash> rand_core::RngCore for HmacDrbg<T> {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    /// Fills `dest` with pseudorandom bytes, splitting it into requests of at most
+    /// [`MAX_REQUEST_BYTES`].
+    ///
+    /// # Panics
+    ///
+    /// If the generator must be reseeded.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(MAX_REQUEST_BYTES) {
+            self.generate(chunk).expect("HMAC_DRBG must be reseeded");
+        }
+    }
+
+    /// Fills `dest` with pseudorandom bytes, splitting it into requests of at most
+    /// [`MAX_REQUEST_BYTES`].
+    ///
+    /// Fails with the code of [`Error::ReseedRequired`] if the generator must be reseeded.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        for chunk in dest.chunks_mut(MAX_REQUEST_BYTES) {
+            self.generate(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+impl<T: Hash> rand_core::CryptoRng for HmacDrbg<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn cavp() {
+        use crate::sha256;
+        use crate::hex::{FromHex, ToHex};
+
+        // First HMAC_DRBG.rsp vector of the NIST CAVP for SHA-256 without prediction resistance,
+        // personalization string or additional input: the second of two 1024-bit requests.
+        let entropy = Vec::from_hex("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488").unwrap();
+        let nonce = Vec::from_hex("659ba96c601dc69fc902940805ec0ca8").unwrap();
+
+        let mut drbg = HmacDrbg::<sha256::Hash>::new(&entropy, &nonce, &[]);
+        let mut out = [0u8; 128];
+        drbg.generate(&mut out).unwrap();
+        drbg.generate(&mut out).unwrap();
+        assert_eq!(
+            out.to_hex(),
+            "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89\
+             d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc1\
+             07694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668\
+             961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8",
+        );
+        assert_eq!(drbg.reseed_counter(), 3);
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn inputs() {
+        use crate::{sha1, sha256, sha512};
+        use crate::hex::ToHex;
+
+        // Same structure as the CAVP vectors (output of the second request), checked against
+        // the OpenSSL HMAC-DRBG implementation.
+        fn bytes(start: u8, len: usize) -> Vec<u8> {
+            (0..len).map(|i| start.wrapping_add(i as u8)).collect()
+        }
+        let entropy = bytes(0x00, 32);
+        let nonce = bytes(0x20, 16);
+        let personalization = bytes(0x40, 32);
+        let additional_input1 = bytes(0x60, 32);
+        let additional_input2 = bytes(0x80, 32);
+        let entropy_reseed = bytes(0xa0, 32);
+        let additional_input_reseed = bytes(0xc0, 32);
+
+        // Personalization string and additional input.
+        let mut drbg = HmacDrbg::<sha256::Hash>::new(&entropy, &nonce, &personalization);
+        let mut out = [0u8; 128];
+        drbg.generate_with_input(&additional_input1, &mut out).unwrap();
+        drbg.generate_with_input(&additional_input2, &mut out).unwrap();
+        assert_eq!(
+            out.to_hex(),
+            "9de19320af8682520197e71e8972e4a3ccf798a04f2d9ea80f613d2543b04dd3\
+             e6559ac792e7aabe240a39b841cab9c9f9c134f8347aa095eb3fe6efa72bd4dd\
+             1acd9cacb53a75e1731e30d0e23573da6950144a6daaca08177e05cc85ef4553\
+             e76234c7e4f7c96c0b490804ef680cc8ad6a5b91b55b20cad77bf7794557bd4f",
+        );
+
+        // Reseed before generating.
+        let mut drbg = HmacDrbg::<sha256::Hash>::new(&entropy, &nonce, &personalization);
+        drbg.reseed(&entropy_reseed, &additional_input_reseed);
+        assert_eq!(drbg.reseed_counter(), 1);
+        drbg.generate_with_input(&additional_input1, &mut out).unwrap();
+        drbg.generate_with_input(&additional_input2, &mut out).unwrap();
+        assert_eq!(
+            out.to_hex(),
+            "54f13126f9bc4a493e0e17628be30c5295a8ad81357b79f260e4222b5df426a9\
+             9ef9d8621ef9870ea901ed8f06d00471b75f791bb173f365d0ef3b29cd672646\
+             e9517c9e9b70563ff15adea0a3feec52902fa7238f7c5099a91c68a8a7f7b17e\
+             5d1078615276b8d397575a6b72080dd2734c9248efd8312594ffb40597491b52",
+        );
+
+        // Other hash functions, with output lengths that are not a multiple of the hash length.
+        let mut drbg = HmacDrbg::<sha1::Hash>::new(&entropy, &nonce, &[]);
+        let mut out = [0u8; 80];
+        drbg.generate(&mut out).unwrap();
+        drbg.generate(&mut out).unwrap();
+        assert_eq!(
+            out.to_hex(),
+            "c0a745a5a45c67397ce88a5aebf3c84444785390f0400c08cd4b4c4303fefdc5\
+             d979c815f51781bb3d80529f38e21c44f30af2df159c98ee213104143c8bda74\
+             b66d5f1a36476df29ee611f7b85e575f",
+        );
+
+        let mut drbg = HmacDrbg::<sha512::Hash>::new(&entropy, &nonce, &[]);
+        let mut out = [0u8; 256];
+        drbg.generate(&mut out).unwrap();
+        drbg.generate(&mut out).unwrap();
+        assert_eq!(
+            out.to_hex(),
+            "3a38e5054004de01c229dbca0330117f48d76cf50c8bdb5b5ed0e744b9f27cc6\
+             257bf8092303d3561b8077775abbec754ffb399920cd5f5016851eb1ce6c6df7\
+             f4427a4730e046e3dc3285bd4b2bcbdac2bec4d06b462d7c2b59acf84986b5c3\
+             d887bcb9cdacb40b8f9999d5878de8f56c973bbbffb8ec555f2a43384411b4ad\
+             df4c7e5957cea5f860e34130130c02a1b12e439c30130c1e8f45f0b9800a9910\
+             e81757874872a319eeba3c5a22b93cfb1c0d3086cf69dbc79bbb623f4b9ff948\
+             c787be9830fbccd1c5c5680575ac2b1eefdba13903e4906f96563bc0fc66db8c\
+             bc78ea546d4110188b2472609ec1b2e251ad7e0c832bb77165891c4ff4f08fce",
+        );
+    }
+
+    #[test]
+    fn limits() {
+        use crate::sha256;
+
+        let mut drbg = HmacDrbg::<sha256::Hash>::new(&[0; 32], &[0; 16], &[]);
+        let mut out = [0u8; MAX_REQUEST_BYTES + 1];
+        assert_eq!(drbg.generate(&mut out), Err(Error::RequestTooLarge(MAX_REQUEST_BYTES + 1)));
+        assert!(drbg.generate(&mut out[..MAX_REQUEST_BYTES]).is_ok());
+
+        drbg.reseed_counter = RESEED_INTERVAL;
+        assert!(drbg.generate(&mut out[..32]).is_ok());
+        assert_eq!(drbg.generate(&mut out[..32]), Err(Error::ReseedRequired));
+        drbg.reseed(&[1; 32], &[]);
+        assert!(drbg.generate(&mut out[..32]).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "rand_core")]
+    fn rng_core() {
+        use rand_core::RngCore;
+        use crate::sha256;
+
+        let mut drbg = HmacDrbg::<sha256::Hash>::new(&[0; 32], &[0; 16], &[]);
+        let mut expected = [0u8; 8];
+        drbg.clone().generate(&mut expected).unwrap();
+        assert_eq!(drbg.next_u64(), u64::from_le_bytes(expected));
+    }
+
+    #[test]
+    #[cfg(feature = "rand_core")]
+    fn rng_core_reseed_required() {
+        use rand_core::RngCore;
+        use crate::sha256;
+
+        let mut drbg = HmacDrbg::<sha256::Hash>::new(&[0; 32], &[0; 16], &[]);
+        let mut out = [0u8; 32];
+        drbg.reseed_counter = RESEED_INTERVAL;
+        assert!(drbg.try_fill_bytes(&mut out).is_ok());
+        let err = drbg.try_fill_bytes(&mut out).unwrap_err();
+        assert_eq!(err.code().map(|code| code.get()), Some(rand_core::Error::CUSTOM_START));
+
+        drbg.reseed(&[1; 32], &[]);
+        assert!(drbg.try_fill_bytes(&mut out).is_ok());
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use super::HmacDrbg;
+    use crate::sha256;
+
+    #[bench]
+    pub fn hmac_drbg_sha256_1k(bh: &mut Bencher) {
+        let mut drbg = HmacDrbg::<sha256::Hash>::new(&[0; 32], &[0; 16], &[]);
+        let mut out = [0u8; 1024];
+        bh.iter( || {
+            drbg.generate(&mut out).unwrap();
+        });
+        bh.bytes = out.len() as u64;
+    }
+}
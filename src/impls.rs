@@ -23,14 +23,16 @@ use std::{error, io};
 #[cfg(not(feature = "std"))]
 use core2::{error, io};
 
-use crate::{Error, HashEngine, hex, sha1, sha256, sha512, ripemd160, siphash24, hmac};
+use crate::{Error, HashEngine, hex, argon2, hkdf, hmac_drbg, scrypt, blake2b, blake2s, blake3, murmur3, sha1, sha1dc, sha224, sha256, sha512, sha512_256, sha384, sha3_256, keccak256, shake128, shake256, ripemd160, siphash13, siphash24, siphash24_128, hmac, taproot};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::{gcs, partial_merkle_tree};
 
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         use Error::*;
 
         match *self {
-            InvalidLength(_, _) => None
+            InvalidLength(_, _) | InvalidMac => None
         }
     }
 }
@@ -45,6 +47,75 @@ impl error::Error for hex::Error {
     }
 }
 
+impl error::Error for argon2::Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use argon2::Error::*;
+
+        match *self {
+            InvalidMemoryCost(_) | InvalidTimeCost(_) | InvalidParallelism(_) | SaltTooShort(_)
+                | InvalidOutputLength(_) | InputTooLong(_) | MemoryTooSmall(_, _) => None
+        }
+    }
+}
+
+impl error::Error for hkdf::MaxLengthError {}
+
+impl error::Error for hmac_drbg::Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use hmac_drbg::Error::*;
+
+        match *self {
+            ReseedRequired | RequestTooLarge(_) => None
+        }
+    }
+}
+
+impl error::Error for taproot::Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use taproot::Error::*;
+
+        match *self {
+            InvalidLeafVersion(_) | InvalidMerkleTreeDepth(_) | InvalidControlBlockSize(_)
+            | NodeNotInDfsOrder | OverCompleteTree | IncompleteTree | EmptyTree | WeightOverflow => None
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl error::Error for gcs::Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use gcs::Error::*;
+
+        match *self {
+            UnexpectedEnd | TooManyElements(_) => None
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl error::Error for partial_merkle_tree::Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use partial_merkle_tree::Error::*;
+
+        match *self {
+            NoTransactions | TooManyTransactions(_) | TooManyHashes | NotEnoughBits | BitsExhausted
+            | HashesExhausted | IdenticalHashesFound | NotAllBitsConsumed | NotAllHashesConsumed
+            | MerkleRootMismatch => None
+        }
+    }
+}
+
+impl error::Error for scrypt::Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use scrypt::Error::*;
+
+        match *self {
+            InvalidCost(_) | InvalidBlockSize(_) | InvalidParallelization(_) | MemoryTooLarge
+                | ScratchTooSmall(_, _) => None
+        }
+    }
+}
+
 impl<'a> io::Read for hex::HexIterator<'a> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let mut bytes_read = 0usize;
@@ -61,6 +132,58 @@ impl<'a> io::Read for hex::HexIterator<'a> {
     }
 }
 
+impl io::Write for blake2b::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for blake2b::VarHashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for blake2s::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for blake2s::VarHashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for blake3::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Read for blake3::XofReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.squeeze(buf);
+        Ok(buf.len())
+    }
+}
+
 impl io::Write for sha1::HashEngine {
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 
@@ -70,6 +193,24 @@ impl io::Write for sha1::HashEngine {
     }
 }
 
+impl io::Write for sha1dc::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for sha224::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
 impl io::Write for sha256::HashEngine {
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 
@@ -88,6 +229,67 @@ impl io::Write for sha512::HashEngine {
     }
 }
 
+impl io::Write for sha512_256::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for sha384::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for sha3_256::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for keccak256::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for shake128::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for shake256::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Read for shake128::XofReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.squeeze(buf);
+        Ok(buf.len())
+    }
+}
+
 impl io::Write for ripemd160::HashEngine {
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 
@@ -97,6 +299,24 @@ impl io::Write for ripemd160::HashEngine {
     }
 }
 
+impl io::Write for murmur3::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Write for siphash13::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
 impl io::Write for siphash24::HashEngine {
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 
@@ -106,6 +326,15 @@ impl io::Write for siphash24::HashEngine {
     }
 }
 
+impl io::Write for siphash24_128::HashEngine {
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+}
+
 impl<T: crate::Hash> io::Write for hmac::HmacEngine<T> {
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 
@@ -119,7 +348,7 @@ impl<T: crate::Hash> io::Write for hmac::HmacEngine<T> {
 mod tests {
     use super::io::Write;
 
-    use crate::{Hash, sha1, sha256, sha256d, sha512, ripemd160, hash160, siphash24, hmac};
+    use crate::{Hash, blake2b, blake2s, blake3, sha1, sha1dc, sha224, sha256, sha256d, sha512, sha512_256, sha384, sha3_256, keccak256, shake128, shake256, ripemd160, hash160, murmur3, siphash13, siphash24, siphash24_128, hmac};
 
     macro_rules! write_test {
         ($mod:ident, $exp_empty:expr, $exp_256:expr, $exp_64k:expr,) => {
@@ -149,6 +378,30 @@ mod tests {
         }
     }
 
+    write_test!(
+        blake2b,
+        "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+         d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce",
+        "dd3335ca787cb9cbf72b59c1461029caadc86dc68373f7852ec9045df3011559\
+         d0629cf1a8262732ae1cfc7c2348767d426cfbb87e1f6a75ff7b00245c3f189d",
+        "1cf41a5d760fc92c686f1926441e1f978add35fb12b60b709125450cb58232a5\
+         7a3dbe51efbb554af94e9b67d0c379168ce84ad95d00d1da2e38568737d3c2cb",
+    );
+
+    write_test!(
+        blake2s,
+        "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9",
+        "269b73e8522c4bbbfe3136fdc836d8693fb07a035a2e4e99f67e18ff8e300473",
+        "e6e998731f7db23358149078d9372bdde65985f477d9cdca1cb374c2aa2e4efb",
+    );
+
+    write_test!(
+        blake3,
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+        "33329ca0326344ec01f69de9f4fddf7e2d66807c6b314b8351ad8abe52a3df02",
+        "1bf22709d3a11a0264eb554849197bb2f350bd2843b546707ed40e19def3ccee",
+    );
+
     write_test!(
         sha1,
         "da39a3ee5e6b4b0d3255bfef95601890afd80709",
@@ -156,6 +409,20 @@ mod tests {
         "e4b66838f9f7b6f91e5be32a02ae78094df402e7",
     );
 
+    write_test!(
+        sha1dc,
+        "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+        "ac458b067c6b021c7e9358229b636e9d1e4cb154",
+        "e4b66838f9f7b6f91e5be32a02ae78094df402e7",
+    );
+
+    write_test!(
+        sha224,
+        "d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f",
+        "4729c84e59381c0cc83d7171caf635a2ccffe4adbfd6db9e0e32999e",
+        "a72e39979b827f94653d6f7da918cc3ffc3ae6ba271fa50f706b5bdf",
+    );
+
     write_test!(
         sha256,
         "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
@@ -180,6 +447,51 @@ mod tests {
          4761df8d04ed04bb734ba48dd2106bb9ea54524f1394cdd18e6da3166e71c3ee",
     );
 
+    write_test!(
+        sha512_256,
+        "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a",
+        "8d4bb96e7956cf5f08bf5c45f7982630c46b0b022f25cbaf722ae97c06a6e7a2",
+        "3367646f3e264653f7dd664ac2cb6d3b96329e86ffb7a29a1082e2a4ddc9ee7a",
+    );
+
+    write_test!(
+        sha384,
+        "38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b",
+        "82135637ef6d6dd31a20e2bc9998681a3eecaf8f8c76d45e545214de38439d9a533848ec75f53e4b1a8805709c5124d0",
+        "fb7511d9a98c5686f9c2f55e242397815c9229d8759451e1710b8da6861e08d52f0357176f4b74f8cad9e23ab65411c7",
+    );
+
+    write_test!(
+        sha3_256,
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+        "8c7a8eec38a0c96723672927b9f4d0f7b5c5f334ee94debda979fb3696ab7581",
+        "60af0ef4115a72e570b1a414c7b8dc6c83677adf1ce2d268d28becd8e0fdd1e6",
+    );
+
+    write_test!(
+        keccak256,
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        "10c18b0ffc7f89e6da317ea664365820b9f7f954fa9f03733072f5ccd0826818",
+        "751a3fd6048cc3f5921ba66b8650fb452269d4741e5487c6736ae64b506538be",
+    );
+
+    write_test!(
+        shake128,
+        "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26",
+        "0f1fc75b89abd162a02becac90c3c910403fb3160c585a8dada6fa7a282e70b3",
+        "71736ef70d64f1d9ebacb5434c56fca97a29ec4db905145b3a653d37758e83f3",
+    );
+
+    write_test!(
+        shake256,
+        "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+         d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+        "f8418fdf0730825dae5cb33753e86ec5d40487b648b6d10ff881090312845b75\
+         84d52564dfa922b53510a8423bae90229be65534885f693754551241469256bd",
+        "480599d4f0c277968c82353a437190ee1317f6310fc3f83ae158343f2c69a59f\
+         d8c16aac02148d3a14c86d3fc21aaa699cee7ae346b7efe47f676ed18706783a",
+    );
+
     write_test!(
         ripemd160,
         "9c1185a5c5e9fc54612808977ee8f548b2258d31",
@@ -194,6 +506,20 @@ mod tests {
         "a9608c952c8dbcc20c53803d2ca5ad31d64d9313",
     );
 
+    write_test!(
+        murmur3,
+        "00000000",
+        "68b41dfb",
+        "e720e80a",
+    );
+
+    write_test!(
+        siphash13,
+        "2c530c1562a7fbd1",
+        "4b33ef0702778451",
+        "3dff8110eb8b84fd",
+    );
+
     write_test!(
         siphash24,
         "d70077739d4b921e",
@@ -201,6 +527,13 @@ mod tests {
         "ce456e4e4ecbc5bf",
     );
 
+    write_test!(
+        siphash24_128,
+        "5049d74780a3e07d4202ab47d4cef2f4",
+        "c85a9b21e3e62fc1dcbd6a1e0035fdaf",
+        "32404f713ac13b337ba60ba2c7407341",
+    );
+
     #[test]
     fn hmac() {
         let mut engine = hmac::HmacEngine::<sha256::Hash>::new(&[0xde, 0xad, 0xbe, 0xef]);
@@ -224,4 +557,19 @@ mod tests {
             "30df499717415a395379a1eaabe50038036e4abb5afc94aa55c952f4aa57be08"
         );
     }
+
+    #[test]
+    fn xof_read() {
+        use super::io::Read;
+
+        let mut engine = shake128::Hash::engine();
+        engine.write_all(&[1; 256]).unwrap();
+        let hash = shake128::Hash::from_engine(engine.clone());
+
+        let mut reader = engine.finalize_xof();
+        let mut out = [0; 32];
+        reader.read_exact(&mut out[..5]).unwrap();
+        reader.read_exact(&mut out[5..]).unwrap();
+        assert_eq!(&out[..], &hash[..]);
+    }
 }
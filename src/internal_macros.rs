@@ -31,6 +31,8 @@ macro_rules! hash_trait_impls {
         hex_fmt_impl!(Hash $(, $gen: $gent)*);
         serde_impl!(Hash, $bits / 8 $(, $gen: $gent)*);
         borrow_slice_impl!(Hash $(, $gen: $gent)*);
+        ct_eq_impl!(Hash $(, $gen: $gent)*);
+        zeroize_impl!(Hash $(, $gen: $gent)*);
 
         impl<I: SliceIndex<[u8]> $(, $gen: $gent)*> Index<I> for Hash<$($gen),*> {
             type Output = I::Output;
@@ -125,3 +127,50 @@ macro_rules! hash_type {
     }
 }
 pub(crate) use hash_type;
+
+/// Implements `zeroize::Zeroize` for the type `$ty` by wiping each of the given fields.
+///
+/// This does nothing unless the `zeroize` feature is enabled.
+macro_rules! zeroize_fields_impl {
+    ($ty:ident, $($field:tt),*) => {
+        #[cfg(feature = "zeroize")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+        impl zeroize::Zeroize for $ty {
+            fn zeroize(&mut self) {
+                $(zeroize::Zeroize::zeroize(&mut self.$field);)*
+            }
+        }
+    }
+}
+pub(crate) use zeroize_fields_impl;
+
+/// Implements `zeroize::Zeroize` for the engine `$ty` and wipes the engine when it is dropped.
+///
+/// Engines which only wrap another engine are already wiped by the `Drop` implementation of the
+/// inner engine, these are marked `wrapper` so that the inner engine can still be moved out.
+///
+/// This does nothing unless the `zeroize` feature is enabled.
+macro_rules! engine_zeroize_impl {
+    ($ty:ident, $($field:tt),*) => {
+        crate::internal_macros::zeroize_fields_impl!($ty, $($field),*);
+
+        #[cfg(feature = "zeroize")]
+        impl Drop for $ty {
+            fn drop(&mut self) {
+                zeroize::Zeroize::zeroize(self);
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+        impl zeroize::ZeroizeOnDrop for $ty {}
+    };
+    (wrapper $ty:ident) => {
+        crate::internal_macros::zeroize_fields_impl!($ty, 0);
+
+        #[cfg(feature = "zeroize")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+        impl zeroize::ZeroizeOnDrop for $ty {}
+    };
+}
+pub(crate) use engine_zeroize_impl;
@@ -0,0 +1,169 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Keccak-f\[1600\] permutation and sponge helpers, shared by the SHA-3 family of hash functions.
+//!
+
+use core::cmp;
+use core::convert::TryInto;
+
+/// Number of bytes in the Keccak-f\[1600\] state.
+pub(crate) const STATE_SIZE: usize = 200;
+
+/// Padding suffix of the original Keccak submission, which has no domain separation bits.
+pub(crate) const KECCAK_SUFFIX: u8 = 0x01;
+/// Domain separation suffix (and first padding bit) of the FIPS 202 hash functions.
+pub(crate) const SHA3_SUFFIX: u8 = 0x06;
+/// Domain separation suffix (and first padding bit) of the FIPS 202 extendable-output functions.
+pub(crate) const SHAKE_SUFFIX: u8 = 0x1f;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+// Rotation offsets and lane order of the combined rho and pi steps.
+const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Applies the Keccak-f\[1600\] permutation to `a`.
+pub(crate) fn f1600(a: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[5 * y + x] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut last = a[1];
+        for (&rho, &pi) in RHO.iter().zip(PI.iter()) {
+            let tmp = a[pi];
+            a[pi] = last.rotate_left(rho);
+            last = tmp;
+        }
+
+        // chi
+        for y in 0..5 {
+            let mut row = [0u64; 5];
+            row.copy_from_slice(&a[5 * y..5 * y + 5]);
+            for x in 0..5 {
+                a[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        a[0] ^= *rc;
+    }
+}
+
+/// XORs a block of input, whose length must be a multiple of 8 bytes, into the
+/// start of the state and then applies the permutation.
+pub(crate) fn absorb_block(a: &mut [u64; 25], block: &[u8]) {
+    debug_assert_eq!(block.len() % 8, 0);
+    debug_assert!(block.len() <= STATE_SIZE);
+
+    for (lane, bytes) in a.iter_mut().zip(block.chunks_exact(8)) {
+        *lane ^= u64::from_le_bytes(bytes.try_into().expect("8 byte slice"));
+    }
+    f1600(a);
+}
+
+/// Pads the final, partially filled, block of input with the domain separation `suffix` and
+/// absorbs it. The length of `block` is the rate of the sponge and `buf_idx` the number of input
+/// bytes it holds.
+pub(crate) fn absorb_final(a: &mut [u64; 25], block: &mut [u8], buf_idx: usize, suffix: u8) {
+    debug_assert!(buf_idx < block.len());
+
+    for b in block[buf_idx..].iter_mut() {
+        *b = 0;
+    }
+    block[buf_idx] ^= suffix;
+    let rate = block.len();
+    block[rate - 1] ^= 0x80;
+    absorb_block(a, block);
+}
+
+/// Serializes the state as little-endian lanes.
+pub(crate) fn state_to_bytes(a: &[u64; 25]) -> [u8; STATE_SIZE] {
+    let mut ret = [0; STATE_SIZE];
+    for (val, ret_bytes) in a.iter().zip(ret.chunks_exact_mut(8)) {
+        ret_bytes.copy_from_slice(&val.to_le_bytes());
+    }
+    ret
+}
+
+/// Reader for the output of a SHAKE extendable-output function.
+///
+/// Obtained by finalizing a [`shake128`](crate::shake128) or [`shake256`](crate::shake256)
+/// engine; any number of output bytes may be squeezed out of it.
+#[derive(Clone)]
+pub struct XofReader {
+    state: [u64; 25],
+    rate: usize,
+    pos: usize,
+}
+
+impl XofReader {
+    /// Creates a reader from a state which has absorbed all input, including padding.
+    pub(crate) fn new(state: [u64; 25], rate: usize) -> XofReader {
+        XofReader { state, rate, pos: 0 }
+    }
+
+    /// Fills `out` with the next `out.len()` bytes of output.
+    ///
+    /// Successive calls continue where the previous one left off, so the output does not depend
+    /// on how it is split into calls.
+    pub fn squeeze(&mut self, mut out: &mut [u8]) {
+        while !out.is_empty() {
+            if self.pos == self.rate {
+                f1600(&mut self.state);
+                self.pos = 0;
+            }
+            let write_len = cmp::min(self.rate - self.pos, out.len());
+            let bytes = state_to_bytes(&self.state);
+            out[..write_len].copy_from_slice(&bytes[self.pos..self.pos + write_len]);
+            self.pos += write_len;
+            out = &mut out[write_len..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn f1600_zero_state() {
+        // First lanes of Keccak-f[1600] applied to the all-zero state, from the
+        // Keccak team's KeccakF-1600-IntermediateValues.txt.
+        let mut a = [0u64; 25];
+        super::f1600(&mut a);
+        assert_eq!(a[0], 0xf1258f7940e1dde7);
+        assert_eq!(a[1], 0x84d5ccf933c0478a);
+        assert_eq!(a[24], 0xeaf1ff7b5ceca249);
+    }
+}
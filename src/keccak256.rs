@@ -0,0 +1,183 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Keccak-256 implementation.
+//!
+//! Keccak-256 is the hash function used by Ethereum. It is the original Keccak submission
+//! with a 512-bit capacity, which differs from [`sha3_256`] only in the
+//! padding applied to the final block.
+//!
+
+use core::str;
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, hex, keccak, sha3_256};
+
+crate::internal_macros::hash_type! {
+    256,
+    false,
+    "Output of the Keccak-256 hash function.",
+    "crate::util::json_hex_string::len_32"
+}
+
+/// Engine to compute Keccak-256 hash function.
+#[derive(Clone, Default)]
+pub struct HashEngine(sha3_256::HashEngine);
+
+crate::internal_macros::engine_zeroize_impl!(wrapper HashEngine);
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; keccak::STATE_SIZE];
+
+    fn midstate(&self) -> [u8; keccak::STATE_SIZE] {
+        self.0.midstate()
+    }
+
+    const BLOCK_SIZE: usize = sha3_256::BLOCK_SIZE;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.0.n_bytes_hashed()
+    }
+
+    fn input(&mut self, inp: &[u8]) {
+        self.0.input(inp)
+    }
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    Hash(e.0.finalize(keccak::KECCAK_SUFFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn test() {
+        use crate::{keccak256, Hash, HashEngine};
+        use crate::hex::{FromHex, ToHex};
+
+        #[derive(Clone)]
+        struct Test {
+            input: &'static str,
+            output: Vec<u8>,
+            output_str: &'static str,
+        }
+
+        let tests = vec![
+            Test {
+                input: "",
+                output: vec![
+                    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c,
+                    0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+                    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b,
+                    0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+                ],
+                output_str: "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            },
+            Test {
+                input: "abc",
+                output: vec![
+                    0x4e, 0x03, 0x65, 0x7a, 0xea, 0x45, 0xa9, 0x4f,
+                    0xc7, 0xd4, 0x7b, 0xa8, 0x26, 0xc8, 0xd6, 0x67,
+                    0xc0, 0xd1, 0xe6, 0xe3, 0x3a, 0x64, 0xa0, 0x36,
+                    0xec, 0x44, 0xf5, 0x8f, 0xa1, 0x2d, 0x6c, 0x45,
+                ],
+                output_str: "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            },
+            Test {
+                input: "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                output: vec![
+                    0xf5, 0x19, 0x74, 0x7e, 0xd5, 0x99, 0x02, 0x4f,
+                    0x38, 0x82, 0x23, 0x8e, 0x5a, 0xb4, 0x39, 0x60,
+                    0x13, 0x25, 0x72, 0xb7, 0x34, 0x5f, 0xbe, 0xb9,
+                    0xa9, 0x07, 0x69, 0xda, 0xfd, 0x21, 0xad, 0x67,
+                ],
+                output_str: "f519747ed599024f3882238e5ab43960132572b7345fbeb9a90769dafd21ad67",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = keccak256::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, keccak256::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash[..], &test.output[..]);
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = keccak256::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = keccak256::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+            assert_eq!(hash.into_inner()[..].as_ref(), test.output.as_slice());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn keccak256_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{keccak256, Hash};
+
+        static HASH_BYTES: [u8; 32] = [
+            0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c,
+            0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+            0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b,
+            0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+        ];
+
+        let hash = keccak256::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(&hash.readable(), &[Token::Str("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")]);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, keccak256};
+
+    #[bench]
+    pub fn keccak256_10(bh: &mut Bencher) {
+        let mut engine = keccak256::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn keccak256_1k(bh: &mut Bencher) {
+        let mut engine = keccak256::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn keccak256_64k(bh: &mut Bencher) {
+        let mut engine = keccak256::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
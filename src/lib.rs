@@ -99,9 +99,12 @@
 #[cfg(bench)] extern crate test;
 #[cfg(any(test, feature = "std"))] extern crate core;
 #[cfg(feature = "core2")] extern crate core2;
+#[cfg(feature = "rand_core")] extern crate rand_core;
 #[cfg(feature = "alloc")] extern crate alloc;
 #[cfg(all(not(feature = "alloc"), feature = "std"))] use std as alloc;
 #[cfg(feature = "serde")] pub extern crate serde;
+#[cfg(feature = "subtle")] pub extern crate subtle;
+#[cfg(feature = "zeroize")] pub extern crate zeroize;
 #[cfg(all(test,feature = "serde"))] extern crate serde_test;
 
 #[doc(hidden)]
@@ -116,25 +119,60 @@ pub mod _export {
 extern crate actual_schemars as schemars;
 
 mod internal_macros;
+mod keccak;
 #[macro_use] mod util;
 #[macro_use] pub mod serde_macros;
 #[cfg(any(feature = "std", feature = "core2"))] mod impls;
 pub mod error;
 pub mod hex;
+pub mod argon2;
+pub mod blake2b;
+pub mod blake2s;
+pub mod blake3;
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+pub mod bloom;
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+pub mod gcs;
 pub mod hash160;
+pub mod hkdf;
 pub mod hmac;
+pub mod hmac_drbg;
+pub mod keccak256;
+pub mod merkle;
+pub mod muhash;
+pub mod murmur3;
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+pub mod partial_merkle_tree;
+pub mod pbkdf2;
+pub mod rfc6979;
 pub mod ripemd160;
+pub mod scrypt;
 pub mod sha1;
+pub mod sha1dc;
+pub mod sha224;
 pub mod sha256;
 pub mod sha256d;
 pub mod sha256t;
+pub mod siphash13;
 pub mod siphash24;
+pub mod siphash24_128;
 pub mod sha512;
+pub mod sha512_256;
+pub mod sha384;
+pub mod sha3_256;
+pub mod shake128;
+pub mod shake256;
+pub mod short_id;
+pub mod taproot;
 pub mod cmp;
 
 use core::{borrow, fmt, hash, ops};
 
-pub use hmac::{Hmac, HmacEngine};
+pub use hmac::{Hmac, HmacEngine, HmacKey};
+pub use hkdf::Hkdf;
 pub use error::Error;
 
 /// A hashing engine which bytes can be serialized into.
@@ -156,6 +194,17 @@ pub trait HashEngine: Clone + Default {
     fn n_bytes_hashed(&self) -> usize;
 }
 
+/// A hashing engine which can be reconstructed from its midstate.
+pub trait FromMidstate: HashEngine {
+    /// Constructs an engine from a midstate and the number of bytes that were hashed to reach
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// If `length` is not a multiple of the block size.
+    fn from_midstate(midstate: Self::MidState, length: usize) -> Self;
+}
+
 /// Trait which applies to hashes of all types.
 pub trait Hash: Copy + Clone + PartialEq + Eq + PartialOrd + Ord +
     hash::Hash + fmt::Debug + fmt::Display + fmt::LowerHex +
@@ -234,5 +283,18 @@ mod tests {
         let h2: TestNewtype = h.to_string().parse().unwrap();
         assert_eq!(h2.as_hash(), h);
     }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize_newtype() {
+        use zeroize::{Zeroize, Zeroizing};
+
+        let mut h = TestNewtype::hash(&[]);
+        h.zeroize();
+        assert_eq!(h, TestNewtype::all_zeros());
+
+        let h = Zeroizing::new(TestNewtype::hash(&[]));
+        assert_eq!(h.as_hash(), sha256d::Hash::hash(&[]));
+    }
 }
 
@@ -0,0 +1,307 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Bitcoin merkle trees.
+//!
+//! Computes the merkle root of a list of hashes the way Bitcoin Core does for the transactions
+//! of a block: hashes are concatenated pairwise and hashed again until a single hash remains,
+//! with the last hash of a level duplicated if the level has an odd number of hashes.
+//!
+//! Duplicating the last hash makes the tree malleable (CVE-2012-2459), for example the lists
+//! `[a, b, c]` and `[a, b, c, c]` have the same root. [`compute_root`] reports the latter as
+//! mutated. A block with mutated transactions must be rejected, but its hash must not be marked
+//! as invalid since the unmutated block may still be valid.
+//!
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::alloc::vec::Vec;
+
+use crate::{sha256d, Hash, HashEngine};
+
+/// Computes the merkle root of `hashes`.
+///
+/// Returns `None` if `hashes` is empty, otherwise the root and whether the tree was mutated, that
+/// is whether two identical hashes were combined. Only `O(log n)` hashes are kept in memory.
+pub fn compute_root<T, I>(hashes: I) -> Option<(T, bool)>
+where
+    T: Hash,
+    I: IntoIterator<Item = T>,
+{
+    compute(hashes, None, |_| {}).map(|(root, mutated, _)| (root, mutated))
+}
+
+/// Computes the witness merkle root of a block from the `wtxid`s of its transactions.
+///
+/// As specified by BIP141 the first `wtxid`, belonging to the coinbase transaction, is replaced
+/// by all zeros. Returns `None` if `wtxids` is empty, see [`compute_root`] for the returned
+/// mutation flag.
+pub fn compute_witness_root<T, I>(wtxids: I) -> Option<(T, bool)>
+where
+    T: Hash,
+    I: IntoIterator<Item = T>,
+{
+    let leaves = wtxids.into_iter()
+        .enumerate()
+        .map(|(i, wtxid)| if i == 0 { T::all_zeros() } else { wtxid });
+    compute_root(leaves)
+}
+
+/// Computes the witness commitment of a block, which is placed in an output of its coinbase
+/// transaction, from its witness merkle root and the witness reserved value.
+pub fn witness_commitment(witness_root: &sha256d::Hash, witness_reserved_value: &[u8; 32]) -> sha256d::Hash {
+    let mut engine = sha256d::Hash::engine();
+    engine.input(&witness_root[..]);
+    engine.input(witness_reserved_value);
+    sha256d::Hash::from_engine(engine)
+}
+
+/// Returns the merkle branch of the hash at `index` in `hashes`, ordered from the leaf to the
+/// root.
+///
+/// Returns `None` if `index` is out of bounds. The root can be recomputed from the branch with
+/// [`root_from_branch`].
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+pub fn branch<T, I>(hashes: I, index: usize) -> Option<Vec<T>>
+where
+    T: Hash,
+    I: IntoIterator<Item = T>,
+{
+    let mut branch = Vec::new();
+    let (_, _, count) = compute(hashes, Some(index as u64), |h| branch.push(h))?;
+    if index as u64 >= count {
+        return None;
+    }
+    Some(branch)
+}
+
+/// Computes the merkle root from the hash at `index` and its merkle `branch`.
+pub fn root_from_branch<T: Hash>(leaf: T, branch: &[T], index: usize) -> T {
+    let mut index = index;
+    let mut h = leaf;
+    for node in branch {
+        h = if index & 1 == 1 { combine(node, &h) } else { combine(&h, node) };
+        index >>= 1;
+    }
+    h
+}
+
+/// Hashes the concatenation of `left` and `right`.
+pub(crate) fn combine<T: Hash>(left: &T, right: &T) -> T {
+    let mut engine = T::engine();
+    engine.input(&left[..]);
+    engine.input(&right[..]);
+    T::from_engine(engine)
+}
+
+/// Computes the merkle tree of `hashes`, calling `branch` with the merkle branch of the leaf at
+/// `branch_pos` ordered from the leaf to the root.
+///
+/// Returns the root, whether the tree was mutated and the number of leaves.
+fn compute<T, I, F>(hashes: I, branch_pos: Option<u64>, mut branch: F) -> Option<(T, bool, u64)>
+where
+    T: Hash,
+    I: IntoIterator<Item = T>,
+    F: FnMut(T),
+{
+    // `inner[level]` holds the root of the last complete subtree of `2^level` leaves which has
+    // not been combined with its sibling yet.
+    let mut inner = [T::all_zeros(); 64];
+    // Level of the subtree in `inner` which contains the leaf at `branch_pos`.
+    let mut match_level = None;
+    let mut mutated = false;
+    let mut count: u64 = 0;
+
+    for leaf in hashes {
+        let mut h = leaf;
+        let mut matches = Some(count) == branch_pos;
+        count += 1;
+        let mut level = 0;
+        while count & (1 << level) == 0 {
+            if matches {
+                branch(inner[level]);
+            } else if match_level == Some(level) {
+                branch(h);
+                matches = true;
+            }
+            mutated |= inner[level] == h;
+            h = combine(&inner[level], &h);
+            level += 1;
+        }
+        inner[level] = h;
+        if matches {
+            match_level = Some(level);
+        }
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    // Combine the remaining subtrees, duplicating the last hash of each level with an odd number
+    // of hashes.
+    let leaves = count;
+    let mut level = count.trailing_zeros() as usize;
+    let mut h = inner[level];
+    let mut matches = match_level == Some(level);
+    while count != 1 << level {
+        if matches {
+            branch(h);
+        }
+        h = combine(&h, &h);
+        count += 1 << level;
+        level += 1;
+        while count & (1 << level) == 0 {
+            if matches {
+                branch(inner[level]);
+            } else if match_level == Some(level) {
+                branch(h);
+                matches = true;
+            }
+            h = combine(&inner[level], &h);
+            level += 1;
+        }
+    }
+    Some((h, mutated, leaves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::FromHex;
+
+    fn leaves(n: u8) -> impl Iterator<Item = sha256d::Hash> {
+        (0..n).map(|i| sha256d::Hash::hash(&[i]))
+    }
+
+    #[test]
+    fn block_100000() {
+        let txids = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+        ];
+        let (root, mutated) = compute_root(txids.iter().map(|s| sha256d::Hash::from_hex(s).unwrap())).unwrap();
+        assert_eq!(root, sha256d::Hash::from_hex("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766").unwrap());
+        assert!(!mutated);
+    }
+
+    #[test]
+    fn roots() {
+        // Computed with a direct implementation of Bitcoin Core's `ComputeMerkleRoot`.
+        struct Test {
+            n: u8,
+            root: &'static str,
+            witness_root: &'static str,
+        }
+
+        let tests = [
+            Test {
+                n: 1,
+                root: "9a538906e6466ebd2617d321f71bc94e56056ce213d366773699e28158e00614",
+                witness_root: "0000000000000000000000000000000000000000000000000000000000000000",
+            },
+            Test {
+                n: 2,
+                root: "55766b905b9b12c5b1ea831fa5ffb90e1cdf3941230d52c7bce2eb38bc83be4b",
+                witness_root: "0fdd842893b9d00896adb5ace6fdbf10da378ac50610ae941c1a49d88bf1ab23",
+            },
+            Test {
+                n: 3,
+                root: "d0c1e5f32d1d424371ac1018770af4446140436d5926d112c67f562fe0df29e1",
+                witness_root: "c787b1c2560479eed712368790ef579987efc5c4148b4ad62752fe4871407f71",
+            },
+            Test {
+                n: 5,
+                root: "48b7979f4fc409cc282b911c6c23eee3aea685f70fcc91bcc3f728d6493811f4",
+                witness_root: "c64b55075dde27a9306d04ad93aafc0c0e580f49b98244a6ea9927e9c40ba027",
+            },
+            Test {
+                n: 7,
+                root: "4da57c69139fb1b4d2ebccb63f239fe9d46aaf94abbec97129c7cd577d5ce67d",
+                witness_root: "fa01fd7c9f79205d674ae3001954d86cebcf0092829170fe38630f18a46aa8f0",
+            },
+            Test {
+                n: 11,
+                root: "7c1a8a58002e8d458febda2579570886832f706bcc7ad183e6a0014b57684ff4",
+                witness_root: "335ac7d231a216296af15b2c503bd85ff4c470bdab2e35be06d370abc46ea84f",
+            },
+        ];
+
+        for test in tests.iter() {
+            let (root, mutated) = compute_root(leaves(test.n)).unwrap();
+            assert_eq!(root, sha256d::Hash::from_hex(test.root).unwrap(), "n = {}", test.n);
+            assert!(!mutated);
+
+            let (root, mutated) = compute_witness_root(leaves(test.n)).unwrap();
+            assert_eq!(root, sha256d::Hash::from_hex(test.witness_root).unwrap(), "n = {}", test.n);
+            assert!(!mutated);
+        }
+
+        assert_eq!(compute_root(leaves(0)), None);
+        assert_eq!(compute_witness_root(leaves(0)), None);
+    }
+
+    #[test]
+    fn mutation() {
+        let mut hashes = [sha256d::Hash::all_zeros(); 8];
+        for (hash, leaf) in hashes.iter_mut().zip(leaves(8)) {
+            *hash = leaf;
+        }
+
+        // Duplicating the last leaf.
+        let (root, mutated) = compute_root(hashes[..3].iter().copied()).unwrap();
+        assert!(!mutated);
+        let mut mutated_hashes = hashes;
+        mutated_hashes[3] = hashes[2];
+        assert_eq!(compute_root(mutated_hashes[..4].iter().copied()), Some((root, true)));
+
+        // Duplicating the last two leaves, which only collide one level up.
+        let (root, mutated) = compute_root(hashes[..6].iter().copied()).unwrap();
+        assert!(!mutated);
+        let mut mutated_hashes = hashes;
+        mutated_hashes[6] = hashes[4];
+        mutated_hashes[7] = hashes[5];
+        assert_eq!(compute_root(mutated_hashes.iter().copied()), Some((root, true)));
+
+        assert_eq!(compute_root(hashes.iter().copied()).map(|(_, mutated)| mutated), Some(false));
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn branches() {
+        for n in 1..=20 {
+            let (root, _) = compute_root(leaves(n)).unwrap();
+            for (index, leaf) in leaves(n).enumerate() {
+                let branch = branch(leaves(n), index).unwrap();
+                assert_eq!(root_from_branch(leaf, &branch, index), root, "n = {}, index = {}", n, index);
+            }
+            assert_eq!(branch(leaves(n), n as usize), None);
+        }
+        assert_eq!(branch(leaves(1), 0), Some(vec![]));
+    }
+
+    #[test]
+    fn witness_commitment_empty_block() {
+        // The commitment of blocks containing only a coinbase transaction, with the usual all
+        // zeros witness reserved value.
+        let (root, _) = compute_witness_root(leaves(1)).unwrap();
+        let commitment = witness_commitment(&root, &[0; 32]);
+        assert_eq!(
+            commitment.into_inner(),
+            <[u8; 32]>::from_hex("e2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf9").unwrap(),
+        );
+    }
+}
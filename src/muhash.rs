@@ -0,0 +1,514 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! MuHash3072 rolling set hash.
+//!
+//! Implementation of the MuHash3072 set hash used by Bitcoin Core for `gettxoutsetinfo muhash`
+//! and assumeutxo snapshots. Every element of the set is expanded into a 3072-bit number by
+//! hashing it with SHA256 and using the result as a ChaCha20 key; the set is represented by the
+//! product of these numbers modulo the prime `2^3072 - 1103717`.
+//!
+//! Since multiplication is commutative, the hash does not depend on the order in which elements
+//! are added, and elements can be removed again by dividing. To avoid computing a modular
+//! inverse on every removal, [`MuHash3072`] keeps a separate numerator and denominator, and only
+//! divides them when the hash is finalized.
+//!
+
+use core::fmt;
+
+use crate::{sha256, Hash};
+
+/// Number of 64-bit limbs in a [`Num3072`].
+const LIMBS: usize = 48;
+
+/// The modulus is `2^3072 - PRIME_DIFF`.
+const PRIME_DIFF: u64 = 1103717;
+
+/// Size of a serialized [`Num3072`], in bytes.
+pub const NUM3072_SIZE: usize = 384;
+
+/// A 3072-bit number modulo `2^3072 - 1103717`.
+///
+/// The number is always kept fully reduced, so two numbers are equal if and only if they
+/// represent the same residue.
+#[derive(Copy, Clone)]
+pub struct Num3072 {
+    limbs: [u64; LIMBS],
+}
+
+impl Num3072 {
+    /// Returns the number one.
+    pub fn one() -> Num3072 {
+        let mut limbs = [0; LIMBS];
+        limbs[0] = 1;
+        Num3072 { limbs }
+    }
+
+    /// Creates a number from its 384-byte little-endian representation, reducing it modulo the
+    /// prime.
+    pub fn from_le_bytes(bytes: &[u8; NUM3072_SIZE]) -> Num3072 {
+        let mut limbs = [0; LIMBS];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(buf);
+        }
+        let mut ret = Num3072 { limbs };
+        ret.reduce();
+        ret
+    }
+
+    /// Returns the 384-byte little-endian representation of the number.
+    pub fn to_le_bytes(&self) -> [u8; NUM3072_SIZE] {
+        let mut bytes = [0; NUM3072_SIZE];
+        for (limb, chunk) in self.limbs.iter().zip(bytes.chunks_mut(8)) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Multiplies the number by `other`.
+    pub fn multiply(&mut self, other: &Num3072) {
+        let mut wide = [0u64; 2 * LIMBS];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let t = u128::from(*a) * u128::from(*b) + u128::from(wide[i + j]) + u128::from(carry);
+                wide[i + j] = t as u64;
+                carry = (t >> 64) as u64;
+            }
+            wide[i + LIMBS] = carry;
+        }
+
+        // Since `2^3072` is congruent to `PRIME_DIFF`, the upper half is folded into the lower
+        // half by multiplying it with `PRIME_DIFF`.
+        let mut carry = 0;
+        for i in 0..LIMBS {
+            let t = u128::from(wide[i]) + u128::from(wide[i + LIMBS]) * u128::from(PRIME_DIFF) + u128::from(carry);
+            self.limbs[i] = t as u64;
+            carry = (t >> 64) as u64;
+        }
+        while carry != 0 {
+            carry = self.add_small(u128::from(carry) * u128::from(PRIME_DIFF));
+        }
+        self.reduce();
+    }
+
+    /// Divides the number by `other`.
+    ///
+    /// Dividing by zero (or a multiple of the prime) results in zero.
+    pub fn divide(&mut self, other: &Num3072) {
+        self.multiply(&other.inverse());
+    }
+
+    /// Returns the modular inverse of the number, or zero if the number is zero.
+    pub fn inverse(&self) -> Num3072 {
+        // By Fermat's little theorem the inverse is `self^(p - 2)`. The exponent is
+        // `2^3072 - 1103719`, which is `2^3051 - 1` followed by the 21 bits of `2^21 - 1103719`.
+        const HIGH_ONES: u32 = 3051;
+        const LOW_BITS: u32 = 21;
+        const LOW: u32 = (1 << LOW_BITS) - (PRIME_DIFF as u32 + 2);
+
+        // Computes `self^(2^n - 1)` by walking the bits of `n`, using that
+        // `x^(2^2k - 1) = (x^(2^k - 1))^(2^k) * x^(2^k - 1)` and
+        // `x^(2^(k+1) - 1) = (x^(2^k - 1))^2 * x`.
+        let mut ret = *self;
+        let mut n = 1;
+        for bit in (0..31 - HIGH_ONES.leading_zeros()).rev() {
+            let acc = ret;
+            ret.square_n(n);
+            ret.multiply(&acc);
+            n *= 2;
+            if HIGH_ONES & (1 << bit) != 0 {
+                ret.square_n(1);
+                ret.multiply(self);
+                n += 1;
+            }
+        }
+        debug_assert_eq!(n, HIGH_ONES);
+
+        for bit in (0..LOW_BITS).rev() {
+            ret.square_n(1);
+            if LOW & (1 << bit) != 0 {
+                ret.multiply(self);
+            }
+        }
+        ret
+    }
+
+    /// Squares the number `n` times.
+    fn square_n(&mut self, n: u32) {
+        for _ in 0..n {
+            let copy = *self;
+            self.multiply(&copy);
+        }
+    }
+
+    /// Adds a value less than `2^128` to the number without reducing it, returning the carry
+    /// out of the top limb.
+    fn add_small(&mut self, mut value: u128) -> u64 {
+        for limb in self.limbs.iter_mut() {
+            if value == 0 {
+                break;
+            }
+            let t = u128::from(*limb) + (value & u128::from(u64::max_value()));
+            *limb = t as u64;
+            value = (value >> 64) + (t >> 64);
+        }
+        value as u64
+    }
+
+    /// Returns true if the number is not less than the prime.
+    fn is_overflow(&self) -> bool {
+        self.limbs[0] > u64::max_value() - PRIME_DIFF
+            && self.limbs[1..].iter().all(|limb| *limb == u64::max_value())
+    }
+
+    /// Brings a number less than twice the prime into the range `[0, p)`.
+    fn reduce(&mut self) {
+        if self.is_overflow() {
+            // Subtracting `2^3072 - PRIME_DIFF` is adding `PRIME_DIFF` and dropping the carry.
+            self.add_small(u128::from(PRIME_DIFF));
+        }
+    }
+}
+
+impl Default for Num3072 {
+    fn default() -> Self {
+        Num3072::one()
+    }
+}
+
+impl PartialEq for Num3072 {
+    fn eq(&self, other: &Num3072) -> bool {
+        self.limbs[..] == other.limbs[..]
+    }
+}
+
+impl Eq for Num3072 {}
+
+impl fmt::Debug for Num3072 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Num3072(0x")?;
+        for limb in self.limbs.iter().rev() {
+            write!(f, "{:016x}", limb)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// A MuHash3072 set hash.
+///
+/// Elements are added with [`MuHash3072::insert`] and removed with [`MuHash3072::remove`], in any
+/// order; removing an element that was never inserted is allowed and cancels out if it is
+/// inserted later.
+#[derive(Debug, Copy, Clone)]
+pub struct MuHash3072 {
+    numerator: Num3072,
+    denominator: Num3072,
+}
+
+impl MuHash3072 {
+    /// Creates the hash of the empty set.
+    pub fn new() -> MuHash3072 {
+        MuHash3072 { numerator: Num3072::one(), denominator: Num3072::one() }
+    }
+
+    /// Creates the hash of a set containing only `element`.
+    pub fn from_element(element: &[u8]) -> MuHash3072 {
+        MuHash3072 { numerator: element_to_num3072(element), denominator: Num3072::one() }
+    }
+
+    /// Creates a hash from its numerator and denominator, as returned by
+    /// [`MuHash3072::numerator`] and [`MuHash3072::denominator`].
+    pub fn from_parts(numerator: Num3072, denominator: Num3072) -> MuHash3072 {
+        MuHash3072 { numerator, denominator }
+    }
+
+    /// Returns the product of all inserted elements.
+    pub fn numerator(&self) -> &Num3072 {
+        &self.numerator
+    }
+
+    /// Returns the product of all removed elements.
+    pub fn denominator(&self) -> &Num3072 {
+        &self.denominator
+    }
+
+    /// Adds `element` to the set.
+    pub fn insert(&mut self, element: &[u8]) {
+        self.numerator.multiply(&element_to_num3072(element));
+    }
+
+    /// Removes `element` from the set.
+    pub fn remove(&mut self, element: &[u8]) {
+        self.denominator.multiply(&element_to_num3072(element));
+    }
+
+    /// Adds all elements of `other` to the set, and removes the elements removed from `other`.
+    pub fn combine(&mut self, other: &MuHash3072) {
+        self.numerator.multiply(&other.numerator);
+        self.denominator.multiply(&other.denominator);
+    }
+
+    /// Removes all elements of `other` from the set, and adds the elements removed from `other`.
+    pub fn divide(&mut self, other: &MuHash3072) {
+        self.numerator.multiply(&other.denominator);
+        self.denominator.multiply(&other.numerator);
+    }
+
+    /// Divides the numerator by the denominator, so that the numerator alone represents the set.
+    ///
+    /// This computes a modular inverse and is not required before [`MuHash3072::finalize`].
+    pub fn normalize(&mut self) {
+        self.numerator.divide(&self.denominator);
+        self.denominator = Num3072::one();
+    }
+
+    /// Computes the SHA256 hash of the 384-byte little-endian representation of the set.
+    pub fn finalize(&self) -> sha256::Hash {
+        let mut num = self.numerator;
+        num.divide(&self.denominator);
+        sha256::Hash::hash(&num.to_le_bytes())
+    }
+}
+
+impl Default for MuHash3072 {
+    fn default() -> Self {
+        MuHash3072::new()
+    }
+}
+
+/// Expands `element` into a 3072-bit number using the ChaCha20 keystream keyed with the
+/// SHA256 hash of the element.
+fn element_to_num3072(element: &[u8]) -> Num3072 {
+    let key = sha256::Hash::hash(element).into_inner();
+
+    let mut bytes = [0; NUM3072_SIZE];
+    for (counter, chunk) in bytes.chunks_mut(CHACHA20_BLOCK_SIZE).enumerate() {
+        chunk.copy_from_slice(&chacha20_block(&key, counter as u32));
+    }
+    Num3072::from_le_bytes(&bytes)
+}
+
+/// Size of a ChaCha20 block, in bytes.
+const CHACHA20_BLOCK_SIZE: usize = 64;
+
+/// The ChaCha20 block function with a zero nonce.
+fn chacha20_block(key: &[u8; 32], counter: u32) -> [u8; CHACHA20_BLOCK_SIZE] {
+    macro_rules! quarter_round {
+        ($x:ident, $a:expr, $b:expr, $c:expr, $d:expr) => {
+            $x[$a] = $x[$a].wrapping_add($x[$b]); $x[$d] = ($x[$d] ^ $x[$a]).rotate_left(16);
+            $x[$c] = $x[$c].wrapping_add($x[$d]); $x[$b] = ($x[$b] ^ $x[$c]).rotate_left(12);
+            $x[$a] = $x[$a].wrapping_add($x[$b]); $x[$d] = ($x[$d] ^ $x[$a]).rotate_left(8);
+            $x[$c] = $x[$c].wrapping_add($x[$d]); $x[$b] = ($x[$b] ^ $x[$c]).rotate_left(7);
+        };
+    }
+
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+    for (word, chunk) in state[4..12].iter_mut().zip(key.chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    state[12] = counter;
+
+    let mut x = state;
+    for _ in 0..10 {
+        // Column round.
+        quarter_round!(x, 0, 4, 8, 12);
+        quarter_round!(x, 1, 5, 9, 13);
+        quarter_round!(x, 2, 6, 10, 14);
+        quarter_round!(x, 3, 7, 11, 15);
+        // Diagonal round.
+        quarter_round!(x, 0, 5, 10, 15);
+        quarter_round!(x, 1, 6, 11, 12);
+        quarter_round!(x, 2, 7, 8, 13);
+        quarter_round!(x, 3, 4, 9, 14);
+    }
+
+    let mut block = [0; CHACHA20_BLOCK_SIZE];
+    for ((x, s), chunk) in x.iter().zip(&state).zip(block.chunks_mut(4)) {
+        chunk.copy_from_slice(&x.wrapping_add(*s).to_le_bytes());
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::FromHex;
+
+    fn from_int(i: u8) -> MuHash3072 {
+        let mut element = [0; 32];
+        element[0] = i;
+        MuHash3072::from_element(&element)
+    }
+
+    /// Returns `2^3072 - PRIME_DIFF + diff`.
+    fn prime_plus(diff: u64) -> [u8; NUM3072_SIZE] {
+        let mut bytes = [0xff; NUM3072_SIZE];
+        bytes[..8].copy_from_slice(&(u64::max_value() - PRIME_DIFF + 1 + diff).to_le_bytes());
+        bytes
+    }
+
+    fn small(n: u64) -> Num3072 {
+        let mut bytes = [0; NUM3072_SIZE];
+        bytes[..8].copy_from_slice(&n.to_le_bytes());
+        Num3072::from_le_bytes(&bytes)
+    }
+
+    #[test]
+    fn core_vectors() {
+        // Test vectors from Bitcoin Core's crypto_tests.cpp, which displays the hash reversed as
+        // `10d312b100cbd32ada024a6646e40d3482fcff103668d2625f10002a607d5863`.
+        let expected = sha256::Hash::from_hex("63587d602a00105f62d2683610fffc82340de446664a02da2ad3cb00b112d310").unwrap();
+
+        let mut acc = from_int(0);
+        acc.combine(&from_int(1));
+        acc.divide(&from_int(2));
+        assert_eq!(acc.finalize(), expected);
+
+        let (mut one, mut two) = ([0; 32], [0; 32]);
+        one[0] = 1;
+        two[0] = 2;
+        let mut acc = from_int(0);
+        acc.insert(&one);
+        acc.remove(&two);
+        assert_eq!(acc.finalize(), expected);
+
+        acc.normalize();
+        assert_eq!(acc.denominator(), &Num3072::one());
+        assert_eq!(acc.finalize(), expected);
+    }
+
+    #[test]
+    fn empty_set() {
+        // The SHA256 hash of the little-endian encoding of one.
+        let expected = sha256::Hash::from_hex("c85525462fdcf30a2c18d6f4b92923000974355c2477f59594d2c205a1d25add").unwrap();
+        assert_eq!(MuHash3072::new().finalize(), expected);
+
+        let mut acc = from_int(7);
+        acc.divide(&from_int(7));
+        assert_eq!(acc.finalize(), expected);
+    }
+
+    #[test]
+    fn order_independence() {
+        let elements = [3u8, 14, 15, 92, 65];
+        let mut forward = MuHash3072::new();
+        for i in elements.iter() {
+            forward.insert(&[*i]);
+        }
+        let mut backward = MuHash3072::new();
+        for i in elements.iter().rev() {
+            backward.insert(&[*i]);
+        }
+        assert_eq!(forward.finalize(), backward.finalize());
+
+        // Removing before inserting gives the same result as never touching the element.
+        let mut acc = MuHash3072::new();
+        acc.remove(&[42]);
+        acc.combine(&forward);
+        acc.insert(&[42]);
+        assert_eq!(acc.finalize(), forward.finalize());
+
+        // Combining two partial sets.
+        let mut left = MuHash3072::new();
+        left.insert(&[3]);
+        left.insert(&[14]);
+        let mut right = MuHash3072::new();
+        right.insert(&[15]);
+        right.insert(&[92]);
+        right.insert(&[65]);
+        left.combine(&right);
+        assert_eq!(left.finalize(), forward.finalize());
+
+        let parts = MuHash3072::from_parts(*left.numerator(), *left.denominator());
+        assert_eq!(parts.finalize(), forward.finalize());
+    }
+
+    #[test]
+    fn num3072_reduction() {
+        let zero = Num3072::from_le_bytes(&[0; NUM3072_SIZE]);
+        assert_eq!(Num3072::from_le_bytes(&prime_plus(0)), zero);
+        assert_eq!(Num3072::from_le_bytes(&prime_plus(1)), Num3072::one());
+        assert_eq!(Num3072::from_le_bytes(&[0xff; NUM3072_SIZE]), small(PRIME_DIFF - 1));
+
+        // (p - 1)^2 = 1.
+        let mut bytes = prime_plus(0);
+        bytes[0] -= 1;
+        let minus_one = Num3072::from_le_bytes(&bytes);
+        let mut square = minus_one;
+        square.multiply(&minus_one);
+        assert_eq!(square, Num3072::one());
+
+        // 2^3071 * 2 = 2^3072 = PRIME_DIFF.
+        let mut bytes = [0; NUM3072_SIZE];
+        bytes[NUM3072_SIZE - 1] = 0x80;
+        let mut num = Num3072::from_le_bytes(&bytes);
+        num.multiply(&small(2));
+        assert_eq!(num, small(PRIME_DIFF));
+        assert_eq!(Num3072::from_le_bytes(&num.to_le_bytes()), num);
+    }
+
+    #[test]
+    fn num3072_inverse() {
+        assert_eq!(Num3072::one().inverse(), Num3072::one());
+        assert_eq!(small(0).inverse(), small(0));
+
+        let mut num = *from_int(1).numerator();
+        let inverse = num.inverse();
+        assert_ne!(inverse, num);
+        num.multiply(&inverse);
+        assert_eq!(num, Num3072::one());
+
+        let mut num = small(6);
+        num.divide(&small(3));
+        assert_eq!(num, small(2));
+    }
+
+    #[test]
+    fn chacha20_block_function() {
+        // Test vector #1 from RFC 8439, appendix A.1.
+        let expected = <[u8; CHACHA20_BLOCK_SIZE]>::from_hex(
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7\
+             da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586",
+        ).unwrap();
+        assert_eq!(&chacha20_block(&[0; 32], 0)[..], &expected[..]);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::muhash::MuHash3072;
+
+    #[bench]
+    pub fn muhash_insert(bh: &mut Bencher) {
+        let mut acc = MuHash3072::new();
+        let element = [1u8; 36];
+        bh.iter(|| {
+            acc.insert(&element);
+        });
+    }
+
+    #[bench]
+    pub fn muhash_finalize(bh: &mut Bencher) {
+        let acc = MuHash3072::from_element(&[1u8; 36]);
+        bh.iter(|| {
+            let _ = acc.finalize();
+        });
+    }
+}
@@ -0,0 +1,258 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! MurmurHash3 (x86_32) implementation.
+//!
+//! This is the non-cryptographic hash function used by BIP37 bloom filters, see the
+//! `bloom` module.
+//!
+
+use core::{cmp, str};
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, Hash as _, HashEngine as _, hex};
+
+crate::internal_macros::hash_type! {
+    32,
+    false,
+    "Output of the MurmurHash3 (x86_32) hash function.",
+    "crate::util::json_hex_string::len_4"
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    Hash::from_u32(Hash::from_engine_to_u32(e))
+}
+
+const C1: u32 = 0xcc9e2d51;
+const C2: u32 = 0x1b873593;
+
+#[inline]
+fn mix_k1(mut k1: u32) -> u32 {
+    k1 = k1.wrapping_mul(C1);
+    k1 = k1.rotate_left(15);
+    k1.wrapping_mul(C2)
+}
+
+/// Engine to compute the MurmurHash3 hash function.
+#[derive(Debug, Clone)]
+pub struct HashEngine {
+    seed: u32,
+    h1: u32,
+    length: usize,   // how many bytes we've processed
+    tail: [u8; 4],   // unprocessed bytes
+    ntail: usize,    // how many bytes in tail are valid
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, seed, h1, length, tail, ntail);
+
+impl HashEngine {
+    /// Creates a new MurmurHash3 engine with a seed.
+    pub fn with_seed(seed: u32) -> HashEngine {
+        HashEngine {
+            seed,
+            h1: seed,
+            length: 0,
+            tail: [0; 4],
+            ntail: 0,
+        }
+    }
+
+    /// Creates a new MurmurHash3 engine.
+    pub fn new() -> HashEngine {
+        HashEngine::with_seed(0)
+    }
+
+    /// Retrieves the seed of this engine.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    #[inline]
+    fn process_block(&mut self, block: [u8; 4]) {
+        self.h1 ^= mix_k1(u32::from_le_bytes(block));
+        self.h1 = self.h1.rotate_left(13);
+        self.h1 = self.h1.wrapping_mul(5).wrapping_add(0xe6546b64);
+    }
+}
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine::new()
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = u32;
+
+    fn midstate(&self) -> u32 {
+        self.h1
+    }
+
+    const BLOCK_SIZE: usize = 4;
+
+    fn input(&mut self, mut msg: &[u8]) {
+        self.length += msg.len();
+
+        while !msg.is_empty() {
+            let write_len = cmp::min(4 - self.ntail, msg.len());
+            self.tail[self.ntail..self.ntail + write_len].copy_from_slice(&msg[..write_len]);
+            self.ntail += write_len;
+            msg = &msg[write_len..];
+
+            if self.ntail == 4 {
+                let block = self.tail;
+                self.process_block(block);
+                self.ntail = 0;
+            }
+        }
+    }
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length
+    }
+}
+
+impl Hash {
+    /// Hashes the given data with an engine with the provided seed.
+    pub fn hash_with_seed(seed: u32, data: &[u8]) -> Hash {
+        let mut engine = HashEngine::with_seed(seed);
+        engine.input(data);
+        Hash::from_engine(engine)
+    }
+
+    /// Hashes the given data directly to u32 with an engine with the provided seed.
+    pub fn hash_to_u32_with_seed(seed: u32, data: &[u8]) -> u32 {
+        let mut engine = HashEngine::with_seed(seed);
+        engine.input(data);
+        Hash::from_engine_to_u32(engine)
+    }
+
+    /// Produces a hash as `u32` from the current state of a given engine.
+    #[inline]
+    pub fn from_engine_to_u32(e: HashEngine) -> u32 {
+        let mut h1 = e.h1;
+
+        if e.ntail > 0 {
+            let mut block = [0; 4];
+            block[..e.ntail].copy_from_slice(&e.tail[..e.ntail]);
+            h1 ^= mix_k1(u32::from_le_bytes(block));
+        }
+
+        // The length is mixed in modulo 2^32, as in the reference implementation.
+        h1 ^= e.length as u32;
+        h1 ^= h1 >> 16;
+        h1 = h1.wrapping_mul(0x85ebca6b);
+        h1 ^= h1 >> 13;
+        h1 = h1.wrapping_mul(0xc2b2ae35);
+        h1 ^ (h1 >> 16)
+    }
+
+    /// Returns the (little endian) 32-bit integer representation of the hash value.
+    pub fn as_u32(&self) -> u32 {
+        u32::from_le_bytes(self.0)
+    }
+
+    /// Creates a hash from its (little endian) 32-bit integer representation.
+    pub fn from_u32(hash: u32) -> Hash {
+        Hash(hash.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_murmur3() {
+        // Test vectors from Bitcoin Core's hash_tests.cpp
+        let vecs: [(u32, u32, &[u8]); 14] = [
+            (0x00000000, 0x00000000, &[]),
+            (0x6a396f08, 0xfba4c795, &[]),
+            (0x81f16f39, 0xffffffff, &[]),
+            (0x514e28b7, 0x00000000, &[0x00]),
+            (0xea3f0b17, 0xfba4c795, &[0x00]),
+            (0xfd6cf10d, 0x00000000, &[0xff]),
+            (0x16c6b7ab, 0x00000000, &[0x00, 0x11]),
+            (0x8eb51c3d, 0x00000000, &[0x00, 0x11, 0x22]),
+            (0xb4471bf8, 0x00000000, &[0x00, 0x11, 0x22, 0x33]),
+            (0xe2301fa8, 0x00000000, &[0x00, 0x11, 0x22, 0x33, 0x44]),
+            (0xfc2e4a15, 0x00000000, &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+            (0xb074502c, 0x00000000, &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66]),
+            (0x8034d2a0, 0x00000000, &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]),
+            (0xb4698def, 0x00000000, &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]),
+        ];
+
+        for (i, &(expected, seed, data)) in vecs.iter().enumerate() {
+            assert_eq!(Hash::hash_to_u32_with_seed(seed, data), expected, "vec #{}", i);
+
+            let hash = Hash::hash_with_seed(seed, data);
+            assert_eq!(hash.as_u32(), expected, "vec #{}", i);
+            assert_eq!(Hash::from_u32(expected), hash, "vec #{}", i);
+
+            let mut engine = HashEngine::with_seed(seed);
+            for byte in data {
+                engine.input(&[*byte]);
+            }
+            assert_eq!(engine.n_bytes_hashed(), data.len());
+            assert_eq!(Hash::from_engine(engine), hash, "vec #{}", i);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        // The hash is displayed in little endian byte order, like `siphash24`.
+        let hash = Hash::hash_with_seed(0xfba4c795, &[]);
+        assert_eq!(hash.as_u32(), 0x6a396f08);
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        assert_eq!(crate::hex::ToHex::to_hex(&hash), "086f396a");
+        assert_eq!(HashEngine::with_seed(0xfba4c795).seed(), 0xfba4c795);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, murmur3};
+
+    #[bench]
+    pub fn murmur3_1ki(bh: &mut Bencher) {
+        let mut engine = murmur3::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter(|| {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn murmur3_64ki(bh: &mut Bencher) {
+        let mut engine = murmur3::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter(|| {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn murmur3_1ki_hash_u32(bh: &mut Bencher) {
+        let bytes = [1u8; 1024];
+        bh.iter(|| {
+            let _ = murmur3::Hash::hash_to_u32_with_seed(0xfba4c795, &bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
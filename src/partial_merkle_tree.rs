@@ -0,0 +1,449 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BIP37 partial merkle trees.
+//!
+//! A partial merkle tree proves that a set of transactions is committed to by the merkle root of
+//! a block header, as sent in `merkleblock` messages. Parsing and verification follow Bitcoin
+//! Core's `CPartialMerkleTree`, including its checks against malformed trees.
+//!
+
+use core::{cmp, fmt};
+
+use crate::alloc::vec::Vec;
+
+use crate::sha256d;
+use crate::bloom::write_compact_size;
+use crate::merkle::combine;
+
+/// Maximum number of transactions a block can contain, the maximum block weight divided by the
+/// minimum transaction weight.
+pub const MAX_TRANSACTIONS: u32 = 4_000_000 / 240;
+
+/// Partial merkle tree error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The tree does not contain any transactions.
+    NoTransactions,
+    /// The tree contains more than [`MAX_TRANSACTIONS`] transactions.
+    TooManyTransactions(u32),
+    /// The tree contains more hashes than transactions.
+    TooManyHashes,
+    /// The tree contains fewer flag bits than hashes.
+    NotEnoughBits,
+    /// Traversing the tree needed more flag bits than present.
+    BitsExhausted,
+    /// Traversing the tree needed more hashes than present.
+    HashesExhausted,
+    /// The left and right branches of a node were identical.
+    IdenticalHashesFound,
+    /// Not all flag bits were used, excluding the padding of the last byte.
+    NotAllBitsConsumed,
+    /// Not all hashes were used.
+    NotAllHashesConsumed,
+    /// The computed root does not match the expected merkle root.
+    MerkleRootMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NoTransactions => f.write_str("partial merkle tree contains no transactions"),
+            Error::TooManyTransactions(n) => write!(f, "partial merkle tree contains too many transactions: {} (maximum {})", n, MAX_TRANSACTIONS),
+            Error::TooManyHashes => f.write_str("partial merkle tree contains more hashes than transactions"),
+            Error::NotEnoughBits => f.write_str("partial merkle tree contains fewer flag bits than hashes"),
+            Error::BitsExhausted => f.write_str("partial merkle tree overflowed its flag bits"),
+            Error::HashesExhausted => f.write_str("partial merkle tree overflowed its hashes"),
+            Error::IdenticalHashesFound => f.write_str("partial merkle tree contains identical sibling hashes"),
+            Error::NotAllBitsConsumed => f.write_str("not all flag bits of the partial merkle tree were used"),
+            Error::NotAllHashesConsumed => f.write_str("not all hashes of the partial merkle tree were used"),
+            Error::MerkleRootMismatch => f.write_str("partial merkle tree does not match the merkle root"),
+        }
+    }
+}
+
+/// A BIP37 partial merkle tree.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PartialMerkleTree {
+    num_transactions: u32,
+    bits: Vec<bool>,
+    hashes: Vec<sha256d::Hash>,
+}
+
+impl PartialMerkleTree {
+    /// Builds the partial merkle tree of the transactions of a block, given by their `txids`,
+    /// which proves the transactions for which `matches` is `true`.
+    ///
+    /// # Panics
+    ///
+    /// If `txids` is empty, has more than `u32::MAX` elements or if `txids` and `matches` have
+    /// different lengths.
+    pub fn from_txids(txids: &[sha256d::Hash], matches: &[bool]) -> PartialMerkleTree {
+        assert!(!txids.is_empty(), "a block always contains a coinbase transaction");
+        assert!(txids.len() <= u32::max_value() as usize, "too many transactions");
+        assert_eq!(txids.len(), matches.len(), "a match flag is needed for each transaction");
+
+        let mut pmt = PartialMerkleTree {
+            num_transactions: txids.len() as u32,
+            bits: Vec::new(),
+            hashes: Vec::new(),
+        };
+        pmt.traverse_and_build(pmt.height(), 0, txids, matches);
+        pmt
+    }
+
+    /// Creates a tree from the fields of a `merkleblock` message, with the flag bits packed into
+    /// `flags` in little endian bit order.
+    ///
+    /// The tree is not checked, this happens when extracting the matches.
+    pub fn from_parts(num_transactions: u32, hashes: Vec<sha256d::Hash>, flags: &[u8]) -> PartialMerkleTree {
+        let bits = (0..flags.len() * 8).map(|i| flags[i / 8] & (1 << (i % 8)) != 0).collect();
+        PartialMerkleTree { num_transactions, bits, hashes }
+    }
+
+    /// Returns the number of transactions in the block.
+    pub fn num_transactions(&self) -> u32 {
+        self.num_transactions
+    }
+
+    /// Returns the flag bits of the depth-first traversal of the tree.
+    pub fn bits(&self) -> &[bool] {
+        &self.bits
+    }
+
+    /// Returns the hashes of the depth-first traversal of the tree.
+    pub fn hashes(&self) -> &[sha256d::Hash] {
+        &self.hashes
+    }
+
+    /// Extracts the matched transactions, appending their txids to `matches` and their positions
+    /// in the block to `indexes`. Returns the merkle root of the tree.
+    ///
+    /// The root still has to be compared to that of the block header, see
+    /// [`PartialMerkleTree::verify`].
+    pub fn extract_matches(&self, matches: &mut Vec<sha256d::Hash>, indexes: &mut Vec<u32>) -> Result<sha256d::Hash, Error> {
+        if self.num_transactions == 0 {
+            return Err(Error::NoTransactions);
+        }
+        if self.num_transactions > MAX_TRANSACTIONS {
+            return Err(Error::TooManyTransactions(self.num_transactions));
+        }
+        if self.hashes.len() > self.num_transactions as usize {
+            return Err(Error::TooManyHashes);
+        }
+        if self.bits.len() < self.hashes.len() {
+            return Err(Error::NotEnoughBits);
+        }
+
+        let mut bits_used = 0;
+        let mut hashes_used = 0;
+        let root = self.traverse_and_extract(self.height(), 0, &mut bits_used, &mut hashes_used, matches, indexes)?;
+        // The flag bits are serialized as bytes, so only the padding of the last byte may be
+        // unused.
+        if (bits_used + 7) / 8 != (self.bits.len() + 7) / 8 {
+            return Err(Error::NotAllBitsConsumed);
+        }
+        if hashes_used != self.hashes.len() {
+            return Err(Error::NotAllHashesConsumed);
+        }
+        Ok(root)
+    }
+
+    /// Extracts the matched transactions like [`PartialMerkleTree::extract_matches`] and checks
+    /// that the tree commits to `merkle_root`.
+    pub fn verify(&self, merkle_root: &sha256d::Hash, matches: &mut Vec<sha256d::Hash>, indexes: &mut Vec<u32>) -> Result<(), Error> {
+        if self.extract_matches(matches, indexes)? == *merkle_root {
+            Ok(())
+        } else {
+            Err(Error::MerkleRootMismatch)
+        }
+    }
+
+    /// Serializes the tree as it appears in a `merkleblock` message.
+    pub fn serialize(&self) -> Vec<u8> {
+        let n_flag_bytes = (self.bits.len() + 7) / 8;
+        let mut ret = Vec::with_capacity(4 + 9 + self.hashes.len() * 32 + 9 + n_flag_bytes);
+        ret.extend_from_slice(&self.num_transactions.to_le_bytes());
+        write_compact_size(&mut ret, self.hashes.len() as u64);
+        for hash in &self.hashes {
+            ret.extend_from_slice(&hash[..]);
+        }
+        write_compact_size(&mut ret, n_flag_bytes as u64);
+        let start = ret.len();
+        ret.resize(start + n_flag_bytes, 0);
+        for (i, bit) in self.bits.iter().enumerate() {
+            if *bit {
+                ret[start + i / 8] |= 1 << (i % 8);
+            }
+        }
+        ret
+    }
+
+    /// Returns the number of nodes at `height`, where the leaves are at height zero.
+    fn tree_width(&self, height: u32) -> u32 {
+        ((self.num_transactions as u64 + (1 << height) - 1) >> height) as u32
+    }
+
+    /// Returns the height of the root.
+    fn height(&self) -> u32 {
+        let mut height = 0;
+        while self.tree_width(height) > 1 {
+            height += 1;
+        }
+        height
+    }
+
+    /// Computes the hash of the node at `height` and `pos`.
+    fn calc_hash(&self, height: u32, pos: u32, txids: &[sha256d::Hash]) -> sha256d::Hash {
+        if height == 0 {
+            return txids[pos as usize];
+        }
+        let left = self.calc_hash(height - 1, pos * 2, txids);
+        let right = if pos * 2 + 1 < self.tree_width(height - 1) {
+            self.calc_hash(height - 1, pos * 2 + 1, txids)
+        } else {
+            left
+        };
+        combine(&left, &right)
+    }
+
+    fn traverse_and_build(&mut self, height: u32, pos: u32, txids: &[sha256d::Hash], matches: &[bool]) {
+        let start = (pos as usize) << height;
+        let end = cmp::min((pos as usize + 1) << height, self.num_transactions as usize);
+        let parent_of_match = matches[start..end].iter().any(|m| *m);
+        self.bits.push(parent_of_match);
+
+        if height == 0 || !parent_of_match {
+            let hash = self.calc_hash(height, pos, txids);
+            self.hashes.push(hash);
+        } else {
+            self.traverse_and_build(height - 1, pos * 2, txids, matches);
+            if pos * 2 + 1 < self.tree_width(height - 1) {
+                self.traverse_and_build(height - 1, pos * 2 + 1, txids, matches);
+            }
+        }
+    }
+
+    fn traverse_and_extract(
+        &self,
+        height: u32,
+        pos: u32,
+        bits_used: &mut usize,
+        hashes_used: &mut usize,
+        matches: &mut Vec<sha256d::Hash>,
+        indexes: &mut Vec<u32>,
+    ) -> Result<sha256d::Hash, Error> {
+        let parent_of_match = *self.bits.get(*bits_used).ok_or(Error::BitsExhausted)?;
+        *bits_used += 1;
+
+        if height == 0 || !parent_of_match {
+            let hash = *self.hashes.get(*hashes_used).ok_or(Error::HashesExhausted)?;
+            *hashes_used += 1;
+            if height == 0 && parent_of_match {
+                matches.push(hash);
+                indexes.push(pos);
+            }
+            return Ok(hash);
+        }
+
+        let left = self.traverse_and_extract(height - 1, pos * 2, bits_used, hashes_used, matches, indexes)?;
+        let right = if pos * 2 + 1 < self.tree_width(height - 1) {
+            let right = self.traverse_and_extract(height - 1, pos * 2 + 1, bits_used, hashes_used, matches, indexes)?;
+            // The txids covered by both branches are unique, so identical branches can only come
+            // from a mutated tree (CVE-2012-2459).
+            if right == left {
+                return Err(Error::IdenticalHashesFound);
+            }
+            right
+        } else {
+            left
+        };
+        Ok(combine(&left, &right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{merkle, Hash};
+
+    /// Deterministic xorshift generator standing in for Bitcoin Core's `InsecureRand`.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn bits(&mut self, bits: u32) -> u64 {
+            self.next() & ((1 << bits) - 1)
+        }
+    }
+
+    fn deserialize(data: &[u8]) -> PartialMerkleTree {
+        // Only single byte compact sizes are produced by the trees used in these tests.
+        let num_transactions = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let (n_hashes, data) = read_compact_size(&data[4..]);
+        let hashes = data[..n_hashes * 32].chunks(32).map(|h| sha256d::Hash::from_slice(h).unwrap()).collect();
+        let (n_flag_bytes, data) = read_compact_size(&data[n_hashes * 32..]);
+        assert_eq!(data.len(), n_flag_bytes);
+        PartialMerkleTree::from_parts(num_transactions, hashes, data)
+    }
+
+    fn read_compact_size(data: &[u8]) -> (usize, &[u8]) {
+        match data[0] {
+            0xfd => (u16::from_le_bytes([data[1], data[2]]) as usize, &data[3..]),
+            n => (n as usize, &data[1..]),
+        }
+    }
+
+    // Port of `pmt_test1` from Bitcoin Core's pmt_tests.cpp.
+    #[test]
+    fn pmt_test1() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let tx_counts = [1, 4, 7, 17, 56, 100, 127, 256, 312, 513, 1000, 4095];
+
+        for &num_tx in tx_counts.iter() {
+            let txids: Vec<sha256d::Hash> = (0..num_tx as u32).map(|i| sha256d::Hash::hash(&i.to_le_bytes())).collect();
+            let (merkle_root_1, _) = merkle::compute_root(txids.iter().copied()).unwrap();
+
+            let mut height = 1;
+            let mut n = num_tx;
+            while n > 1 {
+                n = (n + 1) / 2;
+                height += 1;
+            }
+
+            // Check with random subsets with inclusion chances 1, 1/2, 1/4, ..., 1/128.
+            for att in 1..15 {
+                let matches: Vec<bool> = (0..num_tx).map(|_| rng.bits(att / 2) == 0).collect();
+                let match_txids_1: Vec<sha256d::Hash> = txids.iter()
+                    .zip(matches.iter())
+                    .filter(|(_, m)| **m)
+                    .map(|(txid, _)| *txid)
+                    .collect();
+
+                let pmt1 = PartialMerkleTree::from_txids(&txids, &matches);
+                let serialized = pmt1.serialize();
+
+                // Verify the size guarantees of the tree.
+                let n = cmp::min(num_tx, 1 + match_txids_1.len() * height);
+                assert!(serialized.len() <= 10 + (258 * n + 7) / 8);
+
+                let pmt2 = deserialize(&serialized);
+                assert_eq!(pmt2.num_transactions(), pmt1.num_transactions());
+                assert_eq!(pmt2.hashes(), pmt1.hashes());
+
+                let mut match_txids_2 = vec![];
+                let mut indexes = vec![];
+                let merkle_root_2 = pmt2.extract_matches(&mut match_txids_2, &mut indexes).unwrap();
+                assert_eq!(merkle_root_2, merkle_root_1);
+                assert_eq!(match_txids_2, match_txids_1);
+                for (index, txid) in indexes.iter().zip(match_txids_2.iter()) {
+                    assert_eq!(txids[*index as usize], *txid);
+                }
+                assert_eq!(pmt2.verify(&merkle_root_1, &mut vec![], &mut vec![]), Ok(()));
+
+                // Check that random bit flips break the authentication.
+                for _ in 0..4 {
+                    let mut pmt3 = pmt2.clone();
+                    let n = rng.next() as usize % pmt3.hashes.len();
+                    let bit = rng.bits(8) as usize;
+                    let mut hash = pmt3.hashes[n].into_inner();
+                    hash[bit >> 3] ^= 1 << (bit & 7);
+                    pmt3.hashes[n] = sha256d::Hash::from_inner(hash);
+
+                    let merkle_root_3 = pmt3.extract_matches(&mut vec![], &mut vec![]);
+                    assert_ne!(merkle_root_3, Ok(merkle_root_1));
+                    assert!(pmt3.verify(&merkle_root_1, &mut vec![], &mut vec![]).is_err());
+                }
+            }
+        }
+    }
+
+    // Port of `pmt_malleability` from Bitcoin Core's pmt_tests.cpp.
+    #[test]
+    fn pmt_malleability() {
+        let txids: Vec<sha256d::Hash> = [1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 9, 10].iter()
+            .map(|n| {
+                let mut hash = [0; 32];
+                hash[0] = *n;
+                sha256d::Hash::from_inner(hash)
+            })
+            .collect();
+        let matches = [false, false, false, false, false, false, false, false, false, true, true, false];
+
+        let tree = PartialMerkleTree::from_txids(&txids, &matches);
+        assert_eq!(tree.extract_matches(&mut vec![], &mut vec![]), Err(Error::IdenticalHashesFound));
+    }
+
+    #[test]
+    fn malformed() {
+        let txids: Vec<sha256d::Hash> = (0..7u32).map(|i| sha256d::Hash::hash(&i.to_le_bytes())).collect();
+        let matches = [false, true, false, false, false, true, false];
+        let tree = PartialMerkleTree::from_txids(&txids, &matches);
+        let flags = |tree: &PartialMerkleTree| {
+            let mut flags = vec![0; (tree.bits.len() + 7) / 8];
+            for (i, bit) in tree.bits.iter().enumerate() {
+                flags[i / 8] |= (*bit as u8) << (i % 8);
+            }
+            flags
+        };
+
+        let extract = |tree: &PartialMerkleTree| tree.extract_matches(&mut vec![], &mut vec![]);
+        assert!(extract(&tree).is_ok());
+
+        let no_tx = PartialMerkleTree::from_parts(0, vec![], &[]);
+        assert_eq!(extract(&no_tx), Err(Error::NoTransactions));
+
+        let too_many_tx = PartialMerkleTree::from_parts(MAX_TRANSACTIONS + 1, tree.hashes.clone(), &flags(&tree));
+        assert_eq!(extract(&too_many_tx), Err(Error::TooManyTransactions(MAX_TRANSACTIONS + 1)));
+
+        let too_many_hashes = PartialMerkleTree::from_parts(2, tree.hashes.clone(), &flags(&tree));
+        assert_eq!(extract(&too_many_hashes), Err(Error::TooManyHashes));
+
+        let not_enough_bits = PartialMerkleTree::from_parts(7, tree.hashes.clone(), &[]);
+        assert_eq!(extract(&not_enough_bits), Err(Error::NotEnoughBits));
+
+        let mut hashes = tree.hashes.clone();
+        hashes.pop();
+        let missing_hash = PartialMerkleTree::from_parts(7, hashes, &flags(&tree));
+        assert_eq!(extract(&missing_hash), Err(Error::HashesExhausted));
+
+        let mut hashes = tree.hashes.clone();
+        hashes.push(txids[0]);
+        let extra_hash = PartialMerkleTree::from_parts(7, hashes, &flags(&tree));
+        assert_eq!(extract(&extra_hash), Err(Error::NotAllHashesConsumed));
+
+        let mut extra_bits = flags(&tree);
+        extra_bits.push(0);
+        let extra_bits = PartialMerkleTree::from_parts(7, tree.hashes.clone(), &extra_bits);
+        assert_eq!(extract(&extra_bits), Err(Error::NotAllBitsConsumed));
+
+        let mut bits = tree.bits.clone();
+        bits.truncate(tree.hashes.len());
+        let missing_bits = PartialMerkleTree { bits, ..tree.clone() };
+        assert_eq!(extract(&missing_bits), Err(Error::BitsExhausted));
+
+        let root = extract(&tree).unwrap();
+        let mut other = root.into_inner();
+        other[0] ^= 1;
+        let other = sha256d::Hash::from_inner(other);
+        assert_eq!(tree.verify(&other, &mut vec![], &mut vec![]), Err(Error::MerkleRootMismatch));
+    }
+}
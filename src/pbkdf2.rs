@@ -0,0 +1,189 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Password-Based Key Derivation Function 2 (PBKDF2).
+//!
+//! Implementation of PBKDF2 from RFC 8018 using HMAC as the pseudorandom function, generic over
+//! the underlying hash function. Bitcoin uses PBKDF2-HMAC-SHA512 with 2048 iterations to turn a
+//! BIP39 mnemonic into a seed.
+//!
+
+use crate::{Hash, HashEngine, Hmac, HmacEngine};
+
+/// Derives a key from `password` and `salt` using PBKDF2-HMAC, filling `out`.
+///
+/// The output can be of any length. The HMAC key state derived from `password` is computed once
+/// and reused for every iteration.
+///
+/// # Panics
+///
+/// If `iterations` is zero, or if `out` is longer than `(2^32 - 1) * T::LEN` bytes.
+pub fn pbkdf2<T: Hash>(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    assert!(iterations > 0, "PBKDF2 requires at least one iteration");
+    assert!(
+        (out.len() as u64 + T::LEN as u64 - 1) / T::LEN as u64 <= u32::max_value() as u64,
+        "PBKDF2 output too long",
+    );
+
+    let keyed = HmacEngine::<T>::new(password);
+
+    for (index, chunk) in out.chunks_mut(T::LEN).enumerate() {
+        let mut engine = keyed.clone();
+        engine.input(salt);
+        engine.input(&(index as u32 + 1).to_be_bytes());
+        let mut u = Hmac::from_engine(engine);
+        chunk.copy_from_slice(&u[..chunk.len()]);
+
+        for _ in 1..iterations {
+            let mut engine = keyed.clone();
+            engine.input(&u[..]);
+            u = Hmac::from_engine(engine);
+            xor_into(chunk, &u[..]);
+        }
+    }
+}
+
+/// XORs `src` into `dst`, truncating `src` to the length of `dst`.
+#[inline]
+fn xor_into(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn rfc6070() {
+        use crate::sha1;
+        use crate::hex::ToHex;
+
+        struct Test {
+            password: &'static [u8],
+            salt: &'static [u8],
+            iterations: u32,
+            output_str: &'static str,
+        }
+
+        // Test vectors from RFC 6070 (PBKDF2-HMAC-SHA1), except for the one with 16777216
+        // iterations which is too slow to run here.
+        let tests = vec![
+            Test {
+                password: b"password",
+                salt: b"salt",
+                iterations: 1,
+                output_str: "0c60c80f961f0e71f3a9b524af6012062fe037a6",
+            },
+            Test {
+                password: b"password",
+                salt: b"salt",
+                iterations: 2,
+                output_str: "ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957",
+            },
+            Test {
+                password: b"password",
+                salt: b"salt",
+                iterations: 4096,
+                output_str: "4b007901b765489abead49d926f721d065a429c1",
+            },
+            Test {
+                password: b"passwordPASSWORDpassword",
+                salt: b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                iterations: 4096,
+                output_str: "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038",
+            },
+            Test {
+                password: b"pass\0word",
+                salt: b"sa\0lt",
+                iterations: 4096,
+                output_str: "56fa6aa75548099dcc37d7f03425e0c3",
+            },
+        ];
+
+        for test in tests {
+            let mut out = vec![0u8; test.output_str.len() / 2];
+            pbkdf2::<sha1::Hash>(test.password, test.salt, test.iterations, &mut out);
+            assert_eq!(out.to_hex(), test.output_str);
+
+            // The previous contents of `out` are overwritten.
+            let mut out = vec![0xffu8; test.output_str.len() / 2];
+            pbkdf2::<sha1::Hash>(test.password, test.salt, test.iterations, &mut out);
+            assert_eq!(out.to_hex(), test.output_str);
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn bip39() {
+        use crate::sha512;
+        use crate::hex::ToHex;
+
+        // Seeds from the BIP39 test vectors (passphrase "TREZOR").
+        let tests = [
+            (
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            ),
+        ];
+
+        for &(mnemonic, seed) in tests.iter() {
+            let mut out = [0u8; 64];
+            pbkdf2::<sha512::Hash>(mnemonic.as_bytes(), b"mnemonicTREZOR", 2048, &mut out);
+            assert_eq!(out.to_hex(), seed);
+        }
+    }
+
+    #[test]
+    fn partial_blocks() {
+        use crate::sha256;
+
+        // Output of any length is a prefix of a longer output.
+        let mut long = [0u8; 100];
+        pbkdf2::<sha256::Hash>(b"password", b"salt", 3, &mut long);
+        for len in 0..long.len() {
+            let mut out = [0u8; 100];
+            pbkdf2::<sha256::Hash>(b"password", b"salt", 3, &mut out[..len]);
+            assert_eq!(&out[..len], &long[..len]);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_iterations() {
+        let mut out = [0u8; 32];
+        pbkdf2::<crate::sha256::Hash>(b"password", b"salt", 0, &mut out);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::sha512;
+
+    #[bench]
+    pub fn pbkdf2_bip39_seed(bh: &mut Bencher) {
+        let mut out = [0u8; 64];
+        bh.iter( || {
+            super::pbkdf2::<sha512::Hash>(b"abandon abandon abandon about", b"mnemonic", 2048, &mut out);
+        });
+    }
+}
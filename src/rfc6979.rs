@@ -0,0 +1,227 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Deterministic nonce generation for ECDSA and Schnorr signatures.
+//!
+//! Implementation of RFC 6979 with HMAC-SHA256 for curves with a 256-bit order, such as
+//! secp256k1 and P-256, built on [`hmac_drbg`](crate::hmac_drbg). Extra entropy is mixed in the same way as by
+//! libsecp256k1's `nonce_function_rfc6979`, so the nonces match the ones it generates.
+//!
+
+use crate::hmac_drbg::HmacDrbg;
+use crate::sha256;
+
+/// Iterator over the candidate nonces for a secret key and message hash.
+///
+/// Every item is a big-endian integer in the range `[1, order - 1]`. The first item is the
+/// nonce; a signer moves to the next one only if the signature computed with the current nonce
+/// is invalid (for example if `r` or `s` is zero). The iterator never ends.
+#[derive(Debug, Clone)]
+pub struct Nonces {
+    drbg: HmacDrbg<sha256::Hash>,
+    order: [u8; 32],
+}
+
+impl Nonces {
+    /// Creates the nonce stream for the big-endian `secret` key and `message_hash`, for a curve
+    /// with the given big-endian group `order`.
+    ///
+    /// The message hash is reduced modulo the order (the `bits2octets` step). If present,
+    /// `extra_data` is appended to the DRBG seed after the reduced message hash, as done by
+    /// libsecp256k1 for its `ndata` argument.
+    ///
+    /// # Panics
+    ///
+    /// If `order` is not a 256-bit number, i.e. if its most significant bit is not set.
+    pub fn new(
+        secret: &[u8; 32],
+        message_hash: &[u8; 32],
+        order: &[u8; 32],
+        extra_data: Option<&[u8; 32]>,
+    ) -> Nonces {
+        assert!(order[0] & 0x80 != 0, "RFC 6979 nonces require a 256-bit group order");
+
+        // The hash is less than 2^256, which is less than twice the order.
+        let mut message = *message_hash;
+        if message >= *order {
+            sub_assign(&mut message, order);
+        }
+
+        let extra_data = extra_data.map(|data| &data[..]).unwrap_or(&[]);
+        Nonces {
+            drbg: HmacDrbg::new(secret, &message, extra_data),
+            order: *order,
+        }
+    }
+}
+
+impl Iterator for Nonces {
+    type Item = [u8; 32];
+
+    fn next(&mut self) -> Option<[u8; 32]> {
+        loop {
+            let mut candidate = [0u8; 32];
+            self.drbg.generate(&mut candidate).expect("fewer than 2^48 requests");
+            if candidate != [0; 32] && candidate < self.order {
+                return Some(candidate);
+            }
+        }
+    }
+}
+
+/// Subtracts big-endian `b` from `a`, assuming `a >= b`.
+fn sub_assign(a: &mut [u8; 32], b: &[u8; 32]) {
+    let mut borrow = 0;
+    for (a, b) in a.iter_mut().zip(b.iter()).rev() {
+        let diff = i16::from(*a) - i16::from(*b) - borrow;
+        *a = diff as u8;
+        borrow = if diff < 0 { 1 } else { 0 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hash;
+    use crate::hex::FromHex;
+
+    const SECP256K1_ORDER: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    fn hex32(s: &str) -> [u8; 32] {
+        <[u8; 32]>::from_hex(s).unwrap()
+    }
+
+    #[test]
+    fn rfc6979_p256() {
+        // Test vectors from appendix A.2.5 of RFC 6979 (P-256 with SHA-256).
+        let secret = hex32("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let order = hex32("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551");
+
+        let hash = sha256::Hash::hash(b"sample").into_inner();
+        let mut nonces = Nonces::new(&secret, &hash, &order, None);
+        assert_eq!(
+            nonces.next(),
+            Some(hex32("a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60")),
+        );
+
+        let hash = sha256::Hash::hash(b"test").into_inner();
+        let mut nonces = Nonces::new(&secret, &hash, &order, None);
+        assert_eq!(
+            nonces.next(),
+            Some(hex32("d16b6ae827f17175e040871a1c7ec3500192c4c92677336ec2537acaee0008e0")),
+        );
+    }
+
+    #[test]
+    fn libsecp256k1() {
+        // Outputs of `secp256k1_nonce_function_rfc6979` for attempts 0 to 2.
+        let order = hex32(SECP256K1_ORDER);
+        let secret = sha256::Hash::hash(b"secret key").into_inner();
+        let hash = sha256::Hash::hash(b"sample").into_inner();
+
+        struct Test {
+            secret: [u8; 32],
+            message_hash: [u8; 32],
+            extra_data: Option<[u8; 32]>,
+            nonces: [&'static str; 3],
+        }
+
+        let tests = [
+            Test {
+                secret: hex32("0000000000000000000000000000000000000000000000000000000000000001"),
+                message_hash: hash,
+                extra_data: None,
+                nonces: [
+                    "0f23d7a2ba580b716ff2a03d43e26b3148eea2eb3a1fc6e7abf7cef3877b35be",
+                    "58156259476fb9ff5093bd38a8277da74e2e8ef3234b3558707bdc3feb46d99d",
+                    "690a246dcb65c9233435209a56481d30ada2119b3dd11d0ddfcd2134c54926f0",
+                ],
+            },
+            Test {
+                secret,
+                message_hash: hash,
+                extra_data: None,
+                nonces: [
+                    "c65adbe633e6080e678dd883597978a0a3c4f9db1a632c9d0f7c3435398fe0f9",
+                    "5a0e51dfb1f807e2674b216202a1a2c83ded3e294b521c26e2aed4685fb0865f",
+                    "30116078922f1a14c7a0b2615856ced151387710a7e7c677199fb89e24e28fbb",
+                ],
+            },
+            // Extra entropy, as passed in the `ndata` argument of `secp256k1_ecdsa_sign`.
+            Test {
+                secret,
+                message_hash: hash,
+                extra_data: Some([0x42; 32]),
+                nonces: [
+                    "47505745dde5b0a89244a5b261b966d0cd89ad873fa9e2d6eac51a9a38db5ddb",
+                    "12f31ac4ed202f84d0b9860dda0cc7fa2d7e0ee68bfc8ccc3c8534046eff33e7",
+                    "0a7148b8fc02b1f07abbc687e2b43163b8dc63adea4d2b6ef84f0e896dd8c8c7",
+                ],
+            },
+            // A message hash greater than the order is reduced.
+            Test {
+                secret,
+                message_hash: [0xff; 32],
+                extra_data: None,
+                nonces: [
+                    "a0f7df5c3916206c343e591007a3b7eaa1564f1c1f0946caed32f54a3f1c1256",
+                    "06c7125c23c6ef81e832832c857895b3ac804337dc3c9a45de1fabfc60bdcc64",
+                    "e7a6b7a84bdc48c3e7acb12be46f5f25527c70aa0dd491ef9bc5a8c5e076a9fd",
+                ],
+            },
+        ];
+
+        for test in tests.iter() {
+            let mut nonces = Nonces::new(&test.secret, &test.message_hash, &order, test.extra_data.as_ref());
+            for expected in test.nonces.iter() {
+                assert_eq!(nonces.next(), Some(hex32(expected)));
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_candidates() {
+        // With an order of 2^255 about half of the candidates are rejected.
+        let mut order = [0u8; 32];
+        order[0] = 0x80;
+        let secret = sha256::Hash::hash(b"secret key").into_inner();
+        let hash = sha256::Hash::hash(b"sample").into_inner();
+
+        let mut nonces = Nonces::new(&secret, &hash, &order, None);
+        for expected in [
+            "7a2b3dc22c606ee90abbe02d11a34b1fdabf1580e43260c7099fe45bfc87a1de",
+            "2d36915f085a031c2ff7c7b8efd8e0fb41d990b8330bcad86a847f4d995c9e0c",
+            "63eb3a404608470a50c42bcc163b07bfede8d5697c322219d3ddb989848df249",
+            "471835cd19d7e64192eb836e3fabcefe18d7e06ba0e7727f5911fd047288b2a7",
+        ].iter() {
+            assert_eq!(nonces.next(), Some(hex32(expected)));
+        }
+    }
+
+    #[test]
+    fn reduce_message() {
+        let order = hex32(SECP256K1_ORDER);
+        let mut hash = [0xff; 32];
+        sub_assign(&mut hash, &order);
+        assert_eq!(hash, hex32("000000000000000000000000000000014551231950b75fc4402da1732fc9bebe"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_order() {
+        let mut order = hex32(SECP256K1_ORDER);
+        order[0] = 0x7f;
+        Nonces::new(&[1; 32], &[2; 32], &order, None);
+    }
+}
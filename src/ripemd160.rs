@@ -71,6 +71,8 @@ pub struct HashEngine {
     length: usize,
 }
 
+crate::internal_macros::engine_zeroize_impl!(HashEngine, buffer, h, length);
+
 impl Default for HashEngine {
     fn default() -> Self {
         HashEngine {
@@ -0,0 +1,379 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! scrypt key derivation function.
+//!
+//! Implementation of scrypt as specified in RFC 7914, built on PBKDF2-HMAC-SHA256 from the
+//! [`pbkdf2`] module. scrypt is used by BIP38 to encrypt private keys.
+//!
+//! The memory scrypt needs is supplied by the caller as a scratch buffer of at least
+//! [`Params::scratch_len`] bytes, so no allocation is required.
+//!
+
+use core::fmt;
+
+use crate::{pbkdf2, sha256};
+
+/// Size of a Salsa20/8 block, in bytes.
+const SALSA_BLOCK_SIZE: usize = 64;
+
+/// scrypt error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The CPU/memory cost `N` is not a power of two greater than one, or not less than `2^(16 * r)`.
+    InvalidCost(u64),
+    /// The block size `r` is zero.
+    InvalidBlockSize(u32),
+    /// The parallelization `p` is zero, or `p * r` is not less than `2^30`.
+    InvalidParallelization(u32),
+    /// The memory required by the parameters does not fit in `usize`.
+    MemoryTooLarge,
+    /// The scratch buffer is too small for the parameters (required, got).
+    ScratchTooSmall(usize, usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidCost(n) => write!(f, "invalid scrypt cost parameter N {}", n),
+            Error::InvalidBlockSize(r) => write!(f, "invalid scrypt block size parameter r {}", r),
+            Error::InvalidParallelization(p) => write!(f, "invalid scrypt parallelization parameter p {}", p),
+            Error::MemoryTooLarge => f.write_str("scrypt parameters require more memory than addressable"),
+            Error::ScratchTooSmall(req, got) => write!(f, "scrypt scratch buffer too small: {} bytes (expected {})", got, req),
+        }
+    }
+}
+
+/// Validated scrypt parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Params {
+    n: u64,
+    r: u32,
+    p: u32,
+    block_len: usize,
+    scratch_len: usize,
+}
+
+impl Params {
+    /// Validates the CPU/memory cost `n`, block size `r` and parallelization `p`.
+    ///
+    /// BIP38 uses `n = 16384`, `r = 8` and `p = 8`.
+    pub fn new(n: u64, r: u32, p: u32) -> Result<Params, Error> {
+        if r == 0 {
+            return Err(Error::InvalidBlockSize(r));
+        }
+        if n < 2 || !n.is_power_of_two() || (r < 4 && n.trailing_zeros() >= 16 * r) {
+            return Err(Error::InvalidCost(n));
+        }
+        if p == 0 || u64::from(p) * u64::from(r) >= 1 << 30 {
+            return Err(Error::InvalidParallelization(p));
+        }
+
+        // Layout of the scratch buffer: B (p blocks), V (N blocks), then X and Y (one block each).
+        let block_len = (r as usize).checked_mul(128).ok_or(Error::MemoryTooLarge)?;
+        let scratch_len = (n as usize).checked_add(p as usize)
+            .and_then(|blocks| blocks.checked_add(2))
+            .and_then(|blocks| blocks.checked_mul(block_len))
+            .filter(|_| n <= usize::max_value() as u64)
+            .ok_or(Error::MemoryTooLarge)?;
+
+        Ok(Params { n, r, p, block_len, scratch_len })
+    }
+
+    /// Returns the CPU/memory cost parameter `N`.
+    pub fn n(&self) -> u64 {
+        self.n
+    }
+
+    /// Returns the block size parameter `r`.
+    pub fn r(&self) -> u32 {
+        self.r
+    }
+
+    /// Returns the parallelization parameter `p`.
+    pub fn p(&self) -> u32 {
+        self.p
+    }
+
+    /// Returns the minimum length of the scratch buffer passed to [`scrypt`], in bytes.
+    pub fn scratch_len(&self) -> usize {
+        self.scratch_len
+    }
+}
+
+/// Derives a key from `password` and `salt` using scrypt, filling `out`.
+///
+/// `scratch` must be at least [`Params::scratch_len`] bytes long, its contents are overwritten.
+///
+/// # Panics
+///
+/// If `out` is longer than `(2^32 - 1) * 32` bytes.
+pub fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    params: &Params,
+    scratch: &mut [u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    if scratch.len() < params.scratch_len {
+        return Err(Error::ScratchTooSmall(params.scratch_len, scratch.len()));
+    }
+
+    let block_len = params.block_len;
+    let (b, rest) = scratch.split_at_mut(block_len * params.p as usize);
+    let (v, xy) = rest.split_at_mut(block_len * params.n as usize);
+    let xy = &mut xy[..2 * block_len];
+
+    pbkdf2::pbkdf2::<sha256::Hash>(password, salt, 1, b);
+    for block in b.chunks_mut(block_len) {
+        ro_mix(block, v, xy, params.n);
+    }
+    pbkdf2::pbkdf2::<sha256::Hash>(password, b, 1, out);
+
+    Ok(())
+}
+
+/// The scryptROMix function, mixing `block` in place using `v` and `xy` as scratch space.
+fn ro_mix(block: &mut [u8], v: &mut [u8], xy: &mut [u8], n: u64) {
+    let block_len = block.len();
+    let (x, y) = xy.split_at_mut(block_len);
+
+    x.copy_from_slice(block);
+    for v_i in v.chunks_mut(block_len) {
+        v_i.copy_from_slice(x);
+        block_mix(x, y);
+        x.copy_from_slice(y);
+    }
+    for _ in 0..n {
+        let j = (integerify(x) & (n - 1)) as usize;
+        for (x, v) in x.iter_mut().zip(&v[j * block_len..(j + 1) * block_len]) {
+            *x ^= v;
+        }
+        block_mix(x, y);
+        x.copy_from_slice(y);
+    }
+    block.copy_from_slice(x);
+}
+
+/// The scryptBlockMix function, writing the mix of `input` to `output`.
+fn block_mix(input: &[u8], output: &mut [u8]) {
+    let half = input.len() / 2;
+    let mut x = load_block(&input[input.len() - SALSA_BLOCK_SIZE..]);
+
+    for (i, chunk) in input.chunks(SALSA_BLOCK_SIZE).enumerate() {
+        for (x, b) in x.iter_mut().zip(&load_block(chunk)) {
+            *x ^= b;
+        }
+        salsa20_8(&mut x);
+
+        // Even blocks go to the first half of the output, odd blocks to the second.
+        let pos = (i / 2) * SALSA_BLOCK_SIZE + (i % 2) * half;
+        store_block(&x, &mut output[pos..pos + SALSA_BLOCK_SIZE]);
+    }
+}
+
+/// Interprets the first 8 bytes of the last Salsa20/8 block as a little-endian integer.
+fn integerify(block: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&block[block.len() - SALSA_BLOCK_SIZE..][..8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn load_block(bytes: &[u8]) -> [u32; 16] {
+    let mut block = [0; 16];
+    for (word, chunk) in block.iter_mut().zip(bytes.chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    block
+}
+
+fn store_block(block: &[u32; 16], bytes: &mut [u8]) {
+    for (word, chunk) in block.iter().zip(bytes.chunks_mut(4)) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// The Salsa20/8 core function.
+fn salsa20_8(b: &mut [u32; 16]) {
+    macro_rules! quarter_round {
+        ($x:ident, $a:expr, $b:expr, $c:expr, $d:expr) => {
+            $x[$b] ^= $x[$a].wrapping_add($x[$d]).rotate_left(7);
+            $x[$c] ^= $x[$b].wrapping_add($x[$a]).rotate_left(9);
+            $x[$d] ^= $x[$c].wrapping_add($x[$b]).rotate_left(13);
+            $x[$a] ^= $x[$d].wrapping_add($x[$c]).rotate_left(18);
+        };
+    }
+
+    let mut x = *b;
+    for _ in 0..4 {
+        // Column round.
+        quarter_round!(x, 0, 4, 8, 12);
+        quarter_round!(x, 5, 9, 13, 1);
+        quarter_round!(x, 10, 14, 2, 6);
+        quarter_round!(x, 15, 3, 7, 11);
+        // Row round.
+        quarter_round!(x, 0, 1, 2, 3);
+        quarter_round!(x, 5, 6, 7, 4);
+        quarter_round!(x, 10, 11, 8, 9);
+        quarter_round!(x, 15, 12, 13, 14);
+    }
+    for (b, x) in b.iter_mut().zip(&x) {
+        *b = b.wrapping_add(*x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn rfc7914() {
+        use crate::hex::ToHex;
+
+        struct Test {
+            password: &'static [u8],
+            salt: &'static [u8],
+            n: u64,
+            r: u32,
+            p: u32,
+            output_str: &'static str,
+        }
+
+        // Test vectors from section 12 of RFC 7914, except for the last one which needs 1 GiB.
+        let tests = vec![
+            Test {
+                password: b"",
+                salt: b"",
+                n: 16,
+                r: 1,
+                p: 1,
+                output_str: "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+                             fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906",
+            },
+            Test {
+                password: b"password",
+                salt: b"NaCl",
+                n: 1024,
+                r: 8,
+                p: 16,
+                output_str: "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+                             2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640",
+            },
+            Test {
+                password: b"pleaseletmein",
+                salt: b"SodiumChloride",
+                n: 16384,
+                r: 8,
+                p: 1,
+                output_str: "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2\
+                             d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887",
+            },
+        ];
+
+        for test in tests {
+            let params = Params::new(test.n, test.r, test.p).unwrap();
+            let mut scratch = vec![0u8; params.scratch_len()];
+            let mut out = [0u8; 64];
+            scrypt(test.password, test.salt, &params, &mut scratch, &mut out).unwrap();
+            assert_eq!(out.to_hex(), test.output_str);
+
+            // Reusing the scratch and output buffers gives the same result.
+            scrypt(test.password, test.salt, &params, &mut scratch, &mut out).unwrap();
+            assert_eq!(out.to_hex(), test.output_str);
+        }
+    }
+
+    #[test]
+    fn salsa20_8_core() {
+        use crate::hex::FromHex;
+
+        // Test vector from section 8 of RFC 7914.
+        let input = <[u8; 64]>::from_hex(
+            "7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d\
+             ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e"
+        ).unwrap();
+        let output = <[u8; 64]>::from_hex(
+            "a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29\
+             b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81"
+        ).unwrap();
+
+        let mut block = load_block(&input);
+        salsa20_8(&mut block);
+        let mut result = [0u8; 64];
+        store_block(&block, &mut result);
+        assert_eq!(&result[..], &output[..]);
+    }
+
+    #[test]
+    fn params() {
+        assert_eq!(Params::new(0, 1, 1), Err(Error::InvalidCost(0)));
+        assert_eq!(Params::new(1, 1, 1), Err(Error::InvalidCost(1)));
+        assert_eq!(Params::new(1000, 8, 1), Err(Error::InvalidCost(1000)));
+        assert_eq!(Params::new(1 << 16, 1, 1), Err(Error::InvalidCost(1 << 16)));
+        assert!(Params::new(1 << 15, 1, 1).is_ok());
+        assert_eq!(Params::new(16, 0, 1), Err(Error::InvalidBlockSize(0)));
+        assert_eq!(Params::new(16, 1, 0), Err(Error::InvalidParallelization(0)));
+        assert_eq!(Params::new(16, 1 << 15, 1 << 15), Err(Error::InvalidParallelization(1 << 15)));
+        assert_eq!(Params::new(1 << 62, 8, 1), Err(Error::MemoryTooLarge));
+        // The block length alone, 2^32 bytes, overflows a 32-bit `usize`.
+        let params = Params::new(16, 1 << 25, 1);
+        if cfg!(target_pointer_width = "64") {
+            assert_eq!(params.unwrap().scratch_len() as u64, 19 * (1 << 32));
+        } else {
+            assert_eq!(params, Err(Error::MemoryTooLarge));
+        }
+
+        let params = Params::new(16384, 8, 8).unwrap();
+        assert_eq!((params.n(), params.r(), params.p()), (16384, 8, 8));
+        assert_eq!(params.scratch_len(), 1024 * (16384 + 8 + 2));
+    }
+
+    #[test]
+    fn scratch_too_small() {
+        let params = Params::new(16, 1, 1).unwrap();
+        let mut scratch = [0u8; 128 * 19];
+        let mut out = [0u8; 32];
+        assert_eq!(
+            scrypt(b"", b"", &params, &mut scratch[..128 * 18], &mut out),
+            Err(Error::ScratchTooSmall(128 * 19, 128 * 18)),
+        );
+        assert!(scrypt(b"", b"", &params, &mut scratch, &mut out).is_ok());
+        assert_eq!(
+            &out[..],
+            &[
+                0x77, 0xd6, 0x57, 0x62, 0x38, 0x65, 0x7b, 0x20, 0x3b, 0x19, 0xca, 0x42, 0xc1, 0x8a, 0x04, 0x97,
+                0xf1, 0x6b, 0x48, 0x44, 0xe3, 0x07, 0x4a, 0xe8, 0xdf, 0xdf, 0xfa, 0x3f, 0xed, 0xe2, 0x14, 0x42,
+            ][..],
+        );
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use super::{scrypt, Params};
+
+    #[bench]
+    pub fn scrypt_n1024_r8_p1(bh: &mut Bencher) {
+        let params = Params::new(1024, 8, 1).unwrap();
+        let mut scratch = vec![0u8; params.scratch_len()];
+        let mut out = [0u8; 64];
+        bh.iter( || {
+            scrypt(b"password", b"salt", &params, &mut scratch, &mut out).unwrap();
+        });
+    }
+}
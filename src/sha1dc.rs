@@ -0,0 +1,748 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! SHA1 implementation with collision detection.
+//!
+//! This is the counter-cryptanalysis of Marc Stevens and Dan Shumow ("sha1dc"), as used by Git
+//! and GitHub to reject SHAttered-style inputs. While hashing, every block is checked for being
+//! one half of a near-collision block pair built from one of the 32 disturbance vectors (DVs)
+//! used by known attacks. The unavoidable bit conditions (UBCs) of each DV are tested first, so
+//! that the expensive recompression check only runs for DVs an attacker could have used.
+//!
+//! For inputs without a collision attack the output is identical to [`sha1`](crate::sha1). When
+//! the engine is created with [`HashEngine::with_safe_hash`] and a collision is detected, the
+//! output is instead a "safe hash" that differs from the one of the colliding input, like Git's
+//! `SHA1DCSetSafeHash`.
+//!
+
+use core::{cmp, str};
+use core::convert::TryInto;
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, HashEngine as _, hex};
+
+crate::internal_macros::hash_type! {
+    160,
+    false,
+    "Output of the SHA1 hash function, computed with collision detection.",
+    "crate::util::json_hex_string::len_20"
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    Hash::from_engine_checked(e).0
+}
+
+const BLOCK_SIZE: usize = 64;
+
+/// Engine to compute SHA1 hash function with collision detection.
+#[derive(Clone)]
+pub struct HashEngine {
+    buffer: [u8; BLOCK_SIZE],
+    h: [u32; 5],
+    length: usize,
+    safe_hash: bool,
+    collision: bool,
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, buffer, h, length, safe_hash, collision);
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine {
+            h: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+            safe_hash: false,
+            collision: false,
+        }
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; 20];
+
+    fn midstate(&self) -> [u8; 20] {
+        let mut ret = [0; 20];
+        for (val, ret_bytes) in self.h.iter().zip(ret.chunks_exact_mut(4)) {
+            ret_bytes.copy_from_slice(&val.to_be_bytes())
+        }
+        ret
+    }
+
+    const BLOCK_SIZE: usize = 64;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length
+    }
+
+    engine_input_impl!();
+}
+
+impl HashEngine {
+    /// Creates a new engine. If `safe_hash` is true, the hash of an input for which a collision
+    /// is detected is replaced by a safe hash.
+    pub fn with_safe_hash(safe_hash: bool) -> HashEngine {
+        HashEngine {
+            safe_hash,
+            ..Default::default()
+        }
+    }
+
+    /// Returns whether a collision attack has been detected in the blocks processed so far.
+    pub fn collision_detected(&self) -> bool {
+        self.collision
+    }
+
+    fn process_block(&mut self) {
+        debug_assert_eq!(self.buffer.len(), BLOCK_SIZE);
+
+        let mut w = [0u32; 80];
+        for (w_val, buff_bytes) in w.iter_mut().zip(self.buffer.chunks_exact(4)) {
+            *w_val = u32::from_be_bytes(buff_bytes.try_into().expect("4 bytes slice"))
+        }
+        expand(&mut w);
+
+        let mut states = [[0; 5]; 2];
+        compress(&mut self.h, &w, Some(&mut states));
+
+        if detect_collision(&self.h, &w, &states) {
+            self.collision = true;
+            if self.safe_hash {
+                compress(&mut self.h, &w, None);
+                compress(&mut self.h, &w, None);
+            }
+        }
+    }
+}
+
+impl Hash {
+    /// Hashes the given data, returning the hash and whether a collision attack was detected.
+    pub fn hash_checked(data: &[u8]) -> (Hash, bool) {
+        let mut engine = HashEngine::default();
+        engine.input(data);
+        Hash::from_engine_checked(engine)
+    }
+
+    /// Produces a hash from the current state of a given engine, along with whether a collision
+    /// attack was detected in any of the blocks of the input.
+    pub fn from_engine_checked(mut e: HashEngine) -> (Hash, bool) {
+        // pad buffer with a single 1-bit then all 0s, until there are exactly 8 bytes remaining
+        let data_len = e.length as u64;
+
+        let zeroes = [0; BLOCK_SIZE - 8];
+        e.input(&[0x80]);
+        if e.length % BLOCK_SIZE > zeroes.len() {
+            e.input(&zeroes);
+        }
+        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE);
+        e.input(&zeroes[..pad_length]);
+        debug_assert_eq!(e.length % BLOCK_SIZE, zeroes.len());
+
+        e.input(&(8 * data_len).to_be_bytes());
+        debug_assert_eq!(e.length % BLOCK_SIZE, 0);
+
+        (Hash(e.midstate()), e.collision)
+    }
+}
+
+/// Steps at which the working state is saved for the recompression checks.
+const SAVED_STEPS: [usize; 2] = [58, 65];
+
+fn expand(w: &mut [u32; 80]) {
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+}
+
+#[inline]
+fn round_fn(i: usize, b: u32, c: u32, d: u32) -> u32 {
+    match i {
+        0..=19 => ((b & c) | (!b & d)).wrapping_add(0x5a827999),
+        20..=39 => (b ^ c ^ d).wrapping_add(0x6ed9eba1),
+        40..=59 => ((b & c) | (b & d) | (c & d)).wrapping_add(0x8f1bbcdc),
+        60..=79 => (b ^ c ^ d).wrapping_add(0xca62c1d6),
+        _ => unreachable!(),
+    }
+}
+
+/// Runs step `i` forward on the working state `[a, b, c, d, e]`.
+#[inline]
+fn step_forward(s: &mut [u32; 5], i: usize, wi: u32) {
+    let new_a = s[0].rotate_left(5)
+        .wrapping_add(round_fn(i, s[1], s[2], s[3]))
+        .wrapping_add(s[4])
+        .wrapping_add(wi);
+    *s = [new_a, s[0], s[1].rotate_left(30), s[2], s[3]];
+}
+
+/// Undoes step `i` on the working state `[a, b, c, d, e]`.
+#[inline]
+fn step_backward(s: &mut [u32; 5], i: usize, wi: u32) {
+    let (a, b, c, d) = (s[1], s[2].rotate_right(30), s[3], s[4]);
+    let e = s[0]
+        .wrapping_sub(a.rotate_left(5))
+        .wrapping_sub(round_fn(i, b, c, d))
+        .wrapping_sub(wi);
+    *s = [a, b, c, d, e];
+}
+
+/// Compresses the expanded message block `w` into `h`, saving the working state before each of
+/// the [`SAVED_STEPS`] into `states`.
+fn compress(h: &mut [u32; 5], w: &[u32; 80], mut states: Option<&mut [[u32; 5]; 2]>) {
+    let mut s = *h;
+    for (i, &wi) in w.iter().enumerate() {
+        if let Some(ref mut states) = states {
+            if let Some(n) = SAVED_STEPS.iter().position(|&step| step == i) {
+                states[n] = s;
+            }
+        }
+        step_forward(&mut s, i, wi);
+    }
+    for (hi, si) in h.iter_mut().zip(s.iter()) {
+        *hi = hi.wrapping_add(*si);
+    }
+}
+
+/// Computes the chaining value after a block with expanded message `w`, given the working state
+/// `state` before step `step` of its compression. The chaining value before the block is
+/// recovered by running the steps before `step` backwards.
+fn recompress(step: usize, state: &[u32; 5], w: &[u32; 80]) -> [u32; 5] {
+    let mut ihv_in = *state;
+    for (i, &wi) in w[..step].iter().enumerate().rev() {
+        step_backward(&mut ihv_in, i, wi);
+    }
+    let mut ihv_out = *state;
+    for (i, &wi) in w.iter().enumerate().skip(step) {
+        step_forward(&mut ihv_out, i, wi);
+    }
+    for (out, inp) in ihv_out.iter_mut().zip(ihv_in.iter()) {
+        *out = out.wrapping_add(*inp);
+    }
+    ihv_out
+}
+
+/// Returns whether the block with expanded message `w`, which compressed to `ihv`, forms a
+/// collision with a block differing by the message difference of one of the DVs.
+fn detect_collision(ihv: &[u32; 5], w: &[u32; 80], states: &[[u32; 5]; 2]) -> bool {
+    let mask = ubc_mask(w);
+    if mask == 0 {
+        return false;
+    }
+    for (i, dv) in DISTURBANCE_VECTORS.iter().enumerate() {
+        if mask & (1 << i) == 0 {
+            continue;
+        }
+        let dm = dv.message_difference();
+        let mut w2 = [0; 80];
+        for j in 0..80 {
+            w2[j] = w[j] ^ dm[j];
+        }
+        let state = &states[SAVED_STEPS.iter().position(|&step| step == dv.step).expect("saved step")];
+        let ihv2 = recompress(dv.step, state, &w2);
+        if ihv2 == *ihv {
+            return true;
+        }
+    }
+    false
+}
+
+/// The two families of disturbance vectors, see Stevens' "Counter-cryptanalysis".
+#[derive(Copy, Clone)]
+enum DvKind {
+    I,
+    II,
+}
+
+/// The disturbance vector `I(k, b)` or `II(k, b)`, checked by recompressing from step `step`.
+struct DisturbanceVector {
+    kind: DvKind,
+    k: usize,
+    b: u32,
+    step: usize,
+}
+
+impl DisturbanceVector {
+    /// Returns the message XOR-difference of the local collisions following the DV.
+    fn message_difference(&self) -> [u32; 80] {
+        // `dv[i + 5]` is word `i` of the DV, for `i` from -5 to 79. The DV satisfies the message
+        // expansion and is defined by its words `k..k + 16`.
+        let mut dv = [0u32; 85];
+        let k = self.k + 5;
+        match self.kind {
+            DvKind::I => {
+                dv[k + 15] = 1 << self.b;
+            }
+            DvKind::II => {
+                dv[k + 1] = 1u32.rotate_left(31 + self.b);
+                dv[k + 3] = 1u32.rotate_left(31 + self.b);
+                dv[k + 15] = 1 << self.b;
+            }
+        }
+        for i in k + 16..85 {
+            dv[i] = (dv[i - 3] ^ dv[i - 8] ^ dv[i - 14] ^ dv[i - 16]).rotate_left(1);
+        }
+        for i in (0..k).rev() {
+            dv[i] = dv[i + 16].rotate_right(1) ^ dv[i + 13] ^ dv[i + 8] ^ dv[i + 2];
+        }
+
+        let mut dm = [0; 80];
+        for (t, dmt) in dm.iter_mut().enumerate() {
+            let i = t + 5;
+            *dmt = dv[i] ^ dv[i - 1].rotate_left(5) ^ dv[i - 2]
+                ^ (dv[i - 3] ^ dv[i - 4] ^ dv[i - 5]).rotate_left(30);
+        }
+        dm
+    }
+}
+
+// Bit of each DV in the mask computed by `ubc_mask`, which is its index in `DISTURBANCE_VECTORS`.
+const I_43_0: u32 = 1 << 0;
+const I_44_0: u32 = 1 << 1;
+const I_45_0: u32 = 1 << 2;
+const I_46_0: u32 = 1 << 3;
+const I_46_2: u32 = 1 << 4;
+const I_47_0: u32 = 1 << 5;
+const I_47_2: u32 = 1 << 6;
+const I_48_0: u32 = 1 << 7;
+const I_48_2: u32 = 1 << 8;
+const I_49_0: u32 = 1 << 9;
+const I_49_2: u32 = 1 << 10;
+const I_50_0: u32 = 1 << 11;
+const I_50_2: u32 = 1 << 12;
+const I_51_0: u32 = 1 << 13;
+const I_51_2: u32 = 1 << 14;
+const I_52_0: u32 = 1 << 15;
+const II_45_0: u32 = 1 << 16;
+const II_46_0: u32 = 1 << 17;
+const II_46_2: u32 = 1 << 18;
+const II_47_0: u32 = 1 << 19;
+const II_48_0: u32 = 1 << 20;
+const II_49_0: u32 = 1 << 21;
+const II_49_2: u32 = 1 << 22;
+const II_50_0: u32 = 1 << 23;
+const II_50_2: u32 = 1 << 24;
+const II_51_0: u32 = 1 << 25;
+const II_51_2: u32 = 1 << 26;
+const II_52_0: u32 = 1 << 27;
+const II_53_0: u32 = 1 << 28;
+const II_54_0: u32 = 1 << 29;
+const II_55_0: u32 = 1 << 30;
+const II_56_0: u32 = 1 << 31;
+
+const DISTURBANCE_VECTORS: [DisturbanceVector; 32] = [
+    DisturbanceVector { kind: DvKind::I, k: 43, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 44, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 45, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 46, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 46, b: 2, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 47, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 47, b: 2, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 48, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 48, b: 2, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 49, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 49, b: 2, step: 58 },
+    DisturbanceVector { kind: DvKind::I, k: 50, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::I, k: 50, b: 2, step: 65 },
+    DisturbanceVector { kind: DvKind::I, k: 51, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::I, k: 51, b: 2, step: 65 },
+    DisturbanceVector { kind: DvKind::I, k: 52, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 45, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::II, k: 46, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::II, k: 46, b: 2, step: 58 },
+    DisturbanceVector { kind: DvKind::II, k: 47, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::II, k: 48, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::II, k: 49, b: 0, step: 58 },
+    DisturbanceVector { kind: DvKind::II, k: 49, b: 2, step: 58 },
+    DisturbanceVector { kind: DvKind::II, k: 50, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 50, b: 2, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 51, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 51, b: 2, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 52, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 53, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 54, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 55, b: 0, step: 65 },
+    DisturbanceVector { kind: DvKind::II, k: 56, b: 0, step: 65 },
+];
+
+/// The unavoidable bit conditions, as `(w0, b0, w1, b1, value, dvs)`: unless bit `b0` of `W[w0]`
+/// xor bit `b1` of `W[w1]` equals `value`, none of the DVs in `dvs` can lead to a collision.
+const UNAVOIDABLE_BIT_CONDITIONS: [(usize, u32, usize, u32, u32, u32); 156] = [
+    (44, 29, 45, 29, 0, I_48_0 | I_51_0 | I_52_0 | II_45_0 | II_46_0 | II_50_0 | II_51_0),
+    (49, 29, 50, 29, 0, I_46_0 | II_45_0 | II_50_0 | II_51_0 | II_55_0 | II_56_0),
+    (48, 29, 49, 29, 0, I_45_0 | I_52_0 | II_49_0 | II_50_0 | II_54_0 | II_55_0),
+    (47, 4, 50, 29, 0, I_47_0 | I_49_0 | I_51_0 | II_45_0 | II_51_0 | II_56_0),
+    (47, 29, 48, 29, 0, I_44_0 | I_51_0 | II_48_0 | II_49_0 | II_53_0 | II_54_0),
+    (46, 4, 49, 29, 0, I_46_0 | I_48_0 | I_50_0 | I_52_0 | II_50_0 | II_55_0),
+    (46, 29, 47, 29, 0, I_43_0 | I_50_0 | II_47_0 | II_48_0 | II_52_0 | II_53_0),
+    (45, 4, 48, 29, 0, I_45_0 | I_47_0 | I_49_0 | I_51_0 | II_49_0 | II_54_0),
+    (45, 29, 46, 29, 0, I_49_0 | I_52_0 | II_46_0 | II_47_0 | II_51_0 | II_52_0),
+    (44, 4, 47, 29, 0, I_44_0 | I_46_0 | I_48_0 | I_50_0 | II_48_0 | II_53_0),
+    (43, 4, 46, 29, 0, I_43_0 | I_45_0 | I_47_0 | I_49_0 | II_47_0 | II_52_0),
+    (43, 29, 44, 29, 0, I_47_0 | I_50_0 | I_51_0 | II_45_0 | II_49_0 | II_50_0),
+    (42, 4, 45, 29, 0, I_44_0 | I_46_0 | I_48_0 | I_52_0 | II_46_0 | II_51_0),
+    (41, 4, 44, 29, 0, I_43_0 | I_45_0 | I_47_0 | I_51_0 | II_45_0 | II_50_0),
+    (40, 29, 41, 29, 0, I_44_0 | I_47_0 | I_48_0 | II_46_0 | II_47_0 | II_56_0),
+    (54, 29, 55, 29, 0, I_51_0 | II_47_0 | II_50_0 | II_55_0 | II_56_0),
+    (53, 29, 54, 29, 0, I_50_0 | II_46_0 | II_49_0 | II_54_0 | II_55_0),
+    (52, 29, 53, 29, 0, I_49_0 | II_45_0 | II_48_0 | II_53_0 | II_54_0),
+    (50, 4, 53, 29, 0, I_50_0 | I_52_0 | II_46_0 | II_48_0 | II_54_0),
+    (50, 29, 51, 29, 0, I_47_0 | II_46_0 | II_51_0 | II_52_0 | II_56_0),
+    (49, 4, 52, 29, 0, I_49_0 | I_51_0 | II_45_0 | II_47_0 | II_53_0),
+    (48, 4, 51, 29, 0, I_48_0 | I_50_0 | I_52_0 | II_46_0 | II_52_0),
+    (42, 29, 43, 29, 0, I_46_0 | I_49_0 | I_50_0 | II_48_0 | II_49_0),
+    (41, 29, 42, 29, 0, I_45_0 | I_48_0 | I_49_0 | II_47_0 | II_48_0),
+    (40, 4, 43, 29, 0, I_44_0 | I_46_0 | I_50_0 | II_49_0 | II_56_0),
+    (39, 4, 42, 29, 0, I_43_0 | I_45_0 | I_49_0 | II_48_0 | II_55_0),
+    (38, 4, 41, 29, 0, I_44_0 | I_48_0 | II_47_0 | II_54_0 | II_56_0),
+    (37, 4, 40, 29, 0, I_43_0 | I_47_0 | II_46_0 | II_53_0 | II_55_0),
+    (55, 29, 56, 29, 0, I_52_0 | II_48_0 | II_51_0 | II_56_0),
+    (52, 4, 55, 29, 0, I_52_0 | II_48_0 | II_50_0 | II_56_0),
+    (51, 4, 54, 29, 0, I_51_0 | II_47_0 | II_49_0 | II_55_0),
+    (51, 29, 52, 29, 0, I_48_0 | II_47_0 | II_52_0 | II_53_0),
+    (36, 4, 40, 29, 0, I_46_0 | I_49_0 | II_45_0 | II_48_0),
+    (53, 29, 56, 29, 1, I_52_0 | II_48_0 | II_49_0),
+    (51, 29, 54, 29, 1, I_50_0 | II_46_0 | II_47_0),
+    (50, 29, 52, 29, 1, I_49_0 | I_51_0 | II_45_0),
+    (49, 29, 51, 29, 1, I_48_0 | I_50_0 | I_52_0),
+    (48, 29, 50, 29, 1, I_47_0 | I_49_0 | I_51_0),
+    (47, 29, 49, 29, 1, I_46_0 | I_48_0 | I_50_0),
+    (46, 29, 48, 29, 1, I_45_0 | I_47_0 | I_49_0),
+    (45, 6, 47, 6, 0, I_47_2 | I_49_2 | I_51_2),
+    (45, 29, 47, 29, 1, I_44_0 | I_46_0 | I_48_0),
+    (44, 6, 46, 6, 0, I_46_2 | I_48_2 | I_50_2),
+    (44, 29, 46, 29, 1, I_43_0 | I_45_0 | I_47_0),
+    (41, 1, 42, 6, 1, I_48_2 | II_46_2 | II_51_2),
+    (40, 1, 41, 6, 1, I_47_2 | I_51_2 | II_50_2),
+    (40, 4, 42, 4, 1, I_44_0 | I_46_0 | II_56_0),
+    (39, 1, 40, 6, 1, I_46_2 | I_50_2 | II_49_2),
+    (39, 4, 41, 4, 1, I_43_0 | I_45_0 | II_55_0),
+    (38, 4, 40, 4, 1, I_44_0 | II_54_0 | II_56_0),
+    (37, 4, 39, 4, 1, I_43_0 | II_53_0 | II_55_0),
+    (36, 1, 37, 6, 1, I_47_2 | I_50_2 | II_46_2),
+    (35, 4, 39, 29, 0, I_45_0 | I_48_0 | II_47_0),
+    (63, 0, 64, 5, 1, I_48_0 | II_48_0),
+    (63, 1, 64, 6, 1, I_45_0 | II_45_0),
+    (62, 0, 63, 5, 1, I_47_0 | II_47_0),
+    (61, 0, 62, 5, 1, I_46_0 | II_46_0),
+    (61, 2, 62, 7, 1, I_46_2 | II_46_2),
+    (60, 0, 61, 5, 1, I_45_0 | II_45_0),
+    (58, 29, 59, 29, 0, II_51_0 | II_54_0),
+    (57, 29, 58, 29, 0, II_50_0 | II_53_0),
+    (56, 4, 59, 29, 0, II_52_0 | II_54_0),
+    (56, 29, 59, 29, 1, II_51_0 | II_52_0),
+    (56, 29, 57, 29, 0, II_49_0 | II_52_0),
+    (55, 4, 58, 29, 0, II_51_0 | II_53_0),
+    (54, 4, 57, 29, 0, II_50_0 | II_52_0),
+    (53, 4, 56, 29, 0, II_49_0 | II_51_0),
+    (50, 6, 51, 1, 0, I_50_2 | II_46_2),
+    (48, 6, 50, 6, 0, I_50_2 | II_46_2),
+    (48, 29, 55, 29, 1, I_51_0 | I_52_0),
+    (47, 6, 49, 6, 0, I_49_2 | I_51_2),
+    (47, 6, 48, 1, 0, I_47_2 | II_51_2),
+    (46, 6, 48, 6, 0, I_48_2 | I_50_2),
+    (46, 6, 47, 1, 0, I_46_2 | II_50_2),
+    (44, 1, 45, 6, 1, I_51_2 | II_49_2),
+    (43, 6, 45, 6, 0, I_47_2 | I_49_2),
+    (42, 6, 44, 6, 0, I_46_2 | I_48_2),
+    (42, 6, 43, 1, 0, II_46_2 | II_51_2),
+    (41, 6, 42, 1, 0, I_51_2 | II_50_2),
+    (40, 6, 41, 1, 0, I_50_2 | II_49_2),
+    (39, 4, 43, 29, 0, I_52_0 | II_51_0),
+    (38, 4, 42, 29, 0, I_51_0 | II_50_0),
+    (37, 1, 38, 6, 1, I_48_2 | I_51_2),
+    (37, 4, 41, 29, 0, I_50_0 | II_49_0),
+    (36, 4, 38, 4, 1, II_52_0 | II_54_0),
+    (35, 1, 36, 6, 1, I_46_2 | I_49_2),
+    (35, 3, 39, 28, 0, I_51_0 | II_47_0),
+    (61, 1, 62, 6, 1, I_43_0),
+    (59, 5, 63, 30, 0, I_43_0),
+    (58, 0, 63, 30, 1, I_43_0),
+    (62, 1, 63, 6, 1, I_44_0),
+    (60, 5, 64, 30, 0, I_44_0),
+    (59, 0, 64, 30, 1, I_44_0),
+    (62, 2, 63, 7, 1, I_47_2),
+    (41, 6, 43, 6, 0, I_47_2),
+    (63, 2, 64, 7, 1, I_48_2),
+    (48, 6, 49, 1, 0, I_48_2),
+    (49, 6, 50, 1, 0, I_49_2),
+    (42, 1, 50, 1, 1, I_49_2),
+    (39, 6, 40, 1, 0, I_49_2),
+    (38, 1, 40, 1, 1, I_49_2),
+    (51, 6, 52, 1, 0, I_51_2),
+    (49, 6, 51, 6, 0, I_51_2),
+    (37, 1, 37, 6, 0, I_51_2),
+    (35, 5, 39, 30, 0, I_51_2),
+    (36, 3, 40, 28, 0, II_48_0),
+    (35, 30, 40, 28, 1, II_48_0),
+    (37, 3, 41, 28, 0, II_49_0),
+    (36, 30, 41, 28, 1, II_49_0),
+    (53, 6, 54, 1, 0, II_49_2),
+    (51, 6, 53, 6, 0, II_49_2),
+    (50, 1, 54, 1, 1, II_49_2),
+    (45, 6, 46, 1, 0, II_49_2),
+    (37, 5, 41, 30, 0, II_49_2),
+    (36, 0, 41, 30, 1, II_49_2),
+    (55, 29, 58, 29, 1, II_50_0),
+    (38, 3, 42, 28, 0, II_50_0),
+    (37, 30, 42, 28, 1, II_50_0),
+    (54, 6, 55, 1, 0, II_50_2),
+    (52, 6, 54, 6, 0, II_50_2),
+    (51, 1, 55, 1, 1, II_50_2),
+    (45, 1, 47, 1, 1, II_50_2),
+    (38, 5, 42, 30, 0, II_50_2),
+    (37, 0, 42, 30, 1, II_50_2),
+    (39, 3, 43, 28, 0, II_51_0),
+    (38, 30, 43, 28, 1, II_51_0),
+    (55, 6, 56, 1, 0, II_51_2),
+    (53, 6, 55, 6, 0, II_51_2),
+    (52, 1, 56, 1, 1, II_51_2),
+    (46, 1, 48, 1, 1, II_51_2),
+    (39, 5, 43, 30, 0, II_51_2),
+    (38, 0, 43, 30, 1, II_51_2),
+    (59, 29, 60, 29, 0, II_52_0),
+    (40, 3, 44, 28, 0, II_52_0),
+    (40, 4, 44, 29, 0, II_52_0),
+    (39, 30, 44, 28, 1, II_52_0),
+    (58, 29, 61, 29, 1, II_53_0),
+    (57, 4, 61, 29, 0, II_53_0),
+    (41, 3, 45, 28, 0, II_53_0),
+    (41, 4, 45, 29, 0, II_53_0),
+    (58, 4, 62, 29, 0, II_54_0),
+    (42, 3, 46, 28, 0, II_54_0),
+    (42, 4, 46, 29, 0, II_54_0),
+    (59, 4, 63, 29, 0, II_55_0),
+    (57, 4, 59, 29, 0, II_55_0),
+    (43, 3, 47, 28, 0, II_55_0),
+    (43, 4, 47, 29, 0, II_55_0),
+    (60, 4, 64, 29, 0, II_56_0),
+    (44, 3, 48, 28, 0, II_56_0),
+    (44, 4, 48, 29, 0, II_56_0),
+    (36, 4, 37, 4, 1, I_50_0),
+    (43, 1, 51, 1, 1, I_50_2),
+    (37, 4, 38, 4, 1, I_51_0),
+    (38, 4, 39, 4, 1, I_52_0),
+    (47, 1, 51, 1, 1, II_46_2),
+    (40, 6, 42, 6, 0, I_46_2),
+];
+
+/// Returns the mask of DVs whose unavoidable bit conditions all hold for the expanded message
+/// block `w`.
+fn ubc_mask(w: &[u32; 80]) -> u32 {
+    let mut mask = !0;
+    for &(w0, b0, w1, b1, value, dvs) in UNAVOIDABLE_BIT_CONDITIONS.iter() {
+        if mask & dvs != 0 && ((w[w0] >> b0) ^ (w[w1] >> b1)) & 1 != value {
+            mask &= !dvs;
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn test() {
+        use crate::{sha1, sha1dc, Hash, HashEngine};
+        use crate::hex::{FromHex, ToHex};
+
+        #[derive(Clone)]
+        struct Test {
+            input: &'static str,
+            output: Vec<u8>,
+            output_str: &'static str,
+        }
+
+        let tests = vec![
+            // Examples from wikipedia
+            Test {
+                input: "",
+                output: vec![
+                    0xda, 0x39, 0xa3, 0xee,
+                    0x5e, 0x6b, 0x4b, 0x0d,
+                    0x32, 0x55, 0xbf, 0xef,
+                    0x95, 0x60, 0x18, 0x90,
+                    0xaf, 0xd8, 0x07, 0x09,
+                ],
+                output_str: "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+            },
+            Test {
+                input: "The quick brown fox jumps over the lazy dog",
+                output: vec![
+                    0x2f, 0xd4, 0xe1, 0xc6,
+                    0x7a, 0x2d, 0x28, 0xfc,
+                    0xed, 0x84, 0x9e, 0xe1,
+                    0xbb, 0x76, 0xe7, 0x39,
+                    0x1b, 0x93, 0xeb, 0x12,
+                ],
+                output_str: "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12",
+            },
+            Test {
+                input: "The quick brown fox jumps over the lazy cog",
+                output: vec![
+                    0xde, 0x9f, 0x2c, 0x7f,
+                    0xd2, 0x5e, 0x1b, 0x3a,
+                    0xfa, 0xd3, 0xe8, 0x5a,
+                    0x0b, 0xd1, 0x7d, 0x9b,
+                    0x10, 0x0d, 0xb4, 0xb3,
+                ],
+                output_str: "de9f2c7fd25e1b3afad3e85a0bd17d9b100db4b3",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = sha1dc::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, sha1dc::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash[..], &test.output[..]);
+            assert_eq!(&hash.to_hex(), &test.output_str);
+            assert_eq!(&hash[..], &sha1::Hash::hash(test.input.as_bytes())[..]);
+            assert_eq!(sha1dc::Hash::hash_checked(test.input.as_bytes()), (hash, false));
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = sha1dc::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = sha1dc::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+            assert_eq!(hash.into_inner()[..].as_ref(), test.output.as_slice());
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn shattered() {
+        use crate::{sha1, sha1dc, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        // The first 320 bytes of shattered-1.pdf and shattered-2.pdf from https://shattered.io,
+        // which are a common 192-byte PDF header followed by the two colliding block pairs.
+        let header = Vec::<u8>::from_hex(
+            "255044462d312e330a25e2e3cfd30a0a0a312030206f626a0a3c3c2f57696474\
+             682032203020522f4865696768742033203020522f547970652034203020522f\
+             537562747970652035203020522f46696c7465722036203020522f436f6c6f72\
+             53706163652037203020522f4c656e6774682038203020522f42697473506572\
+             436f6d706f6e656e7420383e3e0a73747265616d0affd8fffe00245348412d31\
+             20697320646561642121212121852fec092339759c39b1a1c63c4c97e1fffe01"
+        ).unwrap();
+        let blocks_1 = Vec::<u8>::from_hex(
+            "7346dc9166b67e118f029ab621b2560ff9ca67cca8c7f85ba84c79030c2b3de2\
+             18f86db3a90901d5df45c14f26fedfb3dc38e96ac22fe7bd728f0e45bce046d2\
+             3c570feb141398bb552ef5a0a82be331fea48037b8b5d71f0e332edf93ac3500\
+             eb4ddc0decc1a864790c782c76215660dd309791d06bd0af3f98cda4bc4629b1"
+        ).unwrap();
+        let blocks_2 = Vec::<u8>::from_hex(
+            "7f46dc93a6b67e013b029aaa1db2560b45ca67d688c7f84b8c4c791fe02b3df6\
+             14f86db1690901c56b45c1530afedfb76038e972722fe7ad728f0e4904e046c2\
+             30570fe9d41398abe12ef5bc942be33542a4802d98b5d70f2a332ec37fac3514\
+             e74ddc0f2cc1a874cd0c78305a21566461309789606bd0bf3f98cda8044629a1"
+        ).unwrap();
+
+        let prefix_1 = [&header[..], &blocks_1[..]].concat();
+        let prefix_2 = [&header[..], &blocks_2[..]].concat();
+        assert_ne!(prefix_1, prefix_2);
+
+        // Plain SHA1 collides.
+        let expected = sha1dc::Hash::from_hex("f92d74e3874587aaf443d1db961d4e26dde13e9c").unwrap();
+        assert_eq!(&sha1::Hash::hash(&prefix_1)[..], &expected[..]);
+        assert_eq!(&sha1::Hash::hash(&prefix_2)[..], &expected[..]);
+
+        // Both inputs are detected, and produce the SHA1 hash unless a safe hash is requested.
+        assert_eq!(sha1dc::Hash::hash_checked(&prefix_1), (expected, true));
+        assert_eq!(sha1dc::Hash::hash_checked(&prefix_2), (expected, true));
+
+        let mut engine = sha1dc::HashEngine::with_safe_hash(true);
+        engine.input(&header);
+        assert!(!engine.collision_detected());
+        for ch in &blocks_1 {
+            engine.input(&[*ch]);
+        }
+        assert!(engine.collision_detected());
+        assert_eq!(
+            sha1dc::Hash::from_engine_checked(engine),
+            (sha1dc::Hash::from_hex("7117b3cb9225aaf0d8ef1a40e493957b0bf8693d").unwrap(), true),
+        );
+
+        let mut engine = sha1dc::HashEngine::with_safe_hash(true);
+        engine.input(&prefix_2);
+        assert_eq!(
+            sha1dc::Hash::from_engine(engine),
+            sha1dc::Hash::from_hex("29f38ae9fd98e2931120fa0bf213e024250d3f6a").unwrap(),
+        );
+
+        // Changing a byte of the colliding blocks breaks the collision, and the detection.
+        let mut tampered = prefix_1.clone();
+        tampered[200] ^= 1;
+        let (hash, detected) = sha1dc::Hash::hash_checked(&tampered);
+        assert!(!detected);
+        assert_eq!(&hash[..], &sha1::Hash::hash(&tampered)[..]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn sha1dc_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{sha1dc, Hash};
+
+        static HASH_BYTES: [u8; 20] = [
+            0x13, 0x20, 0x72, 0xdf,
+            0x69, 0x09, 0x33, 0x83,
+            0x5e, 0xb8, 0xb6, 0xad,
+            0x0b, 0x77, 0xe7, 0xb6,
+            0xf1, 0x4a, 0xca, 0xd7,
+        ];
+
+        let hash = sha1dc::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(&hash.readable(), &[Token::Str("132072df690933835eb8b6ad0b77e7b6f14acad7")]);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, sha1dc};
+
+    #[bench]
+    pub fn sha1dc_10(bh: &mut Bencher) {
+        let mut engine = sha1dc::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha1dc_1k(bh: &mut Bencher) {
+        let mut engine = sha1dc::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha1dc_64k(bh: &mut Bencher) {
+        let mut engine = sha1dc::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
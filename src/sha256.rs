@@ -30,7 +30,7 @@ crate::internal_macros::hash_type! {
 }
 
 #[cfg(not(fuzzing))]
-fn from_engine(mut e: HashEngine) -> Hash {
+pub(crate) fn from_engine(mut e: HashEngine) -> Hash {
     // pad buffer with a single 1-bit then all 0s, until there are exactly 8 bytes remaining
     let data_len = e.length as u64;
 
@@ -50,7 +50,7 @@ fn from_engine(mut e: HashEngine) -> Hash {
 }
 
 #[cfg(fuzzing)]
-fn from_engine(e: HashEngine) -> Hash {
+pub(crate) fn from_engine(e: HashEngine) -> Hash {
     let mut hash = e.midstate().into_inner();
     if hash == [0; 32] {
         // Assume sha256 is secure and never generate 0-hashes (which represent invalid
@@ -60,7 +60,7 @@ fn from_engine(e: HashEngine) -> Hash {
     Hash(hash)
 }
 
-const BLOCK_SIZE: usize = 64;
+pub(crate) const BLOCK_SIZE: usize = 64;
 
 /// Engine to compute SHA256 hash function.
 #[derive(Clone)]
@@ -70,6 +70,8 @@ pub struct HashEngine {
     length: usize,
 }
 
+crate::internal_macros::engine_zeroize_impl!(HashEngine, buffer, h, length);
+
 impl Default for HashEngine {
     fn default() -> Self {
         HashEngine {
@@ -80,6 +82,17 @@ impl Default for HashEngine {
     }
 }
 
+impl HashEngine {
+    /// Constructs a hash engine suitable for use inside the default `sha224::Hash` engine.
+    pub(crate) fn sha224() -> Self {
+        HashEngine {
+            h: [0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+}
+
 impl crate::HashEngine for HashEngine {
     type MidState = Midstate;
 
@@ -108,6 +121,12 @@ impl crate::HashEngine for HashEngine {
     engine_input_impl!();
 }
 
+impl crate::FromMidstate for HashEngine {
+    fn from_midstate(midstate: Midstate, length: usize) -> HashEngine {
+        HashEngine::from_midstate(midstate, length)
+    }
+}
+
 impl Hash {
     /// Iterate the sha256 algorithm to turn a sha256 hash into a sha256d hash
     pub fn hash_again(&self) -> sha256d::Hash {
@@ -122,6 +141,8 @@ pub struct Midstate(pub [u8; 32]);
 hex_fmt_impl!(Midstate);
 serde_impl!(Midstate, 32);
 borrow_slice_impl!(Midstate);
+ct_eq_impl!(Midstate);
+zeroize_impl!(Midstate);
 
 impl<I: SliceIndex<[u8]>> Index<I> for Midstate {
     type Output = I::Output;
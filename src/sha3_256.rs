@@ -0,0 +1,263 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! SHA3-256 implementation.
+//!
+//! SHA3-256 is the Keccak sponge with a capacity of 512 bits and the FIPS 202 domain separation
+//! suffix. The same sponge, finalized with the original Keccak padding, is used by
+//! [`keccak256`](crate::keccak256).
+//!
+
+use core::{cmp, str};
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, hex, keccak};
+
+crate::internal_macros::hash_type! {
+    256,
+    false,
+    "Output of the SHA3-256 hash function.",
+    "crate::util::json_hex_string::len_32"
+}
+
+/// Rate of the sponge, in bytes.
+pub(crate) const BLOCK_SIZE: usize = 136;
+
+/// Engine to compute SHA3-256 hash function.
+#[derive(Clone)]
+pub struct HashEngine {
+    state: [u64; 25],
+    length: usize,
+    buffer: [u8; BLOCK_SIZE],
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, state, length, buffer);
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine {
+            state: [0; 25],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; keccak::STATE_SIZE];
+
+    fn midstate(&self) -> [u8; keccak::STATE_SIZE] {
+        keccak::state_to_bytes(&self.state)
+    }
+
+    const BLOCK_SIZE: usize = 136;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length
+    }
+
+    engine_input_impl!();
+}
+
+impl HashEngine {
+    fn process_block(&mut self) {
+        keccak::absorb_block(&mut self.state, &self.buffer);
+    }
+
+    /// Pads the buffered input using `suffix` as the domain separation bits, absorbs the final
+    /// block and squeezes out a 256-bit digest.
+    pub(crate) fn finalize(mut self, suffix: u8) -> [u8; 32] {
+        keccak::absorb_final(&mut self.state, &mut self.buffer, self.length % BLOCK_SIZE, suffix);
+
+        let mut ret = [0; 32];
+        ret.copy_from_slice(&keccak::state_to_bytes(&self.state)[..32]);
+        ret
+    }
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    Hash(e.finalize(keccak::SHA3_SUFFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn test() {
+        use crate::{sha3_256, Hash, HashEngine};
+        use crate::hex::{FromHex, ToHex};
+
+        #[derive(Clone)]
+        struct Test {
+            input: &'static str,
+            output: Vec<u8>,
+            output_str: &'static str,
+        }
+
+        let tests = vec![
+            // Examples from NIST (SHA3-256_Msg0.pdf in the "Example Values" collection, and the
+            // "abc" and 896-bit messages used for the SHA-2 examples)
+            Test {
+                input: "",
+                output: vec![
+                    0xa7, 0xff, 0xc6, 0xf8, 0xbf, 0x1e, 0xd7, 0x66,
+                    0x51, 0xc1, 0x47, 0x56, 0xa0, 0x61, 0xd6, 0x62,
+                    0xf5, 0x80, 0xff, 0x4d, 0xe4, 0x3b, 0x49, 0xfa,
+                    0x82, 0xd8, 0x0a, 0x4b, 0x80, 0xf8, 0x43, 0x4a,
+                ],
+                output_str: "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+            },
+            Test {
+                input: "abc",
+                output: vec![
+                    0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2,
+                    0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3, 0x90, 0xbd,
+                    0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b,
+                    0x46, 0xbf, 0xe2, 0x45, 0x11, 0x43, 0x15, 0x32,
+                ],
+                output_str: "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            },
+            Test {
+                input: "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                output: vec![
+                    0x91, 0x6f, 0x60, 0x61, 0xfe, 0x87, 0x97, 0x41,
+                    0xca, 0x64, 0x69, 0xb4, 0x39, 0x71, 0xdf, 0xdb,
+                    0x28, 0xb1, 0xa3, 0x2d, 0xc3, 0x6c, 0xb3, 0x25,
+                    0x4e, 0x81, 0x2b, 0xe2, 0x7a, 0xad, 0x1d, 0x18,
+                ],
+                output_str: "916f6061fe879741ca6469b43971dfdb28b1a32dc36cb3254e812be27aad1d18",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = sha3_256::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, sha3_256::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash[..], &test.output[..]);
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = sha3_256::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = sha3_256::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+            assert_eq!(hash.into_inner()[..].as_ref(), test.output.as_slice());
+        }
+    }
+
+    #[test]
+    fn block_boundaries() {
+        use crate::{sha3_256, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        // Messages of 'a's around the rate, so that the padding both fits in and spills over
+        // the final block.
+        let tests = [
+            (135, "8094bb53c44cfb1e67b7c30447f9a1c33696d2463ecc1d9c92538913392843c9"),
+            (136, "3fc5559f14db8e453a0a3091edbd2bc25e11528d81c66fa570a4efdcc2695ee1"),
+            (137, "f8d6846cedd2ccfadf15c5879ef95af724d799eed7391fb1c91f95344e738614"),
+            (272, "a490357b9b3fb39d0a89a117734e5b020b1f33c7bf3fa3575c396425432003d3"),
+        ];
+
+        let data = [b'a'; 272];
+        for &(len, output_str) in tests.iter() {
+            let expected = sha3_256::Hash::from_hex(output_str).expect("parse hex");
+            assert_eq!(sha3_256::Hash::hash(&data[..len]), expected);
+
+            let mut engine = sha3_256::Hash::engine();
+            for chunk in data[..len].chunks(7) {
+                engine.input(chunk);
+            }
+            assert_eq!(engine.n_bytes_hashed(), len);
+            assert_eq!(sha3_256::Hash::from_engine(engine), expected);
+        }
+    }
+
+    #[test]
+    fn midstate() {
+        use crate::{sha3_256, Hash, HashEngine};
+
+        let mut engine = sha3_256::Hash::engine();
+        assert_eq!(engine.midstate()[..], [0; 200][..]);
+
+        // Partial blocks are buffered and do not change the state.
+        engine.input(&[0; 135]);
+        assert_eq!(engine.midstate()[..], [0; 200][..]);
+
+        engine.input(&[0]);
+        let midstate = engine.midstate();
+        assert_eq!(
+            &midstate[..8],
+            &[0xe7, 0xdd, 0xe1, 0x40, 0x79, 0x8f, 0x25, 0xf1][..],
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn sha3_256_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{sha3_256, Hash};
+
+        static HASH_BYTES: [u8; 32] = [
+            0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2,
+            0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3, 0x90, 0xbd,
+            0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b,
+            0x46, 0xbf, 0xe2, 0x45, 0x11, 0x43, 0x15, 0x32,
+        ];
+
+        let hash = sha3_256::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(&hash.readable(), &[Token::Str("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")]);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, sha3_256};
+
+    #[bench]
+    pub fn sha3_256_10(bh: &mut Bencher) {
+        let mut engine = sha3_256::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha3_256_1k(bh: &mut Bencher) {
+        let mut engine = sha3_256::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha3_256_64k(bh: &mut Bencher) {
+        let mut engine = sha3_256::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
@@ -29,7 +29,7 @@ use crate::{Error, HashEngine as _, hex};
 
 crate::internal_macros::hash_trait_impls!(512, false);
 
-const BLOCK_SIZE: usize = 128;
+pub(crate) const BLOCK_SIZE: usize = 128;
 
 /// Engine to compute SHA512 hash function.
 #[derive(Clone)]
@@ -39,6 +39,8 @@ pub struct HashEngine {
     buffer: [u8; BLOCK_SIZE],
 }
 
+crate::internal_macros::engine_zeroize_impl!(HashEngine, h, length, buffer);
+
 impl Default for HashEngine {
     fn default() -> Self {
         HashEngine {
@@ -52,6 +54,32 @@ impl Default for HashEngine {
     }
 }
 
+impl HashEngine {
+    /// Constructs a hash engine suitable for use inside the default `sha384::Hash` engine.
+    pub(crate) fn sha384() -> Self {
+        HashEngine {
+            h: [
+                0xcbbb9d5dc1059ed8, 0x629a292a367cd507, 0x9159015a3070dd17, 0x152fecd8f70e5939,
+                0x67332667ffc00b31, 0x8eb44a8768581511, 0xdb0c2e0d64f98fa7, 0x47b5481dbefa4fa4,
+            ],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+
+    /// Constructs a hash engine suitable for use inside the default `sha512_256::Hash` engine.
+    pub(crate) fn sha512_256() -> Self {
+        HashEngine {
+            h: [
+                0x22312194fc2bf72c, 0x9f555fa3c84c64c2, 0x2393b86b6f53b151, 0x963877195940eabd,
+                0x96283ee2a88effe3, 0xbe5e1e2553863992, 0x2b0199fc2c85b8aa, 0x0eb72ddc81c52ca2,
+            ],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+}
+
 impl crate::HashEngine for HashEngine {
     type MidState = [u8; 64];
 
@@ -141,7 +169,7 @@ impl hash::Hash for Hash {
 }
 
 #[cfg(not(fuzzing))]
-fn from_engine(mut e: HashEngine) -> Hash {
+pub(crate) fn from_engine(mut e: HashEngine) -> Hash {
     // pad buffer with a single 1-bit then all 0s, until there are exactly 16 bytes remaining
     let data_len = e.length as u64;
 
@@ -162,7 +190,7 @@ fn from_engine(mut e: HashEngine) -> Hash {
 }
 
 #[cfg(fuzzing)]
-fn from_engine(e: HashEngine) -> Hash {
+pub(crate) fn from_engine(e: HashEngine) -> Hash {
     let mut hash = e.midstate();
     hash[0] ^= 0xff; // Make this distinct from SHA-256
     Hash(hash)
@@ -190,7 +218,33 @@ macro_rules! round(
     )
 );
 
+impl crate::FromMidstate for HashEngine {
+    fn from_midstate(midstate: [u8; 64], length: usize) -> HashEngine {
+        HashEngine::from_midstate(midstate, length)
+    }
+}
+
 impl HashEngine {
+    /// Create a new [`HashEngine`] from a midstate.
+    ///
+    /// # Panics
+    ///
+    /// If `length` is not a multiple of the block size.
+    pub fn from_midstate(midstate: [u8; 64], length: usize) -> HashEngine {
+        assert!(length % BLOCK_SIZE == 0, "length is no multiple of the block size");
+
+        let mut ret = [0; 8];
+        for (ret_val, midstate_bytes) in ret.iter_mut().zip(midstate.chunks_exact(8)) {
+            *ret_val = u64::from_be_bytes(midstate_bytes.try_into().expect("8 byte slice"));
+        }
+
+        HashEngine {
+            buffer: [0; BLOCK_SIZE],
+            h: ret,
+            length,
+        }
+    }
+
     // Algorithm copied from libsecp256k1
     fn process_block(&mut self) {
         debug_assert_eq!(self.buffer.len(), BLOCK_SIZE);
@@ -384,6 +438,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn engine_with_state() {
+        use crate::{sha512, Hash, HashEngine};
+
+        let mut engine = sha512::Hash::engine();
+        engine.input(&[1; 200]);
+
+        // Initializing an engine with midstate from another engine should result in
+        // both engines producing the same hashes
+        for data in [&[3u8; 1][..], &[4; 127], &[5; 129], &[6; 300]].iter() {
+            let mut engine = engine.clone();
+            engine.input(&[2; 56]);
+            let mut midstate_engine =
+                sha512::HashEngine::from_midstate(engine.midstate(), engine.n_bytes_hashed());
+            engine.input(data);
+            midstate_engine.input(data);
+            assert_eq!(sha512::Hash::from_engine(engine), sha512::Hash::from_engine(midstate_engine));
+        }
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn sha512_serde() {
@@ -0,0 +1,283 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! SHAKE128 implementation.
+//!
+//! SHAKE128 is the FIPS 202 extendable-output function with a capacity of 256 bits. Output of
+//! arbitrary length is read from the [`XofReader`] returned by [`HashEngine::finalize_xof`];
+//! the [`Hash`](struct@Hash) type is the first 256 bits of that output. Hash types with other
+//! output lengths can be created with [`shake_hash_newtype!`](crate::shake_hash_newtype).
+//!
+
+use core::{cmp, str};
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, hex, keccak};
+
+pub use crate::keccak::XofReader;
+
+crate::internal_macros::hash_type! {
+    256,
+    false,
+    "Output of the SHAKE128 extendable-output function, truncated to 256 bits.",
+    "crate::util::json_hex_string::len_32"
+}
+
+/// Rate of the sponge, in bytes.
+pub(crate) const BLOCK_SIZE: usize = 168;
+
+/// Engine to compute SHAKE128 extendable-output function.
+#[derive(Clone)]
+pub struct HashEngine {
+    state: [u64; 25],
+    length: usize,
+    buffer: [u8; BLOCK_SIZE],
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, state, length, buffer);
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine {
+            state: [0; 25],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; keccak::STATE_SIZE];
+
+    fn midstate(&self) -> [u8; keccak::STATE_SIZE] {
+        keccak::state_to_bytes(&self.state)
+    }
+
+    const BLOCK_SIZE: usize = 168;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length
+    }
+
+    engine_input_impl!();
+}
+
+impl HashEngine {
+    fn process_block(&mut self) {
+        keccak::absorb_block(&mut self.state, &self.buffer);
+    }
+
+    /// Finishes absorbing input and returns a reader from which output of any length can be
+    /// squeezed.
+    pub fn finalize_xof(mut self) -> XofReader {
+        let buf_idx = self.length % BLOCK_SIZE;
+        keccak::absorb_final(&mut self.state, &mut self.buffer, buf_idx, keccak::SHAKE_SUFFIX);
+        XofReader::new(self.state, BLOCK_SIZE)
+    }
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    let mut ret = [0; 32];
+    e.finalize_xof().squeeze(&mut ret);
+    Hash(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    crate::shake_hash_newtype!(Test512Hash, shake128, 64, doc="SHAKE128 truncated to 512 bits.");
+
+    #[test]
+    fn newtype() {
+        use crate::{shake128, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let expected = <[u8; 64]>::from_hex(
+            "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc844c50af32acd3f2cdd066568706f509bc1bdde58295dae3f891a9a0fca578378"
+        ).unwrap();
+        let hash = Test512Hash::hash(b"abc");
+        assert_eq!(&hash.into_inner()[..], &expected[..]);
+        assert_eq!(Test512Hash::LEN, 64);
+        assert_eq!(Test512Hash::from_slice(&expected[..]).unwrap(), hash);
+        assert!(Test512Hash::from_slice(&expected[1..]).is_err());
+
+        // The output is a prefix of the XOF output, and of the default length `Hash`.
+        let mut engine = Test512Hash::engine();
+        engine.input(b"abc");
+        let mut xof = [0; 64];
+        engine.finalize_xof().squeeze(&mut xof);
+        assert_eq!(&xof[..], &hash[..]);
+        let default = shake128::Hash::hash(b"abc");
+        let prefix = core::cmp::min(shake128::Hash::LEN, 64);
+        assert_eq!(&default[..prefix], &hash[..prefix]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn test() {
+        use crate::{shake128, Hash, HashEngine};
+        use crate::hex::{FromHex, ToHex};
+
+        #[derive(Clone)]
+        struct Test {
+            input: &'static str,
+            output: Vec<u8>,
+            output_str: &'static str,
+        }
+
+        let tests = vec![
+            // Examples from NIST (SHAKE128_Msg0.pdf in the "Example Values" collection, and the
+            // "abc" and 896-bit messages used for the SHA-2 examples)
+            Test {
+                input: "",
+                output: vec![
+                    0x7f, 0x9c, 0x2b, 0xa4, 0xe8, 0x8f, 0x82, 0x7d,
+                    0x61, 0x60, 0x45, 0x50, 0x76, 0x05, 0x85, 0x3e,
+                    0xd7, 0x3b, 0x80, 0x93, 0xf6, 0xef, 0xbc, 0x88,
+                    0xeb, 0x1a, 0x6e, 0xac, 0xfa, 0x66, 0xef, 0x26,
+                ],
+                output_str: "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26",
+            },
+            Test {
+                input: "abc",
+                output: vec![
+                    0x58, 0x81, 0x09, 0x2d, 0xd8, 0x18, 0xbf, 0x5c,
+                    0xf8, 0xa3, 0xdd, 0xb7, 0x93, 0xfb, 0xcb, 0xa7,
+                    0x40, 0x97, 0xd5, 0xc5, 0x26, 0xa6, 0xd3, 0x5f,
+                    0x97, 0xb8, 0x33, 0x51, 0x94, 0x0f, 0x2c, 0xc8,
+                ],
+                output_str: "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+            },
+            Test {
+                input: "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                output: vec![
+                    0x7b, 0x6d, 0xf6, 0xff, 0x18, 0x11, 0x73, 0xb6,
+                    0xd7, 0x89, 0x8d, 0x7f, 0xf6, 0x3f, 0xb0, 0x7b,
+                    0x7c, 0x23, 0x7d, 0xaf, 0x47, 0x1a, 0x5a, 0xe5,
+                    0x60, 0x2a, 0xdb, 0xcc, 0xef, 0x9c, 0xcf, 0x4b,
+                ],
+                output_str: "7b6df6ff181173b6d7898d7ff63fb07b7c237daf471a5ae5602adbccef9ccf4b",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = shake128::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, shake128::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash[..], &test.output[..]);
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = shake128::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = shake128::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+            assert_eq!(hash.into_inner()[..].as_ref(), test.output.as_slice());
+        }
+    }
+
+    #[test]
+    fn xof() {
+        use crate::{shake128, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let mut engine = shake128::Hash::engine();
+        engine.input(b"abc");
+
+        // Squeeze 512 bytes, more than three times the rate, in one go and in uneven pieces.
+        let mut out = [0u8; 512];
+        engine.clone().finalize_xof().squeeze(&mut out);
+
+        let mut reader = engine.finalize_xof();
+        let mut pieces = [0u8; 512];
+        for chunk in pieces.chunks_mut(37) {
+            reader.squeeze(chunk);
+        }
+        assert_eq!(&out[..], &pieces[..]);
+
+        let hash = shake128::Hash::hash(b"abc");
+        assert_eq!(&out[..32], &hash[..]);
+        assert_eq!(
+            &out[480..],
+            &<[u8; 32]>::from_hex("7085901803ec6f17f0ec650a292198275211a56bf13f0bf7241268b50d3f1ec8").unwrap()[..],
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn shake128_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{shake128, Hash};
+
+        static HASH_BYTES: [u8; 32] = [
+            0x58, 0x81, 0x09, 0x2d, 0xd8, 0x18, 0xbf, 0x5c,
+            0xf8, 0xa3, 0xdd, 0xb7, 0x93, 0xfb, 0xcb, 0xa7,
+            0x40, 0x97, 0xd5, 0xc5, 0x26, 0xa6, 0xd3, 0x5f,
+            0x97, 0xb8, 0x33, 0x51, 0x94, 0x0f, 0x2c, 0xc8,
+        ];
+
+        let hash = shake128::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(&hash.readable(), &[Token::Str("5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8")]);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, shake128};
+
+    #[bench]
+    pub fn shake128_10(bh: &mut Bencher) {
+        let mut engine = shake128::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn shake128_1k(bh: &mut Bencher) {
+        let mut engine = shake128::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn shake128_64k(bh: &mut Bencher) {
+        let mut engine = shake128::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn shake128_squeeze_1k(bh: &mut Bencher) {
+        let mut reader = shake128::Hash::engine().finalize_xof();
+        let mut out = [0u8; 1024];
+        bh.iter( || {
+            reader.squeeze(&mut out);
+        });
+        bh.bytes = out.len() as u64;
+    }
+}
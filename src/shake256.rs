@@ -0,0 +1,348 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! SHAKE256 implementation.
+//!
+//! SHAKE256 is the FIPS 202 extendable-output function with a capacity of 512 bits. Output of
+//! arbitrary length is read from the [`XofReader`] returned by [`HashEngine::finalize_xof`];
+//! the [`Hash`](struct@Hash) type is the first 512 bits of that output. Hash types with other
+//! output lengths can be created with [`shake_hash_newtype!`](crate::shake_hash_newtype).
+//!
+
+use core::{cmp, hash, str};
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, hex, keccak};
+
+pub use crate::keccak::XofReader;
+
+crate::internal_macros::hash_trait_impls!(512, false);
+
+/// Rate of the sponge, in bytes.
+pub(crate) const BLOCK_SIZE: usize = 136;
+
+/// Engine to compute SHAKE256 extendable-output function.
+#[derive(Clone)]
+pub struct HashEngine {
+    state: [u64; 25],
+    length: usize,
+    buffer: [u8; BLOCK_SIZE],
+}
+
+crate::internal_macros::engine_zeroize_impl!(HashEngine, state, length, buffer);
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine {
+            state: [0; 25],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = [u8; keccak::STATE_SIZE];
+
+    fn midstate(&self) -> [u8; keccak::STATE_SIZE] {
+        keccak::state_to_bytes(&self.state)
+    }
+
+    const BLOCK_SIZE: usize = 136;
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.length
+    }
+
+    engine_input_impl!();
+}
+
+impl HashEngine {
+    fn process_block(&mut self) {
+        keccak::absorb_block(&mut self.state, &self.buffer);
+    }
+
+    /// Finishes absorbing input and returns a reader from which output of any length can be
+    /// squeezed.
+    pub fn finalize_xof(mut self) -> XofReader {
+        let buf_idx = self.length % BLOCK_SIZE;
+        keccak::absorb_final(&mut self.state, &mut self.buffer, buf_idx, keccak::SHAKE_SUFFIX);
+        XofReader::new(self.state, BLOCK_SIZE)
+    }
+}
+
+/// Output of the SHAKE256 extendable-output function, truncated to 512 bits.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[repr(transparent)]
+pub struct Hash(
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "crate::util::json_hex_string::len_64"))]
+    [u8; 64]
+);
+
+impl Hash {
+    fn internal_new(arr: [u8; 64]) -> Self {
+        Hash(arr)
+    }
+
+    fn internal_engine() -> HashEngine {
+        Default::default()
+    }
+}
+
+impl PartialEq for Hash {
+    fn eq(&self, other: &Hash) -> bool {
+        self.0[..] == other.0[..]
+    }
+}
+
+impl Eq for Hash {}
+
+impl Default for Hash {
+    fn default() -> Hash {
+        Hash([0; 64])
+    }
+}
+
+impl PartialOrd for Hash {
+    fn partial_cmp(&self, other: &Hash) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hash {
+    fn cmp(&self, other: &Hash) -> cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl hash::Hash for Hash {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    let mut ret = [0; 64];
+    e.finalize_xof().squeeze(&mut ret);
+    Hash(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    crate::shake_hash_newtype!(Test256Hash, shake256, 32, doc="SHAKE256 truncated to 256 bits.");
+
+    #[test]
+    fn newtype() {
+        use crate::{shake256, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let expected = <[u8; 32]>::from_hex(
+            "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739"
+        ).unwrap();
+        let hash = Test256Hash::hash(b"abc");
+        assert_eq!(&hash.into_inner()[..], &expected[..]);
+        assert_eq!(Test256Hash::LEN, 32);
+        assert_eq!(Test256Hash::from_slice(&expected[..]).unwrap(), hash);
+        assert!(Test256Hash::from_slice(&expected[1..]).is_err());
+
+        // The output is a prefix of the XOF output, and of the default length `Hash`.
+        let mut engine = Test256Hash::engine();
+        engine.input(b"abc");
+        let mut xof = [0; 32];
+        engine.finalize_xof().squeeze(&mut xof);
+        assert_eq!(&xof[..], &hash[..]);
+        let default = shake256::Hash::hash(b"abc");
+        let prefix = core::cmp::min(shake256::Hash::LEN, 32);
+        assert_eq!(&default[..prefix], &hash[..prefix]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn test() {
+        use crate::{shake256, Hash, HashEngine};
+        use crate::hex::{FromHex, ToHex};
+
+        #[derive(Clone)]
+        struct Test {
+            input: &'static str,
+            output: Vec<u8>,
+            output_str: &'static str,
+        }
+
+        let tests = vec![
+            // Examples from NIST (SHAKE256_Msg0.pdf in the "Example Values" collection, and the
+            // "abc" and 896-bit messages used for the SHA-2 examples)
+            Test {
+                input: "",
+                output: vec![
+                    0x46, 0xb9, 0xdd, 0x2b, 0x0b, 0xa8, 0x8d, 0x13,
+                    0x23, 0x3b, 0x3f, 0xeb, 0x74, 0x3e, 0xeb, 0x24,
+                    0x3f, 0xcd, 0x52, 0xea, 0x62, 0xb8, 0x1b, 0x82,
+                    0xb5, 0x0c, 0x27, 0x64, 0x6e, 0xd5, 0x76, 0x2f,
+                    0xd7, 0x5d, 0xc4, 0xdd, 0xd8, 0xc0, 0xf2, 0x00,
+                    0xcb, 0x05, 0x01, 0x9d, 0x67, 0xb5, 0x92, 0xf6,
+                    0xfc, 0x82, 0x1c, 0x49, 0x47, 0x9a, 0xb4, 0x86,
+                    0x40, 0x29, 0x2e, 0xac, 0xb3, 0xb7, 0xc4, 0xbe,
+                ],
+                output_str: "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+            },
+            Test {
+                input: "abc",
+                output: vec![
+                    0x48, 0x33, 0x66, 0x60, 0x13, 0x60, 0xa8, 0x77,
+                    0x1c, 0x68, 0x63, 0x08, 0x0c, 0xc4, 0x11, 0x4d,
+                    0x8d, 0xb4, 0x45, 0x30, 0xf8, 0xf1, 0xe1, 0xee,
+                    0x4f, 0x94, 0xea, 0x37, 0xe7, 0x8b, 0x57, 0x39,
+                    0xd5, 0xa1, 0x5b, 0xef, 0x18, 0x6a, 0x53, 0x86,
+                    0xc7, 0x57, 0x44, 0xc0, 0x52, 0x7e, 0x1f, 0xaa,
+                    0x9f, 0x87, 0x26, 0xe4, 0x62, 0xa1, 0x2a, 0x4f,
+                    0xeb, 0x06, 0xbd, 0x88, 0x01, 0xe7, 0x51, 0xe4,
+                ],
+                output_str: "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+            },
+            Test {
+                input: "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                output: vec![
+                    0x98, 0xbe, 0x04, 0x51, 0x6c, 0x04, 0xcc, 0x73,
+                    0x59, 0x3f, 0xef, 0x3e, 0xd0, 0x35, 0x2e, 0xa9,
+                    0xf6, 0x44, 0x39, 0x42, 0xd6, 0x95, 0x0e, 0x29,
+                    0xa3, 0x72, 0xa6, 0x81, 0xc3, 0xde, 0xaf, 0x45,
+                    0x35, 0x42, 0x37, 0x09, 0xb0, 0x28, 0x43, 0x94,
+                    0x86, 0x84, 0xe0, 0x29, 0x01, 0x0b, 0xad, 0xcc,
+                    0x0a, 0xcd, 0x83, 0x03, 0xfc, 0x85, 0xfd, 0xad,
+                    0x3e, 0xab, 0xf4, 0xf7, 0x8c, 0xae, 0x16, 0x56,
+                ],
+                output_str: "98be04516c04cc73593fef3ed0352ea9f6443942d6950e29a372a681c3deaf4535423709b02843948684e029010badcc0acd8303fc85fdad3eabf4f78cae1656",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = shake256::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, shake256::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash[..], &test.output[..]);
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = shake256::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = shake256::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+            assert_eq!(hash.into_inner()[..].as_ref(), test.output.as_slice());
+        }
+    }
+
+    #[test]
+    fn xof() {
+        use crate::{shake256, Hash, HashEngine};
+        use crate::hex::FromHex;
+
+        let mut engine = shake256::Hash::engine();
+        engine.input(b"abc");
+
+        // Squeeze 512 bytes, more than three times the rate, in one go and in uneven pieces.
+        let mut out = [0u8; 512];
+        engine.clone().finalize_xof().squeeze(&mut out);
+
+        let mut reader = engine.finalize_xof();
+        let mut pieces = [0u8; 512];
+        for chunk in pieces.chunks_mut(37) {
+            reader.squeeze(chunk);
+        }
+        assert_eq!(&out[..], &pieces[..]);
+
+        let hash = shake256::Hash::hash(b"abc");
+        assert_eq!(&out[..64], &hash[..]);
+        assert_eq!(
+            &out[480..],
+            &<[u8; 32]>::from_hex("9440b99d6088e20203aebafa8e9dffa94ed35ef1f41f5fdf549fbcc5a0f68298").unwrap()[..],
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn shake256_serde() {
+        use serde_test::{Configure, Token, assert_tokens};
+        use crate::{shake256, Hash};
+
+        static HASH_BYTES: [u8; 64] = [
+            0x48, 0x33, 0x66, 0x60, 0x13, 0x60, 0xa8, 0x77,
+            0x1c, 0x68, 0x63, 0x08, 0x0c, 0xc4, 0x11, 0x4d,
+            0x8d, 0xb4, 0x45, 0x30, 0xf8, 0xf1, 0xe1, 0xee,
+            0x4f, 0x94, 0xea, 0x37, 0xe7, 0x8b, 0x57, 0x39,
+            0xd5, 0xa1, 0x5b, 0xef, 0x18, 0x6a, 0x53, 0x86,
+            0xc7, 0x57, 0x44, 0xc0, 0x52, 0x7e, 0x1f, 0xaa,
+            0x9f, 0x87, 0x26, 0xe4, 0x62, 0xa1, 0x2a, 0x4f,
+            0xeb, 0x06, 0xbd, 0x88, 0x01, 0xe7, 0x51, 0xe4,
+        ];
+
+        let hash = shake256::Hash::from_slice(&HASH_BYTES).expect("right number of bytes");
+        assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
+        assert_tokens(&hash.readable(), &[Token::Str(
+                "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739\
+                 d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4"
+            )]);
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, shake256};
+
+    #[bench]
+    pub fn shake256_10(bh: &mut Bencher) {
+        let mut engine = shake256::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn shake256_1k(bh: &mut Bencher) {
+        let mut engine = shake256::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn shake256_64k(bh: &mut Bencher) {
+        let mut engine = shake256::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn shake256_squeeze_1k(bh: &mut Bencher) {
+        let mut reader = shake256::Hash::engine().finalize_xof();
+        let mut out = [0u8; 1024];
+        bh.iter( || {
+            reader.squeeze(&mut out);
+        });
+        bh.bytes = out.len() as u64;
+    }
+}
@@ -0,0 +1,148 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BIP152 compact block short transaction IDs.
+//!
+//! A compact block refers to the transactions of a block by 6-byte short IDs. The short ID of a
+//! txid (or wtxid) is its [`siphash24`] hash with the two most significant bytes dropped, keyed
+//! with the first 16 bytes of the SHA256 hash of the block header followed by the nonce of the
+//! `cmpctblock` message.
+//!
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::alloc::vec::Vec;
+
+use crate::{sha256, sha256d, siphash24, Hash, HashEngine};
+
+/// Length of a short transaction ID, in bytes.
+pub const SHORT_ID_LEN: usize = 6;
+
+/// A BIP152 short transaction ID.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ShortId([u8; SHORT_ID_LEN]);
+
+impl ShortId {
+    /// Creates a short ID from its serialized bytes.
+    pub fn from_bytes(bytes: [u8; SHORT_ID_LEN]) -> ShortId {
+        ShortId(bytes)
+    }
+
+    /// Returns the serialized bytes of the short ID.
+    pub fn as_bytes(&self) -> &[u8; SHORT_ID_LEN] {
+        &self.0
+    }
+
+    /// Creates a short ID from the lower 48 bits of `id`.
+    pub fn from_u64(id: u64) -> ShortId {
+        let mut bytes = [0; SHORT_ID_LEN];
+        bytes.copy_from_slice(&id.to_le_bytes()[..SHORT_ID_LEN]);
+        ShortId(bytes)
+    }
+
+    /// Returns the short ID as a 48-bit integer.
+    pub fn to_u64(self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..SHORT_ID_LEN].copy_from_slice(&self.0);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+/// The SipHash-2-4 keys used to compute the short IDs of a compact block.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ShortIdKeys {
+    k0: u64,
+    k1: u64,
+}
+
+impl ShortIdKeys {
+    /// Derives the keys from the serialized block header and the nonce of a `cmpctblock`
+    /// message.
+    pub fn new(header: &[u8; 80], nonce: u64) -> ShortIdKeys {
+        let mut engine = sha256::Hash::engine();
+        engine.input(header);
+        engine.input(&nonce.to_le_bytes());
+        ShortIdKeys::from_hash(&sha256::Hash::from_engine(engine))
+    }
+
+    /// Derives the keys from the SHA256 hash of the block header followed by the nonce.
+    pub fn from_hash(hash: &sha256::Hash) -> ShortIdKeys {
+        let mut k0 = [0; 8];
+        let mut k1 = [0; 8];
+        k0.copy_from_slice(&hash[0..8]);
+        k1.copy_from_slice(&hash[8..16]);
+        ShortIdKeys { k0: u64::from_le_bytes(k0), k1: u64::from_le_bytes(k1) }
+    }
+
+    /// Returns the SipHash-2-4 keys `(k0, k1)`.
+    pub fn keys(&self) -> (u64, u64) {
+        (self.k0, self.k1)
+    }
+
+    /// Computes the short ID of a txid or wtxid.
+    pub fn short_id(&self, txid: &sha256d::Hash) -> ShortId {
+        ShortId::from_u64(siphash24::Hash::hash_uint256_to_u64_with_keys(self.k0, self.k1, txid.as_inner()))
+    }
+
+    /// Computes the short IDs of txids or wtxids, in order.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+    pub fn short_ids<'a, I>(&self, txids: I) -> Vec<ShortId>
+    where
+        I: IntoIterator<Item = &'a sha256d::Hash>,
+    {
+        txids.into_iter().map(|txid| self.short_id(txid)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::FromHex;
+
+    #[test]
+    fn bip152_short_id() {
+        // The header, nonce and only short ID of a version 2 compact block, which uses wtxids.
+        let bytes = Vec::from_hex(
+            "000000206c750a364035aefd5f81508a08769975116d9195312ee4520dceac39e1fdc62c4dc67473b8e354358c1e610afe\
+             aff7410858bd45df43e2940f8a62bd3d5e3ac943c2975cffff7f2000000000"
+        ).unwrap();
+        let mut header = [0; 80];
+        header.copy_from_slice(&bytes);
+        let nonce = 18053200567810711460;
+        let wtxid = sha256d::Hash::from_hex("3c8d7eff789992c75db9542a53d19b6ede7d844dea42c2a0782c3e3694c7bace").unwrap();
+
+        let keys = ShortIdKeys::new(&header, nonce);
+        let hash = sha256::Hash::from_hex("ea3b57884ee6191f1722d0e318d9c442fefdd0a833eb583086fa71e82cf15c69").unwrap();
+        assert_eq!(ShortIdKeys::from_hash(&hash), keys);
+
+        let short_id = keys.short_id(&wtxid);
+        assert_eq!(short_id.as_bytes(), &[0x0a, 0x69, 0x79, 0xe9, 0x71, 0x45]);
+        assert_eq!(short_id, ShortId::from_u64(0xffff_4571_e979_690a));
+        assert_eq!(short_id.to_u64(), 0x4571_e979_690a);
+
+        let (k0, k1) = keys.keys();
+        let slow = siphash24::Hash::hash_to_u64_with_keys(k0, k1, &wtxid[..]);
+        assert_eq!(short_id, ShortId::from_u64(slow));
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn short_ids() {
+        let keys = ShortIdKeys::from_hash(&sha256::Hash::hash(b"keys"));
+        let txids = [sha256d::Hash::hash(b"a"), sha256d::Hash::hash(b"b")];
+        let short_ids = keys.short_ids(txids.iter());
+        assert_eq!(short_ids, [keys.short_id(&txids[0]), keys.short_id(&txids[1])]);
+        assert_ne!(short_ids[0], short_ids[1]);
+    }
+}
@@ -0,0 +1,251 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! SipHash 1-3 implementation.
+//!
+//! SipHash-1-3 is the variant used by the standard library's `DefaultHasher`. It shares its
+//! compression function with [`siphash24`].
+//!
+
+use core::str;
+use core::ops::Index;
+use core::slice::SliceIndex;
+
+use crate::{Error, Hash as _, HashEngine as _, hex, siphash24};
+use crate::siphash24::{State, Sip13Rounds};
+
+crate::internal_macros::hash_type! {
+    64,
+    false,
+    "Output of the SipHash13 hash function.",
+    "crate::util::json_hex_string::len_8"
+}
+
+fn from_engine(e: HashEngine) -> Hash {
+    Hash::from_u64(Hash::from_engine_to_u64(e))
+}
+
+/// Engine to compute the SipHash13 hash function.
+#[derive(Debug, Clone)]
+pub struct HashEngine(siphash24::HashEngine);
+
+crate::internal_macros::engine_zeroize_impl!(wrapper HashEngine);
+
+impl HashEngine {
+    /// Creates a new SipHash13 engine with keys.
+    pub fn with_keys(k0: u64, k1: u64) -> HashEngine {
+        HashEngine(siphash24::HashEngine::with_keys(k0, k1))
+    }
+
+    /// Creates a new SipHash13 engine.
+    pub fn new() -> HashEngine {
+        HashEngine::with_keys(0, 0)
+    }
+
+    /// Retrieves the keys of this engine.
+    pub fn keys(&self) -> (u64, u64) {
+        self.0.keys()
+    }
+}
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine::new()
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = State;
+
+    fn midstate(&self) -> State {
+        self.0.midstate()
+    }
+
+    const BLOCK_SIZE: usize = 8;
+
+    #[inline]
+    fn input(&mut self, msg: &[u8]) {
+        self.0.input_rounds::<Sip13Rounds>(msg)
+    }
+
+    fn n_bytes_hashed(&self) -> usize {
+        self.0.n_bytes_hashed()
+    }
+}
+
+impl Hash {
+    /// Hashes the given data with an engine with the provided keys.
+    pub fn hash_with_keys(k0: u64, k1: u64, data: &[u8]) -> Hash {
+        let mut engine = HashEngine::with_keys(k0, k1);
+        engine.input(data);
+        Hash::from_engine(engine)
+    }
+
+    /// Hashes the given data directly to u64 with an engine with the provided keys.
+    pub fn hash_to_u64_with_keys(k0: u64, k1: u64, data: &[u8]) -> u64 {
+        let mut engine = HashEngine::with_keys(k0, k1);
+        engine.input(data);
+        Hash::from_engine_to_u64(engine)
+    }
+
+    /// Produces a hash as `u64` from the current state of a given engine.
+    #[inline]
+    pub fn from_engine_to_u64(e: HashEngine) -> u64 {
+        let state = e.0.finalize_rounds::<Sip13Rounds>(0xff);
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+
+    /// Returns the (little endian) 64-bit integer representation of the hash value.
+    pub fn as_u64(&self) -> u64 {
+        u64::from_le_bytes(self.0)
+    }
+
+    /// Creates a hash from its (little endian) 64-bit integer representation.
+    pub fn from_u64(hash: u64) -> Hash {
+        Hash(hash.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_siphash_1_3() {
+        let vecs: [[u8; 8]; 64] = [
+            [0xdc, 0xc4, 0x0f, 0x05, 0x58, 0x01, 0xac, 0xab],
+            [0x93, 0xca, 0x57, 0x7d, 0xf3, 0x9b, 0xf4, 0xc9],
+            [0x4d, 0xd4, 0xc7, 0x4d, 0x02, 0x9b, 0xcb, 0x82],
+            [0xfb, 0xf7, 0xdd, 0xe7, 0xb8, 0x0a, 0xf8, 0x8b],
+            [0x28, 0x83, 0xd3, 0x88, 0x60, 0x57, 0x75, 0xcf],
+            [0x67, 0x3b, 0x53, 0x49, 0x2f, 0xd5, 0xf9, 0xde],
+            [0xa7, 0x22, 0x9f, 0xc5, 0x50, 0x2b, 0x0d, 0xc5],
+            [0x40, 0x11, 0xb1, 0x9b, 0x98, 0x7d, 0x92, 0xd3],
+            [0x8e, 0x9a, 0x29, 0x8d, 0x11, 0x95, 0x90, 0x36],
+            [0xe4, 0x3d, 0x06, 0x6c, 0xb3, 0x8e, 0xa4, 0x25],
+            [0x7f, 0x09, 0xff, 0x92, 0xee, 0x85, 0xde, 0x79],
+            [0x52, 0xc3, 0x4d, 0xf9, 0xc1, 0x18, 0xc1, 0x70],
+            [0xa2, 0xd9, 0xb4, 0x57, 0xb1, 0x84, 0xa3, 0x78],
+            [0xa7, 0xff, 0x29, 0x12, 0x0c, 0x76, 0x6f, 0x30],
+            [0x34, 0x5d, 0xf9, 0xc0, 0x11, 0xa1, 0x5a, 0x60],
+            [0x56, 0x99, 0x51, 0x2a, 0x6d, 0xd8, 0x20, 0xd3],
+            [0x66, 0x8b, 0x90, 0x7d, 0x1a, 0xdd, 0x4f, 0xcc],
+            [0x0c, 0xd8, 0xdb, 0x63, 0x90, 0x68, 0xf2, 0x9c],
+            [0x3e, 0xe6, 0x73, 0xb4, 0x9c, 0x38, 0xfc, 0x8f],
+            [0x1c, 0x7d, 0x29, 0x8d, 0xe5, 0x9d, 0x1f, 0xf2],
+            [0x40, 0xe0, 0xcc, 0xa6, 0x46, 0x2f, 0xdc, 0xc0],
+            [0x44, 0xf8, 0x45, 0x2b, 0xfe, 0xab, 0x92, 0xb9],
+            [0x2e, 0x87, 0x20, 0xa3, 0x9b, 0x7b, 0xfe, 0x7f],
+            [0x23, 0xc1, 0xe6, 0xda, 0x7f, 0x0e, 0x5a, 0x52],
+            [0x8c, 0x9c, 0x34, 0x67, 0xb2, 0xae, 0x64, 0xf4],
+            [0x79, 0x09, 0x5b, 0x70, 0x28, 0x59, 0xcd, 0x45],
+            [0xa5, 0x13, 0x99, 0xca, 0xe3, 0x35, 0x3e, 0x3a],
+            [0x35, 0x3b, 0xde, 0x4a, 0x4e, 0xc7, 0x1d, 0xa9],
+            [0x0d, 0xd0, 0x6c, 0xef, 0x02, 0xed, 0x0b, 0xfb],
+            [0xf4, 0xe1, 0xb1, 0x4a, 0xb4, 0x3c, 0xd9, 0x88],
+            [0x63, 0xe6, 0xc5, 0x43, 0xd6, 0x11, 0x0f, 0x54],
+            [0xbc, 0xd1, 0x21, 0x8c, 0x1f, 0xdd, 0x70, 0x23],
+            [0x0d, 0xb6, 0xa7, 0x16, 0x6c, 0x7b, 0x15, 0x81],
+            [0xbf, 0xf9, 0x8f, 0x7a, 0xe5, 0xb9, 0x54, 0x4d],
+            [0x3e, 0x75, 0x2a, 0x1f, 0x78, 0x12, 0x9f, 0x75],
+            [0x91, 0x6b, 0x18, 0xbf, 0xbe, 0xa3, 0xa1, 0xce],
+            [0x06, 0x62, 0xa2, 0xad, 0xd3, 0x08, 0xf5, 0x2c],
+            [0x57, 0x30, 0xc3, 0xa3, 0x2d, 0x1c, 0x10, 0xb6],
+            [0xa1, 0x36, 0x3a, 0xae, 0x96, 0x74, 0xf4, 0xb3],
+            [0x92, 0x83, 0x10, 0x7b, 0x54, 0x57, 0x6b, 0x62],
+            [0x31, 0x15, 0xe4, 0x99, 0x32, 0x36, 0xd2, 0xc1],
+            [0x44, 0xd9, 0x1a, 0x3f, 0x92, 0xc1, 0x7c, 0x66],
+            [0x25, 0x88, 0x13, 0xc8, 0xfe, 0x4f, 0x70, 0x65],
+            [0xa6, 0x49, 0x89, 0xc2, 0xd1, 0x80, 0xf2, 0x24],
+            [0x6b, 0x87, 0xf8, 0xfa, 0xed, 0x1c, 0xca, 0xc2],
+            [0x96, 0x21, 0x04, 0x9f, 0xfc, 0x4b, 0x16, 0xc2],
+            [0x23, 0xd6, 0xb1, 0x68, 0x93, 0x9c, 0x6e, 0xa1],
+            [0xfd, 0x14, 0x51, 0x8b, 0x9c, 0x16, 0xfb, 0x49],
+            [0x46, 0x4c, 0x07, 0xdf, 0xf8, 0x43, 0x31, 0x9f],
+            [0xb3, 0x86, 0xcc, 0x12, 0x24, 0xaf, 0xfd, 0xc6],
+            [0x8f, 0x09, 0x52, 0x0a, 0xd1, 0x49, 0xaf, 0x7e],
+            [0x9a, 0x2f, 0x29, 0x9d, 0x55, 0x13, 0xf3, 0x1c],
+            [0x12, 0x1f, 0xf4, 0xa2, 0xdd, 0x30, 0x4a, 0xc4],
+            [0xd0, 0x1e, 0xa7, 0x43, 0x89, 0xe9, 0xfa, 0x36],
+            [0xe6, 0xbc, 0xf0, 0x73, 0x4c, 0xb3, 0x8f, 0x31],
+            [0x80, 0xe9, 0xa7, 0x70, 0x36, 0xbf, 0x7a, 0xa2],
+            [0x75, 0x6d, 0x3c, 0x24, 0xdb, 0xc0, 0xbc, 0xb4],
+            [0x13, 0x15, 0xb7, 0xfd, 0x52, 0xd8, 0xf8, 0x23],
+            [0x08, 0x8a, 0x7d, 0xa6, 0x4d, 0x5f, 0x03, 0x8f],
+            [0x48, 0xf1, 0xe8, 0xb7, 0xe5, 0xd0, 0x9c, 0xd8],
+            [0xee, 0x44, 0xa6, 0xf7, 0xbc, 0xe6, 0xf4, 0xf6],
+            [0xf2, 0x37, 0x18, 0x0f, 0xd8, 0x9a, 0xc5, 0xae],
+            [0xe0, 0x94, 0x66, 0x4b, 0x15, 0xf6, 0xb2, 0xc3],
+            [0xa8, 0xb3, 0xbb, 0xb7, 0x62, 0x90, 0x19, 0x9d],
+        ];
+
+        let k0 = 0x_07_06_05_04_03_02_01_00;
+        let k1 = 0x_0f_0e_0d_0c_0b_0a_09_08;
+        let mut vin = [0u8; 64];
+        let mut state_inc = HashEngine::with_keys(k0, k1);
+
+        for i in 0..64 {
+            vin[i] = i as u8;
+            let vec = Hash::from_slice(&vecs[i][..]).unwrap();
+            let out = Hash::hash_with_keys(k0, k1, &vin[0..i]);
+            assert_eq!(vec, out, "vec #{}", i);
+
+            let inc = Hash::from_engine(state_inc.clone());
+            assert_eq!(vec, inc, "vec #{}", i);
+            state_inc.input(&[i as u8]);
+        }
+        assert_eq!(state_inc.keys(), (k0, k1));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_default_hasher() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let data = b"The quick brown fox jumps over the lazy dog";
+        for i in 0..data.len() {
+            let mut hasher = DefaultHasher::new();
+            hasher.write(&data[..i]);
+            assert_eq!(Hash::hash_to_u64_with_keys(0, 0, &data[..i]), hasher.finish());
+        }
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{Hash, HashEngine, siphash13};
+
+    #[bench]
+    pub fn siphash13_1ki(bh: &mut Bencher) {
+        let mut engine = siphash13::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter(|| {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn siphash13_64ki(bh: &mut Bencher) {
+        let mut engine = siphash13::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter(|| {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
@@ -19,6 +19,9 @@
 
 //! SipHash 2-4 implementation.
 //!
+//! The compression core in this module is shared with the [`siphash13`](crate::siphash13) and
+//! [`siphash24_128`](crate::siphash24_128) variants.
+//!
 
 use core::{cmp, mem, ptr, str};
 use core::ops::Index;
@@ -92,10 +95,66 @@ pub struct State {
     // and simd implementations of SipHash will use vectors
     // of v02 and v13. By placing them in this order in the struct,
     // the compiler can pick up on just a few simd optimizations by itself.
-    v0: u64,
-    v2: u64,
-    v1: u64,
-    v3: u64,
+    pub(crate) v0: u64,
+    pub(crate) v2: u64,
+    pub(crate) v1: u64,
+    pub(crate) v3: u64,
+}
+
+crate::internal_macros::zeroize_fields_impl!(State, v0, v2, v1, v3);
+
+impl State {
+    /// Creates the initial state for the given keys.
+    fn with_keys(k0: u64, k1: u64) -> State {
+        State {
+            v0: k0 ^ 0x736f6d6570736575,
+            v1: k1 ^ 0x646f72616e646f6d,
+            v2: k0 ^ 0x6c7967656e657261,
+            v3: k1 ^ 0x7465646279746573,
+        }
+    }
+}
+
+/// Numbers of compression and finalization rounds of a SipHash-c-d variant.
+pub(crate) trait Rounds {
+    fn c_rounds(state: &mut State);
+    fn d_rounds(state: &mut State);
+}
+
+/// Rounds of SipHash-1-3.
+pub(crate) struct Sip13Rounds;
+
+impl Rounds for Sip13Rounds {
+    #[inline]
+    fn c_rounds(state: &mut State) {
+        compress!(state);
+    }
+
+    #[inline]
+    fn d_rounds(state: &mut State) {
+        compress!(state);
+        compress!(state);
+        compress!(state);
+    }
+}
+
+/// Rounds of SipHash-2-4.
+pub(crate) struct Sip24Rounds;
+
+impl Rounds for Sip24Rounds {
+    #[inline]
+    fn c_rounds(state: &mut State) {
+        compress!(state);
+        compress!(state);
+    }
+
+    #[inline]
+    fn d_rounds(state: &mut State) {
+        compress!(state);
+        compress!(state);
+        compress!(state);
+        compress!(state);
+    }
 }
 
 /// Engine to compute the SipHash24 hash function.
@@ -109,6 +168,8 @@ pub struct HashEngine {
     ntail: usize,  // how many bytes in tail are valid
 }
 
+crate::internal_macros::engine_zeroize_impl!(HashEngine, k0, k1, length, state, tail, ntail);
+
 impl HashEngine {
     /// Creates a new SipHash24 engine with keys.
     pub fn with_keys(k0: u64, k1: u64) -> HashEngine {
@@ -116,17 +177,19 @@ impl HashEngine {
             k0,
             k1,
             length: 0,
-            state: State {
-                v0: k0 ^ 0x736f6d6570736575,
-                v1: k1 ^ 0x646f72616e646f6d,
-                v2: k0 ^ 0x6c7967656e657261,
-                v3: k1 ^ 0x7465646279746573,
-            },
+            state: State::with_keys(k0, k1),
             tail: 0,
             ntail: 0,
         }
     }
 
+    /// Creates an engine with keys for a variant with 128-bit output.
+    pub(crate) fn with_keys_128(k0: u64, k1: u64) -> HashEngine {
+        let mut engine = HashEngine::with_keys(k0, k1);
+        engine.state.v1 ^= 0xee;
+        engine
+    }
+
     /// Creates a new SipHash24 engine.
     pub fn new() -> HashEngine {
         HashEngine::with_keys(0, 0)
@@ -137,38 +200,9 @@ impl HashEngine {
         (self.k0, self.k1)
     }
 
+    /// Absorbs `msg` using the compression rounds of `R`.
     #[inline]
-    fn c_rounds(state: &mut State) {
-        compress!(state);
-        compress!(state);
-    }
-
-    #[inline]
-    fn d_rounds(state: &mut State) {
-        compress!(state);
-        compress!(state);
-        compress!(state);
-        compress!(state);
-    }
-}
-
-impl Default for HashEngine {
-    fn default() -> Self {
-        HashEngine::new()
-    }
-}
-
-impl crate::HashEngine for HashEngine {
-    type MidState = State;
-
-    fn midstate(&self) -> State {
-        self.state.clone()
-    }
-
-    const BLOCK_SIZE: usize = 8;
-
-    #[inline]
-    fn input(&mut self, msg: &[u8]) {
+    pub(crate) fn input_rounds<R: Rounds>(&mut self, msg: &[u8]) {
         let length = msg.len();
         self.length += length;
 
@@ -182,7 +216,7 @@ impl crate::HashEngine for HashEngine {
                 return;
             } else {
                 self.state.v3 ^= self.tail;
-                HashEngine::c_rounds(&mut self.state);
+                R::c_rounds(&mut self.state);
                 self.state.v0 ^= self.tail;
                 self.ntail = 0;
             }
@@ -197,7 +231,7 @@ impl crate::HashEngine for HashEngine {
             let mi = unsafe { load_int_le!(msg, i, u64) };
 
             self.state.v3 ^= mi;
-            HashEngine::c_rounds(&mut self.state);
+            R::c_rounds(&mut self.state);
             self.state.v0 ^= mi;
 
             i += 8;
@@ -207,6 +241,44 @@ impl crate::HashEngine for HashEngine {
         self.ntail = left;
     }
 
+    /// Absorbs the final block and runs the finalization rounds of `R`, with `fin` as the
+    /// finalization constant. Returns the state from which the output is extracted.
+    #[inline]
+    pub(crate) fn finalize_rounds<R: Rounds>(self, fin: u64) -> State {
+        let mut state = self.state.clone();
+
+        let b: u64 = ((self.length as u64 & 0xff) << 56) | self.tail;
+
+        state.v3 ^= b;
+        R::c_rounds(&mut state);
+        state.v0 ^= b;
+
+        state.v2 ^= fin;
+        R::d_rounds(&mut state);
+        state
+    }
+}
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine::new()
+    }
+}
+
+impl crate::HashEngine for HashEngine {
+    type MidState = State;
+
+    fn midstate(&self) -> State {
+        self.state.clone()
+    }
+
+    const BLOCK_SIZE: usize = 8;
+
+    #[inline]
+    fn input(&mut self, msg: &[u8]) {
+        self.input_rounds::<Sip24Rounds>(msg)
+    }
+
     fn n_bytes_hashed(&self) -> usize {
         self.length
     }
@@ -228,20 +300,36 @@ impl Hash {
         Hash::from_engine_to_u64(engine)
     }
 
-    /// Produces a hash as `u64` from the current state of a given engine.
+    /// Hashes 32 bytes directly to u64 with the provided keys.
+    ///
+    /// This gives the same result as [`Hash::hash_to_u64_with_keys`], but skips the buffering of
+    /// the engine, which makes hashing many 32-byte hashes such as txids faster.
     #[inline]
-    pub fn from_engine_to_u64(e: HashEngine) -> u64 {
-        let mut state = e.state;
-
-        let b: u64 = ((e.length as u64 & 0xff) << 56) | e.tail;
+    pub fn hash_uint256_to_u64_with_keys(k0: u64, k1: u64, data: &[u8; 32]) -> u64 {
+        let mut state = State::with_keys(k0, k1);
+        for chunk in data.chunks(8) {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            let m = u64::from_le_bytes(word);
+            state.v3 ^= m;
+            Sip24Rounds::c_rounds(&mut state);
+            state.v0 ^= m;
+        }
 
+        let b = 32u64 << 56;
         state.v3 ^= b;
-        HashEngine::c_rounds(&mut state);
+        Sip24Rounds::c_rounds(&mut state);
         state.v0 ^= b;
 
         state.v2 ^= 0xff;
-        HashEngine::d_rounds(&mut state);
+        Sip24Rounds::d_rounds(&mut state);
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
 
+    /// Produces a hash as `u64` from the current state of a given engine.
+    #[inline]
+    pub fn from_engine_to_u64(e: HashEngine) -> u64 {
+        let state = e.finalize_rounds::<Sip24Rounds>(0xff);
         state.v0 ^ state.v1 ^ state.v2 ^ state.v3
     }
 
@@ -368,6 +456,24 @@ mod tests {
             assert_eq!(vec, inc, "vec #{}", i);
             state_inc.input(&[i as u8]);
         }
+
+        let mut uint256 = [0u8; 32];
+        uint256.copy_from_slice(&vin[0..32]);
+        let out = Hash::hash_uint256_to_u64_with_keys(k0, k1, &uint256);
+        assert_eq!(Hash::from_u64(out), Hash::from_slice(&vecs[32][..]).unwrap());
+    }
+
+    #[test]
+    fn test_uint256_fast_path() {
+        let mut data = [0u8; 32];
+        for i in 0..32u64 {
+            data[i as usize] = 0xa5 ^ i as u8;
+            let (k0, k1) = (i.wrapping_mul(0x9e3779b97f4a7c15), !i);
+            assert_eq!(
+                Hash::hash_uint256_to_u64_with_keys(k0, k1, &data),
+                Hash::hash_to_u64_with_keys(k0, k1, &data),
+            );
+        }
     }
 }
 
@@ -418,4 +524,14 @@ mod benches {
         });
         bh.bytes = bytes.len() as u64;
     }
+    #[bench]
+    pub fn siphash24_uint256_hash_u64(bh: &mut Bencher) {
+        let k0 = 0x_07_06_05_04_03_02_01_00;
+        let k1 = 0x_0f_0e_0d_0c_0b_0a_09_08;
+        let bytes = [1u8; 32];
+        bh.iter(|| {
+            let _ = siphash24::Hash::hash_uint256_to_u64_with_keys(k0, k1, &bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
 }
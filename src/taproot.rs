@@ -0,0 +1,754 @@
+// Bitcoin Hashes Library
+// Written in 2022 by
+//   The rust-bitcoin developers.
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BIP341 Taproot script trees.
+//!
+//! Hashing of script leaves and branches with the `TapLeaf` and `TapBranch` tagged hashes,
+//! building script trees and computing and verifying the merkle paths found in control blocks.
+//! Scripts are handled as plain bytes. Tweaking the internal key needs elliptic curve operations
+//! which are not provided here, [`TapTweakHash`] only computes the tweak.
+//!
+
+use core::fmt;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::cmp::Reverse;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::alloc::{collections::BinaryHeap, vec::Vec};
+
+use crate::{Hash, HashEngine};
+
+/// The leaf version of BIP342 tapscript.
+pub const LEAF_VERSION_TAPSCRIPT: u8 = 0xc0;
+
+/// Size of a control block without a merkle path, in bytes.
+pub const CONTROL_BLOCK_BASE_SIZE: usize = 33;
+
+/// Size of a node hash of a merkle path in a control block, in bytes.
+pub const CONTROL_BLOCK_NODE_SIZE: usize = 32;
+
+/// Maximum depth of a script tree, the maximum number of nodes in a merkle path.
+pub const MAX_TREE_DEPTH: usize = 128;
+
+// Midstates of SHA256 after hashing the tags twice, as done by BIP340 tagged hashes.
+const MIDSTATE_TAPLEAF: [u8; 32] = [
+    156, 224, 228, 230, 124, 17, 108, 57, 56, 179, 202, 242, 195, 15, 80, 137, 211, 243, 147, 108,
+    71, 99, 110, 96, 125, 179, 62, 234, 221, 198, 240, 201,
+];
+const MIDSTATE_TAPBRANCH: [u8; 32] = [
+    35, 168, 101, 169, 184, 164, 13, 167, 151, 124, 30, 4, 196, 158, 36, 111, 181, 190, 19, 118,
+    157, 36, 201, 183, 181, 131, 181, 212, 168, 210, 38, 210,
+];
+const MIDSTATE_TAPTWEAK: [u8; 32] = [
+    209, 41, 162, 243, 112, 28, 101, 93, 101, 131, 182, 195, 185, 65, 151, 39, 149, 244, 226, 50,
+    148, 253, 84, 244, 162, 174, 141, 133, 71, 202, 89, 11,
+];
+
+crate::sha256t_hash_newtype!(TapLeafHash, TapLeafTag, MIDSTATE_TAPLEAF, 64,
+    doc="Taproot-tagged hash of a script leaf.", false
+);
+crate::sha256t_hash_newtype!(TapNodeHash, TapBranchTag, MIDSTATE_TAPBRANCH, 64,
+    doc="Taproot-tagged hash of a node of a script tree, either a branch or a leaf.", false
+);
+crate::sha256t_hash_newtype!(TapTweakHash, TapTweakTag, MIDSTATE_TAPTWEAK, 64,
+    doc="Taproot-tagged hash used to tweak the internal key.", false
+);
+
+impl TapLeafHash {
+    /// Computes the leaf hash of `script` with the given leaf version.
+    pub fn from_script(script: &[u8], leaf_version: u8) -> TapLeafHash {
+        let mut engine = TapLeafHash::engine();
+        engine.input(&[leaf_version]);
+        input_compact_size(&mut engine, script.len() as u64);
+        engine.input(script);
+        TapLeafHash::from_engine(engine)
+    }
+}
+
+impl From<TapLeafHash> for TapNodeHash {
+    fn from(leaf: TapLeafHash) -> TapNodeHash {
+        TapNodeHash::from_inner(leaf.into_inner())
+    }
+}
+
+impl TapNodeHash {
+    /// Computes the hash of the branch with the children `a` and `b`, which are sorted
+    /// lexicographically first so their order does not matter.
+    pub fn from_node_hashes(a: TapNodeHash, b: TapNodeHash) -> TapNodeHash {
+        let mut engine = TapNodeHash::engine();
+        if a < b {
+            engine.input(&a[..]);
+            engine.input(&b[..]);
+        } else {
+            engine.input(&b[..]);
+            engine.input(&a[..]);
+        }
+        TapNodeHash::from_engine(engine)
+    }
+
+    /// Computes the merkle root from the hash of a leaf and its merkle path, ordered from the
+    /// leaf to the root.
+    pub fn from_merkle_path<I>(leaf: TapLeafHash, path: I) -> TapNodeHash
+    where
+        I: IntoIterator<Item = TapNodeHash>,
+    {
+        path.into_iter().fold(TapNodeHash::from(leaf), TapNodeHash::from_node_hashes)
+    }
+}
+
+impl TapTweakHash {
+    /// Computes the tweak of the x-only `internal_key` committing to the script tree with
+    /// `merkle_root`, or to no script tree.
+    pub fn from_key_and_merkle_root(internal_key: &[u8; 32], merkle_root: Option<TapNodeHash>) -> TapTweakHash {
+        let mut engine = TapTweakHash::engine();
+        engine.input(internal_key);
+        if let Some(root) = merkle_root {
+            engine.input(&root[..]);
+        }
+        TapTweakHash::from_engine(engine)
+    }
+}
+
+fn input_compact_size<E: HashEngine>(engine: &mut E, n: u64) {
+    if n < 0xfd {
+        engine.input(&[n as u8]);
+    } else if n <= 0xffff {
+        engine.input(&[0xfd]);
+        engine.input(&(n as u16).to_le_bytes());
+    } else if n <= 0xffff_ffff {
+        engine.input(&[0xfe]);
+        engine.input(&(n as u32).to_le_bytes());
+    } else {
+        engine.input(&[0xff]);
+        engine.input(&n.to_le_bytes());
+    }
+}
+
+/// Returns whether `leaf_version` is a valid leaf version, an even value other than the annex
+/// tag `0x50`.
+pub fn is_valid_leaf_version(leaf_version: u8) -> bool {
+    leaf_version & 1 == 0 && leaf_version != 0x50
+}
+
+/// Taproot error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The leaf version is odd or the annex tag.
+    InvalidLeafVersion(u8),
+    /// The depth of a node exceeds [`MAX_TREE_DEPTH`].
+    InvalidMerkleTreeDepth(usize),
+    /// The size of a control block is not [`CONTROL_BLOCK_BASE_SIZE`] plus a multiple of
+    /// [`CONTROL_BLOCK_NODE_SIZE`] or its merkle path is too long.
+    InvalidControlBlockSize(usize),
+    /// Nodes were not added to the tree builder in depth-first order.
+    NodeNotInDfsOrder,
+    /// A node was added to the tree builder after the tree was complete.
+    OverCompleteTree,
+    /// The tree builder was finalized before the tree was complete.
+    IncompleteTree,
+    /// The tree does not contain any nodes.
+    EmptyTree,
+    /// The sum of the weights of the leaves overflowed.
+    WeightOverflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidLeafVersion(version) => write!(f, "invalid leaf version {:#04x}", version),
+            Error::InvalidMerkleTreeDepth(depth) => write!(f, "merkle tree depth {} exceeds the maximum of {}", depth, MAX_TREE_DEPTH),
+            Error::InvalidControlBlockSize(size) => write!(f, "invalid control block size {}", size),
+            Error::NodeNotInDfsOrder => f.write_str("nodes must be added in depth-first order"),
+            Error::OverCompleteTree => f.write_str("node added to a complete tree"),
+            Error::IncompleteTree => f.write_str("the tree is incomplete"),
+            Error::EmptyTree => f.write_str("the tree is empty"),
+            Error::WeightOverflow => f.write_str("the sum of the leaf weights overflowed"),
+        }
+    }
+}
+
+/// A control block, as found in the witness of a script path spend.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ControlBlock<'a> {
+    leaf_version: u8,
+    output_key_parity: bool,
+    internal_key: [u8; 32],
+    merkle_path: &'a [u8],
+}
+
+impl<'a> ControlBlock<'a> {
+    /// Parses a control block.
+    pub fn decode(bytes: &'a [u8]) -> Result<ControlBlock<'a>, Error> {
+        if bytes.len() < CONTROL_BLOCK_BASE_SIZE
+            || (bytes.len() - CONTROL_BLOCK_BASE_SIZE) % CONTROL_BLOCK_NODE_SIZE != 0
+            || (bytes.len() - CONTROL_BLOCK_BASE_SIZE) / CONTROL_BLOCK_NODE_SIZE > MAX_TREE_DEPTH
+        {
+            return Err(Error::InvalidControlBlockSize(bytes.len()));
+        }
+        let leaf_version = bytes[0] & 0xfe;
+        if !is_valid_leaf_version(leaf_version) {
+            return Err(Error::InvalidLeafVersion(leaf_version));
+        }
+        let mut internal_key = [0; 32];
+        internal_key.copy_from_slice(&bytes[1..CONTROL_BLOCK_BASE_SIZE]);
+        Ok(ControlBlock {
+            leaf_version,
+            output_key_parity: bytes[0] & 1 == 1,
+            internal_key,
+            merkle_path: &bytes[CONTROL_BLOCK_BASE_SIZE..],
+        })
+    }
+
+    /// Returns the leaf version of the spent script.
+    pub fn leaf_version(&self) -> u8 {
+        self.leaf_version
+    }
+
+    /// Returns whether the y coordinate of the output key is odd.
+    pub fn output_key_parity(&self) -> bool {
+        self.output_key_parity
+    }
+
+    /// Returns the x-only internal key.
+    pub fn internal_key(&self) -> &[u8; 32] {
+        &self.internal_key
+    }
+
+    /// Returns the node hashes of the merkle path, ordered from the leaf to the root.
+    pub fn merkle_path(&self) -> impl Iterator<Item = TapNodeHash> + 'a {
+        self.merkle_path
+            .chunks(CONTROL_BLOCK_NODE_SIZE)
+            .map(|node| TapNodeHash::from_slice(node).expect("chunks have the node size"))
+    }
+
+    /// Computes the merkle root committed to by this control block when spending `script`.
+    pub fn merkle_root(&self, script: &[u8]) -> TapNodeHash {
+        let leaf = TapLeafHash::from_script(script, self.leaf_version);
+        TapNodeHash::from_merkle_path(leaf, self.merkle_path())
+    }
+
+    /// Returns whether spending `script` with this control block commits to `merkle_root`.
+    ///
+    /// Checking that the output key is the internal key tweaked with this root is left to the
+    /// caller.
+    pub fn verify_merkle_root(&self, script: &[u8], merkle_root: &TapNodeHash) -> bool {
+        self.merkle_root(script) == *merkle_root
+    }
+}
+
+/// A script leaf of a script tree, with its merkle path.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScriptLeaf {
+    version: u8,
+    script: Vec<u8>,
+    merkle_path: Vec<TapNodeHash>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl ScriptLeaf {
+    /// Returns the leaf version.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the script.
+    pub fn script(&self) -> &[u8] {
+        &self.script
+    }
+
+    /// Returns the leaf hash.
+    pub fn leaf_hash(&self) -> TapLeafHash {
+        TapLeafHash::from_script(&self.script, self.version)
+    }
+
+    /// Returns the node hashes of the merkle path, ordered from the leaf to the root.
+    pub fn merkle_path(&self) -> &[TapNodeHash] {
+        &self.merkle_path
+    }
+
+    /// Serializes the control block for spending this leaf, given the x-only internal key and
+    /// the parity of the tweaked output key.
+    pub fn control_block(&self, internal_key: &[u8; 32], output_key_parity: bool) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(CONTROL_BLOCK_BASE_SIZE + self.merkle_path.len() * CONTROL_BLOCK_NODE_SIZE);
+        ret.push(self.version | output_key_parity as u8);
+        ret.extend_from_slice(internal_key);
+        for node in &self.merkle_path {
+            ret.extend_from_slice(&node[..]);
+        }
+        ret
+    }
+}
+
+/// A node of a script tree, with the script leaves below it.
+///
+/// The leaves of hidden nodes, of which only the hash is known, are not included.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeInfo {
+    hash: TapNodeHash,
+    leaves: Vec<ScriptLeaf>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl NodeInfo {
+    /// Creates a node of a single script leaf.
+    pub fn new_leaf(script: Vec<u8>, version: u8) -> Result<NodeInfo, Error> {
+        if !is_valid_leaf_version(version) {
+            return Err(Error::InvalidLeafVersion(version));
+        }
+        let leaf = ScriptLeaf { version, script, merkle_path: Vec::new() };
+        Ok(NodeInfo { hash: leaf.leaf_hash().into(), leaves: crate::alloc::vec![leaf] })
+    }
+
+    /// Creates a hidden node, of which only the hash is known.
+    pub fn new_hidden(hash: TapNodeHash) -> NodeInfo {
+        NodeInfo { hash, leaves: Vec::new() }
+    }
+
+    /// Combines two nodes into a branch, extending the merkle paths of their leaves.
+    pub fn combine(a: NodeInfo, b: NodeInfo) -> Result<NodeInfo, Error> {
+        let (a_hash, b_hash) = (a.hash, b.hash);
+        let mut leaves = Vec::with_capacity(a.leaves.len() + b.leaves.len());
+        for (mut leaf, sibling) in a.leaves.into_iter().map(|leaf| (leaf, b_hash))
+            .chain(b.leaves.into_iter().map(|leaf| (leaf, a_hash)))
+        {
+            if leaf.merkle_path.len() >= MAX_TREE_DEPTH {
+                return Err(Error::InvalidMerkleTreeDepth(leaf.merkle_path.len() + 1));
+            }
+            leaf.merkle_path.push(sibling);
+            leaves.push(leaf);
+        }
+        Ok(NodeInfo { hash: TapNodeHash::from_node_hashes(a_hash, b_hash), leaves })
+    }
+
+    /// Builds a tree from weighted script leaves, given as `(weight, script, leaf version)`,
+    /// using Huffman coding so that likelier leaves have shorter merkle paths.
+    pub fn huffman<I>(leaves: I) -> Result<NodeInfo, Error>
+    where
+        I: IntoIterator<Item = (u32, Vec<u8>, u8)>,
+    {
+        // Nodes are taken out of `nodes` when combined, ties in weight are broken by taking the
+        // node added first.
+        let mut nodes = Vec::new();
+        let mut heap = BinaryHeap::new();
+        for (weight, script, version) in leaves {
+            heap.push(Reverse((weight as u64, nodes.len())));
+            nodes.push(Some(NodeInfo::new_leaf(script, version)?));
+        }
+
+        while heap.len() > 1 {
+            let Reverse((weight_a, a)) = heap.pop().expect("heap has more than one node");
+            let Reverse((weight_b, b)) = heap.pop().expect("heap has more than one node");
+            let a = nodes[a].take().expect("nodes are only combined once");
+            let b = nodes[b].take().expect("nodes are only combined once");
+            let weight = weight_a.checked_add(weight_b).ok_or(Error::WeightOverflow)?;
+            heap.push(Reverse((weight, nodes.len())));
+            nodes.push(Some(NodeInfo::combine(a, b)?));
+        }
+
+        match heap.pop() {
+            Some(Reverse((_, root))) => Ok(nodes[root].take().expect("root was not combined")),
+            None => Err(Error::EmptyTree),
+        }
+    }
+
+    /// Returns the hash of the node, the merkle root if this is the root of a tree.
+    pub fn hash(&self) -> TapNodeHash {
+        self.hash
+    }
+
+    /// Returns the script leaves below this node in depth-first order.
+    pub fn leaves(&self) -> &[ScriptLeaf] {
+        &self.leaves
+    }
+}
+
+/// Builds a script tree from nodes added in depth-first order with their depth.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "std", feature = "alloc"))))]
+#[derive(Debug, Clone, Default)]
+pub struct TreeBuilder {
+    // Incomplete subtrees on the path to the last added node, indexed by depth.
+    branch: Vec<Option<NodeInfo>>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl TreeBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> TreeBuilder {
+        TreeBuilder::default()
+    }
+
+    /// Adds a script leaf at `depth`, where the root is at depth zero.
+    pub fn add_leaf(self, depth: usize, script: Vec<u8>, version: u8) -> Result<TreeBuilder, Error> {
+        let leaf = NodeInfo::new_leaf(script, version)?;
+        self.insert(leaf, depth)
+    }
+
+    /// Adds a hidden node at `depth`, of which only the hash is known.
+    pub fn add_hidden(self, depth: usize, hash: TapNodeHash) -> Result<TreeBuilder, Error> {
+        self.insert(NodeInfo::new_hidden(hash), depth)
+    }
+
+    /// Returns whether all added nodes form a complete tree.
+    pub fn is_complete(&self) -> bool {
+        self.branch.len() == 1 && self.branch[0].is_some()
+    }
+
+    /// Returns the root of the tree.
+    pub fn finalize(mut self) -> Result<NodeInfo, Error> {
+        match self.branch.len() {
+            0 => Err(Error::EmptyTree),
+            1 => self.branch.pop().and_then(|root| root).ok_or(Error::IncompleteTree),
+            _ => Err(Error::IncompleteTree),
+        }
+    }
+
+    fn insert(mut self, mut node: NodeInfo, mut depth: usize) -> Result<TreeBuilder, Error> {
+        if depth > MAX_TREE_DEPTH {
+            return Err(Error::InvalidMerkleTreeDepth(depth));
+        }
+        // A node can not be added above an incomplete subtree.
+        if depth + 1 < self.branch.len() {
+            return Err(Error::NodeNotInDfsOrder);
+        }
+        // Combine the node with its left siblings for as long as these are complete.
+        while self.branch.len() == depth + 1 {
+            let sibling = match self.branch.pop() {
+                Some(Some(sibling)) => sibling,
+                Some(None) => {
+                    self.branch.push(None);
+                    break;
+                }
+                None => unreachable!("the branch is not empty"),
+            };
+            if depth == 0 {
+                return Err(Error::OverCompleteTree);
+            }
+            node = NodeInfo::combine(sibling, node)?;
+            depth -= 1;
+        }
+        while self.branch.len() < depth + 1 {
+            self.branch.push(None);
+        }
+        self.branch[depth] = Some(node);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha256;
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    use crate::hex::FromHex;
+
+    #[test]
+    fn midstates() {
+        fn tagged_engine(tag: &[u8]) -> sha256::HashEngine {
+            let tag_hash = sha256::Hash::hash(tag);
+            let mut engine = sha256::Hash::engine();
+            engine.input(&tag_hash[..]);
+            engine.input(&tag_hash[..]);
+            engine
+        }
+
+        assert_eq!(tagged_engine(b"TapLeaf").midstate(), TapLeafHash::engine().midstate());
+        assert_eq!(tagged_engine(b"TapBranch").midstate(), TapNodeHash::engine().midstate());
+        assert_eq!(tagged_engine(b"TapTweak").midstate(), TapTweakHash::engine().midstate());
+    }
+
+    // Test vectors from BIP341 wallet-test-vectors.json.
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn bip341_script_trees() {
+        struct Leaf {
+            depth: usize,
+            version: u8,
+            script: &'static str,
+            leaf_hash: &'static str,
+            control_block: &'static str,
+        }
+
+        struct Test {
+            internal_key: &'static str,
+            leaves: &'static [Leaf],
+            merkle_root: Option<&'static str>,
+            tweak: &'static str,
+        }
+
+        let tests = [
+            Test {
+                internal_key: "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+                leaves: &[],
+                merkle_root: None,
+                tweak: "b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70",
+            },
+            Test {
+                internal_key: "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+                leaves: &[
+                    Leaf {
+                        depth: 0,
+                        version: 0xc0,
+                        script: "20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac",
+                        leaf_hash: "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21",
+                        control_block: "c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+                    },
+                ],
+                merkle_root: Some("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"),
+                tweak: "cbd8679ba636c1110ea247542cfbd964131a6be84f873f7f3b62a777528ed001",
+            },
+            Test {
+                internal_key: "93478e9488f956df2396be2ce6c5cced75f900dfa18e7dabd2428aae78451820",
+                leaves: &[
+                    Leaf {
+                        depth: 0,
+                        version: 0xc0,
+                        script: "20b617298552a72ade070667e86ca63b8f5789a9fe8731ef91202a91c9f3459007ac",
+                        leaf_hash: "c525714a7f49c28aedbbba78c005931a81c234b2f6c99a73e4d06082adc8bf2b",
+                        control_block: "c093478e9488f956df2396be2ce6c5cced75f900dfa18e7dabd2428aae78451820",
+                    },
+                ],
+                merkle_root: Some("c525714a7f49c28aedbbba78c005931a81c234b2f6c99a73e4d06082adc8bf2b"),
+                tweak: "6af9e28dbf9d6aaf027696e2598a5b3d056f5fd2355a7fd5a37a0e5008132d30",
+            },
+            Test {
+                internal_key: "ee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf3786592",
+                leaves: &[
+                    Leaf {
+                        depth: 1,
+                        version: 0xc0,
+                        script: "20387671353e273264c495656e27e39ba899ea8fee3bb69fb2a680e22093447d48ac",
+                        leaf_hash: "8ad69ec7cf41c2a4001fd1f738bf1e505ce2277acdcaa63fe4765192497f47a7",
+                        control_block: "c0ee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf3786592\
+                            f224a923cd0021ab202ab139cc56802ddb92dcfc172b9212261a539df79a112a",
+                    },
+                    Leaf {
+                        depth: 1,
+                        version: 0xfa,
+                        script: "06424950333431",
+                        leaf_hash: "f224a923cd0021ab202ab139cc56802ddb92dcfc172b9212261a539df79a112a",
+                        control_block: "faee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf3786592\
+                            8ad69ec7cf41c2a4001fd1f738bf1e505ce2277acdcaa63fe4765192497f47a7",
+                    },
+                ],
+                merkle_root: Some("6c2dc106ab816b73f9d07e3cd1ef2c8c1256f519748e0813e4edd2405d277bef"),
+                tweak: "9e0517edc8259bb3359255400b23ca9507f2a91cd1e4250ba068b4eafceba4a9",
+            },
+            Test {
+                internal_key: "f9f400803e683727b14f463836e1e78e1c64417638aa066919291a225f0e8dd8",
+                leaves: &[
+                    Leaf {
+                        depth: 1,
+                        version: 0xc0,
+                        script: "2044b178d64c32c4a05cc4f4d1407268f764c940d20ce97abfd44db5c3592b72fdac",
+                        leaf_hash: "64512fecdb5afa04f98839b50e6f0cb7b1e539bf6f205f67934083cdcc3c8d89",
+                        control_block: "c1f9f400803e683727b14f463836e1e78e1c64417638aa066919291a225f0e8dd8\
+                            2cb2b90daa543b544161530c925f285b06196940d6085ca9474d41dc3822c5cb",
+                    },
+                    Leaf {
+                        depth: 1,
+                        version: 0xc0,
+                        script: "07546170726f6f74",
+                        leaf_hash: "2cb2b90daa543b544161530c925f285b06196940d6085ca9474d41dc3822c5cb",
+                        control_block: "c1f9f400803e683727b14f463836e1e78e1c64417638aa066919291a225f0e8dd8\
+                            64512fecdb5afa04f98839b50e6f0cb7b1e539bf6f205f67934083cdcc3c8d89",
+                    },
+                ],
+                merkle_root: Some("ab179431c28d3b68fb798957faf5497d69c883c6fb1e1cd9f81483d87bac90cc"),
+                tweak: "639f0281b7ac49e742cd25b7f188657626da1ad169209078e2761cefd91fd65e",
+            },
+            Test {
+                internal_key: "e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6f",
+                leaves: &[
+                    Leaf {
+                        depth: 1,
+                        version: 0xc0,
+                        script: "2072ea6adcf1d371dea8fba1035a09f3d24ed5a059799bae114084130ee5898e69ac",
+                        leaf_hash: "2645a02e0aac1fe69d69755733a9b7621b694bb5b5cde2bbfc94066ed62b9817",
+                        control_block: "c0e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6f\
+                            ffe578e9ea769027e4f5a3de40732f75a88a6353a09d767ddeb66accef85e553",
+                    },
+                    Leaf {
+                        depth: 2,
+                        version: 0xc0,
+                        script: "202352d137f2f3ab38d1eaa976758873377fa5ebb817372c71e2c542313d4abda8ac",
+                        leaf_hash: "ba982a91d4fc552163cb1c0da03676102d5b7a014304c01f0c77b2b8e888de1c",
+                        control_block: "c0e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6f\
+                            9e31407bffa15fefbf5090b149d53959ecdf3f62b1246780238c24501d5ceaf6\
+                            2645a02e0aac1fe69d69755733a9b7621b694bb5b5cde2bbfc94066ed62b9817",
+                    },
+                    Leaf {
+                        depth: 2,
+                        version: 0xc0,
+                        script: "207337c0dd4253cb86f2c43a2351aadd82cccb12a172cd120452b9bb8324f2186aac",
+                        leaf_hash: "9e31407bffa15fefbf5090b149d53959ecdf3f62b1246780238c24501d5ceaf6",
+                        control_block: "c0e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6f\
+                            ba982a91d4fc552163cb1c0da03676102d5b7a014304c01f0c77b2b8e888de1c\
+                            2645a02e0aac1fe69d69755733a9b7621b694bb5b5cde2bbfc94066ed62b9817",
+                    },
+                ],
+                merkle_root: Some("ccbd66c6f7e8fdab47b3a486f59d28262be857f30d4773f2d5ea47f7761ce0e2"),
+                tweak: "b57bfa183d28eeb6ad688ddaabb265b4a41fbf68e5fed2c72c74de70d5a786f4",
+            },
+            Test {
+                internal_key: "55adf4e8967fbd2e29f20ac896e60c3b0f1d5b0efa9d34941b5958c7b0a0312d",
+                leaves: &[
+                    Leaf {
+                        depth: 1,
+                        version: 0xc0,
+                        script: "2071981521ad9fc9036687364118fb6ccd2035b96a423c59c5430e98310a11abe2ac",
+                        leaf_hash: "f154e8e8e17c31d3462d7132589ed29353c6fafdb884c5a6e04ea938834f0d9d",
+                        control_block: "c155adf4e8967fbd2e29f20ac896e60c3b0f1d5b0efa9d34941b5958c7b0a0312d\
+                            3cd369a528b326bc9d2133cbd2ac21451acb31681a410434672c8e34fe757e91",
+                    },
+                    Leaf {
+                        depth: 2,
+                        version: 0xc0,
+                        script: "20d5094d2dbe9b76e2c245a2b89b6006888952e2faa6a149ae318d69e520617748ac",
+                        leaf_hash: "737ed1fe30bc42b8022d717b44f0d93516617af64a64753b7a06bf16b26cd711",
+                        control_block: "c155adf4e8967fbd2e29f20ac896e60c3b0f1d5b0efa9d34941b5958c7b0a0312d\
+                            d7485025fceb78b9ed667db36ed8b8dc7b1f0b307ac167fa516fe4352b9f4ef7\
+                            f154e8e8e17c31d3462d7132589ed29353c6fafdb884c5a6e04ea938834f0d9d",
+                    },
+                    Leaf {
+                        depth: 2,
+                        version: 0xc0,
+                        script: "20c440b462ad48c7a77f94cd4532d8f2119dcebbd7c9764557e62726419b08ad4cac",
+                        leaf_hash: "d7485025fceb78b9ed667db36ed8b8dc7b1f0b307ac167fa516fe4352b9f4ef7",
+                        control_block: "c155adf4e8967fbd2e29f20ac896e60c3b0f1d5b0efa9d34941b5958c7b0a0312d\
+                            737ed1fe30bc42b8022d717b44f0d93516617af64a64753b7a06bf16b26cd711\
+                            f154e8e8e17c31d3462d7132589ed29353c6fafdb884c5a6e04ea938834f0d9d",
+                    },
+                ],
+                merkle_root: Some("2f6b2c5397b6d68ca18e09a3f05161668ffe93a988582d55c6f07bd5b3329def"),
+                tweak: "6579138e7976dc13b6a92f7bfd5a2fc7684f5ea42419d43368301470f3b74ed9",
+            },
+        ];
+
+        for test in tests.iter() {
+            let internal_key = <[u8; 32]>::from_hex(test.internal_key).unwrap();
+            let merkle_root = test.merkle_root.map(|root| TapNodeHash::from_hex(root).unwrap());
+            assert_eq!(
+                TapTweakHash::from_key_and_merkle_root(&internal_key, merkle_root),
+                TapTweakHash::from_hex(test.tweak).unwrap(),
+            );
+
+            let mut builder = TreeBuilder::new();
+            for leaf in test.leaves {
+                builder = builder.add_leaf(leaf.depth, Vec::from_hex(leaf.script).unwrap(), leaf.version).unwrap();
+            }
+            let root = match merkle_root {
+                Some(root) => root,
+                None => {
+                    assert_eq!(builder.finalize(), Err(Error::EmptyTree));
+                    continue;
+                }
+            };
+            let tree = builder.finalize().unwrap();
+            assert_eq!(tree.hash(), root);
+
+            assert_eq!(tree.leaves().len(), test.leaves.len());
+            for (leaf, expected) in tree.leaves().iter().zip(test.leaves) {
+                assert_eq!(leaf.leaf_hash(), TapLeafHash::from_hex(expected.leaf_hash).unwrap());
+                assert_eq!(leaf.merkle_path().len(), expected.depth);
+
+                let control_block = Vec::from_hex(expected.control_block).unwrap();
+                let output_key_parity = control_block[0] & 1 == 1;
+                assert_eq!(leaf.control_block(&internal_key, output_key_parity), control_block);
+
+                let decoded = ControlBlock::decode(&control_block).unwrap();
+                assert_eq!(decoded.leaf_version(), leaf.version());
+                assert_eq!(decoded.output_key_parity(), output_key_parity);
+                assert_eq!(decoded.internal_key(), &internal_key);
+                assert!(decoded.merkle_path().eq(leaf.merkle_path().iter().copied()));
+                assert!(decoded.verify_merkle_root(leaf.script(), &root));
+                assert!(!decoded.verify_merkle_root(&[0x51], &root));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn huffman() {
+        let leaves = vec![(10, vec![0x51], 0xc0), (1, vec![0x52], 0xc0), (1, vec![0x53], 0xc0), (1, vec![0x54], 0xc0)];
+        let tree = NodeInfo::huffman(leaves).unwrap();
+
+        // The two lightest leaves are combined first, then the result with the remaining leaf of
+        // weight one and finally with the heaviest leaf.
+        let expected = TreeBuilder::new()
+            .add_leaf(2, vec![0x54], 0xc0).unwrap()
+            .add_leaf(3, vec![0x52], 0xc0).unwrap()
+            .add_leaf(3, vec![0x53], 0xc0).unwrap()
+            .add_leaf(1, vec![0x51], 0xc0).unwrap()
+            .finalize().unwrap();
+        assert_eq!(tree.hash(), expected.hash());
+
+        for leaf in tree.leaves() {
+            let depth = match leaf.script() {
+                [0x51] => 1,
+                [0x54] => 2,
+                _ => 3,
+            };
+            assert_eq!(leaf.merkle_path().len(), depth);
+            assert_eq!(TapNodeHash::from_merkle_path(leaf.leaf_hash(), leaf.merkle_path().iter().copied()), tree.hash());
+        }
+
+        assert_eq!(NodeInfo::huffman(vec![]), Err(Error::EmptyTree));
+        assert_eq!(NodeInfo::huffman(vec![(1, vec![], 0x51)]), Err(Error::InvalidLeafVersion(0x51)));
+        let heavy = vec![(u32::max_value(), vec![0x51], 0xc0); 3];
+        assert!(NodeInfo::huffman(heavy).is_ok());
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn tree_builder() {
+        let script = vec![0x51];
+        let leaf = |builder: TreeBuilder, depth| builder.add_leaf(depth, script.clone(), LEAF_VERSION_TAPSCRIPT);
+
+        assert_eq!(TreeBuilder::new().finalize(), Err(Error::EmptyTree));
+        assert_eq!(leaf(TreeBuilder::new(), 1).unwrap().finalize(), Err(Error::IncompleteTree));
+        assert_eq!(leaf(leaf(TreeBuilder::new(), 0).unwrap(), 0).unwrap_err(), Error::OverCompleteTree);
+        assert_eq!(leaf(leaf(TreeBuilder::new(), 2).unwrap(), 1).unwrap_err(), Error::NodeNotInDfsOrder);
+        assert_eq!(leaf(TreeBuilder::new(), 129).unwrap_err(), Error::InvalidMerkleTreeDepth(129));
+        assert_eq!(TreeBuilder::new().add_leaf(0, vec![], 0x50).unwrap_err(), Error::InvalidLeafVersion(0x50));
+
+        // A leaf next to a hidden node.
+        let hidden = TapNodeHash::from_inner([1; 32]);
+        let builder = TreeBuilder::new().add_hidden(1, hidden).unwrap();
+        assert!(!builder.is_complete());
+        let builder = leaf(builder, 1).unwrap();
+        assert!(builder.is_complete());
+        let tree = builder.finalize().unwrap();
+        assert_eq!(tree.leaves().len(), 1);
+        assert_eq!(tree.leaves()[0].merkle_path(), &[hidden]);
+        let leaf_hash = TapLeafHash::from_script(&script, LEAF_VERSION_TAPSCRIPT);
+        assert_eq!(tree.hash(), TapNodeHash::from_node_hashes(hidden, leaf_hash.into()));
+    }
+
+    #[test]
+    fn control_block_decode() {
+        let mut bytes = [0xc0; CONTROL_BLOCK_BASE_SIZE + CONTROL_BLOCK_NODE_SIZE];
+        assert!(ControlBlock::decode(&bytes).is_ok());
+        assert!(ControlBlock::decode(&bytes[..CONTROL_BLOCK_BASE_SIZE]).is_ok());
+        assert_eq!(ControlBlock::decode(&bytes[..32]), Err(Error::InvalidControlBlockSize(32)));
+        assert_eq!(ControlBlock::decode(&bytes[..34]), Err(Error::InvalidControlBlockSize(34)));
+        bytes[0] = 0x51;
+        assert_eq!(ControlBlock::decode(&bytes), Err(Error::InvalidLeafVersion(0x50)));
+    }
+}
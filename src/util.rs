@@ -49,6 +49,68 @@ macro_rules! hex_fmt_impl(
     );
 );
 
+/// Adds a [`ConstantTimeEq`](crate::cmp::ConstantTimeEq) implementation to a given type `$ty`,
+/// and a `subtle::ConstantTimeEq` implementation if the `subtle` feature is enabled.
+#[macro_export]
+macro_rules! ct_eq_impl(
+    ($ty:ident) => (
+        $crate::ct_eq_impl!($ty, );
+    );
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::cmp::ConstantTimeEq for $ty<$($gen),*> {
+            fn fixed_time_eq(&self, other: &Self) -> bool {
+                $crate::cmp::fixed_time_eq(&self[..], &other[..])
+            }
+        }
+
+        $crate::subtle_impl!($ty, $($gen: $gent),*);
+    )
+);
+
+/// Implements `subtle::ConstantTimeEq` for a given type `$ty`.
+#[macro_export]
+#[doc(hidden)]
+#[cfg(feature = "subtle")]
+macro_rules! subtle_impl(
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::subtle::ConstantTimeEq for $ty<$($gen),*> {
+            fn ct_eq(&self, other: &Self) -> $crate::subtle::Choice {
+                $crate::subtle::ConstantTimeEq::ct_eq(&self[..], &other[..])
+            }
+        }
+    )
+);
+
+/// Does an "empty" `subtle` implementation for the configuration without the `subtle` feature.
+#[macro_export]
+#[doc(hidden)]
+#[cfg(not(feature = "subtle"))]
+macro_rules! subtle_impl(
+    ($ty:ident, $($gen:ident: $gent:ident),*) => ()
+);
+
+/// Implements `zeroize::Zeroize` for a given type `$ty` whose field is a hash or byte array.
+#[macro_export]
+#[doc(hidden)]
+#[cfg(feature = "zeroize")]
+macro_rules! zeroize_impl(
+    ($ty:ident $(, $gen:ident: $gent:ident)*) => (
+        impl<$($gen: $gent),*> $crate::zeroize::Zeroize for $ty<$($gen),*> {
+            fn zeroize(&mut self) {
+                $crate::zeroize::Zeroize::zeroize(&mut self.0);
+            }
+        }
+    )
+);
+
+/// Does an "empty" `zeroize` implementation for the configuration without the `zeroize` feature.
+#[macro_export]
+#[doc(hidden)]
+#[cfg(not(feature = "zeroize"))]
+macro_rules! zeroize_impl(
+    ($ty:ident $(, $gen:ident: $gent:ident)*) => ()
+);
+
 /// Adds slicing traits implementations to a given type `$ty`
 #[macro_export]
 macro_rules! borrow_slice_impl(
@@ -110,6 +172,10 @@ macro_rules! engine_input_impl(
 
 
 /// Creates a new newtype around a [`Hash`] type.
+///
+/// With the `zeroize` feature enabled the newtype implements `zeroize::Zeroize`. Hashes are `Copy`
+/// so they can not be wiped automatically when dropped, wrap secret values in
+/// `zeroize::Zeroizing` to opt into this.
 #[macro_export]
 macro_rules! hash_newtype {
     ($newtype:ident, $hash:ty, $len:expr, $docs:meta) => {
@@ -124,6 +190,8 @@ macro_rules! hash_newtype {
         $crate::hex_fmt_impl!($newtype);
         $crate::serde_impl!($newtype, $len);
         $crate::borrow_slice_impl!($newtype);
+        $crate::ct_eq_impl!($newtype);
+        $crate::zeroize_impl!($newtype);
 
         impl $newtype {
             /// Creates this type from the inner hash type.
@@ -211,6 +279,127 @@ macro_rules! hash_newtype {
     };
 }
 
+/// Creates a new hash type whose value is the first `$len` bytes of the output of a SHAKE
+/// extendable-output function.
+///
+/// `$module` is the module of the function, `shake128` or `shake256`. The hash type has the same
+/// engine as the `Hash` type of that module, so `$len` can be chosen freely as long as
+/// [`FromHex`](crate::hex::FromHex) is implemented for `[u8; $len]`.
+///
+/// ```
+/// use bitcoin_hashes::{shake_hash_newtype, Hash};
+///
+/// shake_hash_newtype!(Shake256_256, shake256, 32, doc="SHAKE256 with a 256-bit output.");
+///
+/// let hash = Shake256_256::hash(b"abc");
+/// assert_eq!(hash.into_inner().len(), 32);
+/// ```
+#[macro_export]
+macro_rules! shake_hash_newtype {
+    ($newtype:ident, $module:ident, $len:expr, $docs:meta) => {
+        $crate::shake_hash_newtype!($newtype, $module, $len, $docs, false);
+    };
+    ($newtype:ident, $module:ident, $len:expr, $docs:meta, $reverse:expr) => {
+        #[$docs]
+        #[derive(Copy, Clone)]
+        #[repr(transparent)]
+        pub struct $newtype([u8; $len]);
+
+        $crate::hex_fmt_impl!($newtype);
+        $crate::serde_impl!($newtype, $len);
+        $crate::borrow_slice_impl!($newtype);
+        $crate::ct_eq_impl!($newtype);
+        $crate::zeroize_impl!($newtype);
+
+        impl $crate::_export::_core::cmp::PartialEq for $newtype {
+            fn eq(&self, other: &$newtype) -> bool {
+                self.0[..] == other.0[..]
+            }
+        }
+
+        impl $crate::_export::_core::cmp::Eq for $newtype {}
+
+        impl $crate::_export::_core::cmp::PartialOrd for $newtype {
+            fn partial_cmp(&self, other: &$newtype) -> Option<$crate::_export::_core::cmp::Ordering> {
+                Some($crate::_export::_core::cmp::Ord::cmp(self, other))
+            }
+        }
+
+        impl $crate::_export::_core::cmp::Ord for $newtype {
+            fn cmp(&self, other: &$newtype) -> $crate::_export::_core::cmp::Ordering {
+                self.0[..].cmp(&other.0[..])
+            }
+        }
+
+        impl $crate::_export::_core::hash::Hash for $newtype {
+            fn hash<H: $crate::_export::_core::hash::Hasher>(&self, state: &mut H) {
+                $crate::_export::_core::hash::Hash::hash(&self.0[..], state)
+            }
+        }
+
+        impl $crate::Hash for $newtype {
+            type Engine = $crate::$module::HashEngine;
+            type Inner = [u8; $len];
+
+            const LEN: usize = $len;
+            const DISPLAY_BACKWARD: bool = $reverse;
+
+            fn from_engine(e: Self::Engine) -> Self {
+                let mut ret = [0; $len];
+                e.finalize_xof().squeeze(&mut ret);
+                $newtype(ret)
+            }
+
+            #[inline]
+            fn from_slice(sl: &[u8]) -> Result<$newtype, $crate::Error> {
+                if sl.len() != $len {
+                    Err($crate::Error::InvalidLength($len, sl.len()))
+                } else {
+                    let mut ret = [0; $len];
+                    ret.copy_from_slice(sl);
+                    Ok($newtype(ret))
+                }
+            }
+
+            #[inline]
+            fn from_inner(inner: Self::Inner) -> Self {
+                $newtype(inner)
+            }
+
+            #[inline]
+            fn into_inner(self) -> Self::Inner {
+                self.0
+            }
+
+            #[inline]
+            fn as_inner(&self) -> &Self::Inner {
+                &self.0
+            }
+
+            #[inline]
+            fn all_zeros() -> Self {
+                $newtype([0; $len])
+            }
+        }
+
+        impl $crate::_export::_core::str::FromStr for $newtype {
+            type Err = $crate::hex::Error;
+            fn from_str(s: &str) -> $crate::_export::_core::result::Result<$newtype, Self::Err> {
+                $crate::hex::FromHex::from_hex(s)
+            }
+        }
+
+        impl<I: $crate::_export::_core::slice::SliceIndex<[u8]>> $crate::_export::_core::ops::Index<I> for $newtype {
+            type Output = I::Output;
+
+            #[inline]
+            fn index(&self, index: I) -> &Self::Output {
+                &self.0[index]
+            }
+        }
+    };
+}
+
 #[cfg(feature = "schemars")]
 #[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
 pub mod json_hex_string {
@@ -229,9 +418,13 @@ pub mod json_hex_string {
             }
         };
     }
+    define_custom_hex!(len_4, 4);
     define_custom_hex!(len_8, 8);
+    define_custom_hex!(len_16, 16);
     define_custom_hex!(len_20, 20);
+    define_custom_hex!(len_28, 28);
     define_custom_hex!(len_32, 32);
+    define_custom_hex!(len_48, 48);
     define_custom_hex!(len_64, 64);
 }
 